@@ -0,0 +1,305 @@
+//! ABI 编解码辅助：十六进制、uint256、revert 原因解析（EVM 与 Tron 合约共用）
+
+/// Error(string) 选择器
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Panic(uint256) 选择器
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// 去掉 0x / 0X 前缀
+pub fn strip_0x(s: &str) -> &str {
+    s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s)
+}
+
+/// 十六进制字符串（可带 0x）解码为字节
+pub fn hex_to_bytes(s: &str) -> Result<Vec<u8>, String> {
+    let h = strip_0x(s.trim());
+    let padded;
+    let h = if h.len() % 2 == 1 {
+        padded = format!("0{}", h);
+        padded.as_str()
+    } else {
+        h
+    };
+    hex::decode(h).map_err(|e| format!("非法十六进制 {}: {}", s, e))
+}
+
+/// 大端字节转 u128，超出位宽返回 None
+pub fn uint_bytes_to_u128(bytes: &[u8]) -> Option<u128> {
+    let trimmed: Vec<u8> = bytes.iter().copied().skip_while(|b| *b == 0).collect();
+    if trimmed.len() > 16 {
+        return None;
+    }
+    Some(trimmed.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128))
+}
+
+/// 解析数量字段：0x 十六进制、十进制字符串或 JSON 数字
+pub fn parse_quantity(v: &serde_json::Value) -> Option<u128> {
+    match v {
+        serde_json::Value::Number(n) => n.as_u64().map(|x| x as u128),
+        serde_json::Value::String(s) => {
+            let s = s.trim();
+            if s.starts_with("0x") || s.starts_with("0X") {
+                u128::from_str_radix(strip_0x(s), 16).ok()
+            } else {
+                s.parse().ok()
+            }
+        }
+        _ => None,
+    }
+}
+
+/// 数量转 JSON-RPC 所需的 0x 十六进制
+pub fn to_hex_quantity(v: u128) -> String {
+    format!("0x{:x}", v)
+}
+
+/// 按 32 字节字（word）读取 ABI 数据，越界返回 None
+pub fn word(data: &[u8], index: usize) -> Option<&[u8]> {
+    data.get(index * 32..index * 32 + 32)
+}
+
+/// uint 编码为 32 字节 ABI word
+pub fn encode_uint(v: u128) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[16..].copy_from_slice(&v.to_be_bytes());
+    w
+}
+
+/// 20 字节地址编码为 32 字节 ABI word（左侧补零）
+pub fn encode_address(addr: &[u8; 20]) -> [u8; 32] {
+    let mut w = [0u8; 32];
+    w[12..].copy_from_slice(addr);
+    w
+}
+
+/// Tron Base58Check 地址（T 开头）解码为去掉 0x41 前缀的 20 字节地址
+pub fn tron_address_to_bytes(addr: &str) -> Result<[u8; 20], String> {
+    let raw = bs58::decode(addr.trim())
+        .with_check(None)
+        .into_vec()
+        .map_err(|e| format!("非法 Tron 地址 {}: {}", addr, e))?;
+    if raw.len() != 21 || raw[0] != 0x41 {
+        return Err(format!("非法 Tron 地址 {}: 长度或前缀不正确", addr));
+    }
+    let mut out = [0u8; 20];
+    out.copy_from_slice(&raw[1..]);
+    Ok(out)
+}
+
+/// 十六进制 Tron 地址（41 开头的 21 字节）编码为 Base58Check 地址（T 开头）
+pub fn tron_address_from_hex(hex_addr: &str) -> Result<String, String> {
+    let raw = hex_to_bytes(hex_addr)?;
+    if raw.len() != 21 || raw[0] != 0x41 {
+        return Err(format!("非法 Tron 十六进制地址 {}: 长度或前缀不正确", hex_addr));
+    }
+    Ok(bs58::encode(raw).with_check().into_string())
+}
+
+/// 最小单位按精度格式化为十进制字符串（去掉小数末尾的 0），如 (1_500_000, 6) → "1.5"
+pub fn format_units(raw: u128, decimals: u32) -> String {
+    if decimals == 0 {
+        return raw.to_string();
+    }
+    let s = format!("{:0>width$}", raw, width = decimals as usize + 1);
+    let (int_part, frac_part) = s.split_at(s.len() - decimals as usize);
+    let frac_part = frac_part.trim_end_matches('0');
+    if frac_part.is_empty() {
+        int_part.to_string()
+    } else {
+        format!("{}.{}", int_part, frac_part)
+    }
+}
+
+/// 十进制字符串按精度换算为最小单位（精确换算，不经浮点），如 ("1.5", 6) → 1_500_000；小数位超过精度时报错
+pub fn parse_units(s: &str, decimals: u32) -> Result<u128, String> {
+    let s = s.trim();
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    let is_digits = |p: &str| p.chars().all(|c| c.is_ascii_digit());
+    if (int_part.is_empty() && frac_part.is_empty()) || !is_digits(int_part) || !is_digits(frac_part) {
+        return Err(format!("{} 不是非负十进制数", s));
+    }
+    if frac_part.len() > decimals as usize {
+        return Err(format!("{} 的小数位超过精度 {}", s, decimals));
+    }
+    let digits = format!("{}{:0<width$}", int_part, frac_part, width = decimals as usize);
+    match digits.trim_start_matches('0') {
+        "" => Ok(0),
+        d => d.parse().map_err(|_| format!("{} 超出数值范围", s)),
+    }
+}
+
+/// 解析 revert 返回数据：Error(string)、Panic(uint256) 与自定义错误
+pub fn decode_revert_reason(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "无返回数据（require 无消息、调用非合约地址或 gas 不足）".to_string();
+    }
+    if data.len() < 4 {
+        return format!("无法识别的返回数据 0x{}", hex::encode(data));
+    }
+    let (selector, body) = data.split_at(4);
+    if selector == ERROR_STRING_SELECTOR {
+        if let Some(msg) = decode_abi_string(body) {
+            return format!("Error(\"{}\")", msg);
+        }
+    } else if selector == PANIC_SELECTOR {
+        if let Some(code) = word(body, 0).and_then(uint_bytes_to_u128) {
+            return format!("Panic(0x{:02x}): {}", code, panic_description(code));
+        }
+    }
+    format!(
+        "自定义错误 selector=0x{} data=0x{}",
+        hex::encode(selector),
+        hex::encode(body)
+    )
+}
+
+/// 解析 ABI 编码的单个动态 string（offset + length + bytes）
+pub fn decode_abi_string(body: &[u8]) -> Option<String> {
+    // offset 与 len 来自链上返回数据，超出 usize 或相加溢出都视为无法解码
+    let offset = usize::try_from(uint_bytes_to_u128(word(body, 0)?)?).ok()?;
+    let start = offset.checked_add(32)?;
+    let len = usize::try_from(uint_bytes_to_u128(body.get(offset..start)?)?).ok()?;
+    let bytes = body.get(start..start.checked_add(len)?)?;
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Solidity Panic 错误码说明
+fn panic_description(code: u128) -> &'static str {
+    match code {
+        0x01 => "assert 失败",
+        0x11 => "算术溢出/下溢",
+        0x12 => "除以零或对零取模",
+        0x21 => "非法枚举值转换",
+        0x22 => "存储字节数组编码错误",
+        0x31 => "对空数组 pop",
+        0x32 => "数组越界访问",
+        0x41 => "内存分配过大",
+        0x51 => "调用未初始化的函数指针",
+        _ => "未知 Panic 错误码",
+    }
+}
+
+/// Keccak-f[1600] 轮常量
+const KECCAK_RC: [u64; 24] = [
+    0x0000_0000_0000_0001, 0x0000_0000_0000_8082, 0x8000_0000_0000_808a, 0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b, 0x0000_0000_8000_0001, 0x8000_0000_8000_8081, 0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a, 0x0000_0000_0000_0088, 0x0000_0000_8000_8009, 0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b, 0x8000_0000_0000_008b, 0x8000_0000_0000_8089, 0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002, 0x8000_0000_0000_0080, 0x0000_0000_0000_800a, 0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081, 0x8000_0000_0000_8080, 0x0000_0000_8000_0001, 0x8000_0000_8000_8008,
+];
+/// ρ 步骤的循环位移量（按 π 步骤的遍历顺序）
+const KECCAK_ROTC: [u32; 24] = [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+/// π 步骤的 lane 遍历顺序
+const KECCAK_PILN: [usize; 24] = [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+/// Keccak-256（以太坊使用的原始 Keccak 填充，非 NIST SHA3-256）
+///
+/// 已签名 EVM 交易的哈希即其 RLP 编码的 Keccak-256，广播前即可确定交易哈希（SDK 只在广播后返回哈希）。
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    const RATE: usize = 136;
+    let mut padded = data.to_vec();
+    padded.push(0x01);
+    padded.resize(padded.len().div_ceil(RATE) * RATE, 0);
+    if let Some(last) = padded.last_mut() {
+        *last |= 0x80;
+    }
+
+    let mut state = [0u64; 25];
+    for block in padded.chunks(RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(bytes);
+            *lane ^= u64::from_le_bytes(word);
+        }
+        keccak_f(&mut state);
+    }
+
+    let mut out = [0u8; 32];
+    for (chunk, lane) in out.chunks_mut(8).zip(state.iter()) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+    out
+}
+
+/// Keccak-f[1600] 置换（24 轮 θ、ρ、π、χ、ι）
+fn keccak_f(a: &mut [u64; 25]) {
+    for rc in KECCAK_RC {
+        let mut c = [0u64; 5];
+        for (x, col) in c.iter_mut().enumerate() {
+            *col = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[y * 5 + x] ^= d;
+            }
+        }
+
+        let mut carry = a[1];
+        for (&j, &rot) in KECCAK_PILN.iter().zip(KECCAK_ROTC.iter()) {
+            let next = a[j];
+            a[j] = carry.rotate_left(rot);
+            carry = next;
+        }
+
+        for y in 0..5 {
+            let row = [a[y * 5], a[y * 5 + 1], a[y * 5 + 2], a[y * 5 + 3], a[y * 5 + 4]];
+            for x in 0..5 {
+                a[y * 5 + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        a[0] ^= rc;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keccak_hex(data: &[u8]) -> String {
+        hex::encode(keccak256(data))
+    }
+
+    #[test]
+    fn keccak256_known_answers() {
+        assert_eq!(keccak_hex(b""), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+        assert_eq!(keccak_hex(b"abc"), "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45");
+    }
+
+    #[test]
+    fn keccak256_rate_boundary() {
+        // 速率 136 字节：135 字节时填充只占一个字节（0x81），136 字节时填充独占一个新块
+        let cases = [
+            (135, "34367dc248bbd832f4e3e69dfaac2f92638bd0bbd18f2912ba4ef454919cf446"),
+            (136, "a6c4d403279fe3e0af03729caada8374b5ca54d8065329a3ebcaeb4b60aa386e"),
+            (137, "d869f639c7046b4929fc92a4d988a8b22c55fbadb802c0c66ebcd484f1915f39"),
+            (272, "cf7fcd4f705ee749930d19ca84561a9bf62516bd90a471545fa2f49fdc7e63c8"),
+        ];
+        for (len, expected) in cases {
+            assert_eq!(keccak_hex(&vec![b'a'; len]), expected, "{} 字节", len);
+        }
+    }
+
+    #[test]
+    fn parse_units_exact() {
+        assert_eq!(parse_units("1.5", 6), Ok(1_500_000));
+        assert_eq!(parse_units(" 42 ", 0), Ok(42));
+        assert_eq!(parse_units("0.000001", 6), Ok(1));
+        assert_eq!(parse_units("1.", 6), Ok(1_000_000));
+        assert_eq!(parse_units(".5", 6), Ok(500_000));
+        assert_eq!(parse_units("000", 18), Ok(0));
+    }
+
+    #[test]
+    fn parse_units_rejects_invalid() {
+        assert!(parse_units("1.0000001", 6).unwrap_err().contains("小数位超过精度"));
+        assert!(parse_units("1.5", 0).unwrap_err().contains("小数位超过精度"));
+        assert!(parse_units("340282366920938463463374607431768211456", 0).unwrap_err().contains("超出数值范围"));
+        assert!(parse_units("1000000000000000000000", 18).unwrap_err().contains("超出数值范围"));
+        for bad in ["", " ", ".", "-1", "1e6", "1,5", "0x10", "1.2.3"] {
+            assert!(parse_units(bad, 6).unwrap_err().contains("不是非负十进制数"), "{:?}", bad);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Arbitrum 费用拆分：经 ArbGasInfo 预编译（0x…6C）查询 L1 calldata 单价，把转账预览的 gas 上限拆为 L2 执行与 L1 数据两部分
+//!
+//! Arbitrum 的 eth_estimateGas 把 L1 数据费用按 L2 baseFee 折算成 gas 计入 gas 上限；回执中的 gasUsedForL1 即其中的 L1 部分（见 receipt）。
+//! 非 Arbitrum 链上预编译不存在（eth_call 返回空），fetch 返回 None，调用方据此跳过。
+
+use crate::abi;
+use crate::evm_fees::format_gwei;
+use crate::evm_rpc::EvmRpc;
+use serde_json::{json, Value};
+
+/// ArbGasInfo 预编译地址
+const ARB_GAS_INFO: &str = "0x000000000000000000000000000000000000006C";
+/// getPricesInWei()
+const GET_PRICES_IN_WEI: &str = "0x41b247a8";
+/// getL1BaseFeeEstimate()
+const GET_L1_BASE_FEE_ESTIMATE: &str = "0xf5d6ded7";
+/// 签名交易中 data 以外的字段（nonce、费用、gas、to、value、签名等）按此字节数估算
+const TX_OVERHEAD_BYTES: u128 = 140;
+
+/// ArbGasInfo 当前价格（wei）
+pub struct ArbPrices {
+    /// 每笔交易的固定 L1 费用
+    pub per_l2_tx: u128,
+    /// 每字节 L1 calldata 费用
+    pub per_l1_calldata_byte: u128,
+    /// 每单位 L2 gas 的价格（当前 L2 baseFee + 拥堵费）
+    pub per_arb_gas_total: u128,
+    /// L1 baseFee 估计
+    pub l1_base_fee: Option<u128>,
+}
+
+impl ArbPrices {
+    /// 查询 ArbGasInfo；非 Arbitrum 链或查询失败时返回 None
+    pub async fn fetch(rpc: &EvmRpc) -> Option<Self> {
+        let prices = call(rpc, GET_PRICES_IN_WEI).await?;
+        let at = |i: usize| abi::word(&prices, i).and_then(abi::uint_bytes_to_u128);
+        let l1_base_fee = call(rpc, GET_L1_BASE_FEE_ESTIMATE)
+            .await
+            .and_then(|d| abi::word(&d, 0).and_then(abi::uint_bytes_to_u128));
+        Some(Self {
+            per_l2_tx: at(0)?,
+            per_l1_calldata_byte: at(1)?,
+            per_arb_gas_total: at(5).filter(|p| *p > 0)?,
+            l1_base_fee,
+        })
+    }
+
+    /// L1 数据费用估计（wei）与折算的 L2 gas
+    pub fn l1_component(&self, tx_bytes: u128) -> (u128, u128) {
+        let fee = self
+            .per_l2_tx
+            .saturating_add(self.per_l1_calldata_byte.saturating_mul(tx_bytes));
+        (fee, fee.div_ceil(self.per_arb_gas_total))
+    }
+
+    /// eth-fees 附加输出
+    pub fn print(&self) {
+        println!("\n[Arbitrum L1 数据费用（ArbGasInfo）]");
+        println!("  L2 gas 单价: {} gwei", format_gwei(self.per_arb_gas_total));
+        println!("  L1 calldata: {} gwei/字节，每笔固定 {} gwei", format_gwei(self.per_l1_calldata_byte), format_gwei(self.per_l2_tx));
+        if let Some(fee) = self.l1_base_fee {
+            println!("  L1 baseFee 估计: {} gwei", format_gwei(fee));
+        }
+        let (fee, gas) = self.l1_component(TX_OVERHEAD_BYTES);
+        println!("  原生转账的 L1 部分约 {} gas ≈ {} ETH", gas, abi::format_units(fee, 18));
+    }
+}
+
+async fn call(rpc: &EvmRpc, selector: &str) -> Option<Vec<u8>> {
+    rpc.call(&json!({ "to": ARB_GAS_INFO, "data": selector }), "latest")
+        .await
+        .ok()
+        .filter(|d| !d.is_empty())
+}
+
+/// 转账预览：在 Arbitrum 上把 gas 上限拆为 L2 执行与 L1 数据（按当前价格估算），其他链不输出
+pub async fn print_fee_preview(rpc: &EvmRpc, tx_json: &str) {
+    let prices = match ArbPrices::fetch(rpc).await {
+        Some(p) => p,
+        None => return,
+    };
+    let tx: Value = match serde_json::from_str(tx_json) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    let data_len = tx
+        .get("data")
+        .or_else(|| tx.get("input"))
+        .and_then(|d| d.as_str())
+        .and_then(|d| abi::hex_to_bytes(d).ok())
+        .map(|d| d.len() as u128)
+        .unwrap_or(0);
+    let tx_bytes = data_len + TX_OVERHEAD_BYTES;
+    let (l1_fee, l1_gas) = prices.l1_component(tx_bytes);
+    let price = prices.per_arb_gas_total;
+
+    println!("   Arbitrum 费用拆分（ArbGasInfo，L2 gas 单价 {} gwei）:", format_gwei(price));
+    if let Some(gas) = tx.get("gas").or_else(|| tx.get("gasLimit")).and_then(abi::parse_quantity) {
+        let l2_gas = gas.saturating_sub(l1_gas);
+        println!(
+            "     L2 执行: 约 {} gas ≈ {} ETH",
+            l2_gas,
+            abi::format_units(l2_gas.saturating_mul(price), 18)
+        );
+    }
+    println!(
+        "     L1 数据: 约 {} gas ≈ {} ETH（约 {} 字节 × {} gwei/字节，gas 上限已含此部分）",
+        l1_gas,
+        abi::format_units(l1_fee, 18),
+        tx_bytes,
+        format_gwei(prices.per_l1_calldata_byte)
+    );
+}
@@ -0,0 +1,380 @@
+//! 批量交易监听：`monitor-batch` 同时跟踪大量交易哈希（如一批出款）
+//!
+//! 哈希来自命令参数、MONITOR_HASH_FILE 文件或标准输入（参数 `-`）。所有交易共用一个轮询循环：
+//! 每轮先查询一次链头（EVM 最新区块 / Tron 最新固化区块），再以有限并发查询各笔未完结交易，
+//! 状态变化即时输出，结束时打印按状态分类的汇总表。
+
+use crate::abi;
+use crate::config;
+use crate::evm_rpc::EvmRpc;
+use crate::rpc::RpcError;
+use crate::tron_monitor;
+use crate::tron_rpc::TronApi;
+use std::collections::HashSet;
+use std::env;
+use std::io::BufRead;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// 同时进行的 RPC 查询数默认值
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// 单笔交易的状态
+#[derive(Clone, Copy, PartialEq)]
+enum BatchStatus {
+    /// 未查到（未上链、已被丢弃或哈希不属于当前网络）
+    Pending,
+    /// 已上链，EVM 未达到确认数 / Tron 未固化
+    Included { block: u64 },
+    /// 已最终确认且执行成功
+    Confirmed { block: u64 },
+    /// 已最终确认但执行失败
+    Failed { block: u64 },
+}
+
+impl BatchStatus {
+    fn is_final(&self) -> bool {
+        matches!(self, BatchStatus::Confirmed { .. } | BatchStatus::Failed { .. })
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            BatchStatus::Pending => "未上链",
+            BatchStatus::Included { .. } => "已上链",
+            BatchStatus::Confirmed { .. } => "成功",
+            BatchStatus::Failed { .. } => "失败",
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            BatchStatus::Pending => self.label().to_string(),
+            BatchStatus::Included { block } | BatchStatus::Confirmed { block } | BatchStatus::Failed { block } => {
+                format!("{}（区块 {}）", self.label(), block)
+            }
+        }
+    }
+}
+
+/// 单次查询得到的上链位置；未上链为 None
+pub struct Inclusion {
+    pub block: u64,
+    /// 区块哈希（用于发现 EVM 重组；Tron 回执不含区块哈希，为空）
+    pub block_hash: String,
+    pub success: bool,
+    /// Tron：区块号与执行结果来自固化节点（walletsolidity），所在区块不可逆；EVM 恒为 false
+    pub solidified: bool,
+}
+
+/// 按链区分的查询后端（batch-send 的确认阶段共用）
+#[derive(Clone)]
+pub enum Backend {
+    Evm(EvmRpc),
+    Tron(TronApi),
+}
+
+impl Backend {
+    /// 链头：EVM 为最新区块号，Tron 为最新固化区块号
+    pub async fn head(&self) -> Result<u64, RpcError> {
+        match self {
+            Backend::Evm(rpc) => rpc.block_number().await,
+            Backend::Tron(api) => Ok(api.solidified_block_number().await?.max(0) as u64),
+        }
+    }
+
+    /// 交易上链位置；未上链返回 None
+    pub async fn inclusion(&self, hash: &str) -> Result<Option<Inclusion>, RpcError> {
+        match self {
+            Backend::Evm(rpc) => {
+                let receipt = rpc.get_transaction_receipt(hash).await?;
+                if receipt.is_null() {
+                    return Ok(None);
+                }
+                Ok(Some(Inclusion {
+                    block: receipt.get("blockNumber").and_then(abi::parse_quantity).unwrap_or(0) as u64,
+                    block_hash: receipt
+                        .get("blockHash")
+                        .and_then(|h| h.as_str())
+                        .unwrap_or("")
+                        .to_lowercase(),
+                    success: receipt.get("status").and_then(abi::parse_quantity) == Some(1),
+                    solidified: false,
+                }))
+            }
+            Backend::Tron(api) => {
+                // 先查固化节点：查到即以其区块与执行结果为准；否则按未固化节点的结果显示为已上链
+                let mut info = api.solidified_transaction_info(hash).await?;
+                let solidified = info.get("blockNumber").is_some();
+                if !solidified {
+                    info = api.get_transaction_info_by_id(hash).await?;
+                }
+                let block = match info.get("blockNumber").and_then(|b| b.as_i64()) {
+                    Some(b) => b.max(0) as u64,
+                    None => return Ok(None),
+                };
+                Ok(Some(Inclusion {
+                    block,
+                    block_hash: String::new(),
+                    success: tron_monitor::execution_result(&info).1,
+                    solidified,
+                }))
+            }
+        }
+    }
+
+    /// 回执所在区块是否仍是规范链上该高度的区块（最终确认前复核，避免把已被重组掉的区块记为最终）。
+    /// Tron 以固化节点查到该交易为准：未固化节点上的区块高度已固化而固化节点查不到时，原区块已被分叉丢弃
+    pub async fn is_canonical(&self, inclusion: &Inclusion) -> Result<bool, RpcError> {
+        match self {
+            Backend::Evm(rpc) => {
+                let block = rpc.get_block_by_number(inclusion.block, false).await?;
+                let hash = block.get("hash").and_then(|h| h.as_str()).unwrap_or("").to_lowercase();
+                Ok(!hash.is_empty() && hash == inclusion.block_hash)
+            }
+            Backend::Tron(_) => Ok(inclusion.solidified),
+        }
+    }
+}
+
+/// 单笔交易的跟踪记录
+struct Entry {
+    hash: String,
+    status: BatchStatus,
+    block_hash: String,
+}
+
+impl Entry {
+    /// 按本轮查询结果更新状态；状态变化或发生重组时即时输出。
+    /// `depth` 为最终确认所需区块数（EVM 为确认数，Tron 固定 1：所在区块 ≤ 最新固化区块）；
+    /// `canonical` 为达到确认数时复核所在区块的结果，未通过则保持已上链，下轮重新查询
+    fn update(&mut self, inclusion: Option<Inclusion>, canonical: bool, head: u64, depth: u64, started: Instant) {
+        let elapsed = started.elapsed().as_secs();
+        let next = match inclusion {
+            None => {
+                if let BatchStatus::Included { block } = self.status {
+                    println!("[{:>4}s] ⚠ {} 区块重组：已不在区块 {} 中，等待重新打包", elapsed, self.hash, block);
+                }
+                self.block_hash.clear();
+                BatchStatus::Pending
+            }
+            Some(inc) => {
+                if !self.block_hash.is_empty() && !inc.block_hash.is_empty() && self.block_hash != inc.block_hash {
+                    println!(
+                        "[{:>4}s] ⚠ {} 区块重组：重新打包进区块 {}（{}）",
+                        elapsed, self.hash, inc.block, inc.block_hash
+                    );
+                }
+                if head + 1 >= inc.block + depth && !canonical {
+                    println!(
+                        "[{:>4}s] ⚠ {} 区块 {} 已不在规范链上，暂不确认，等待重新查询",
+                        elapsed, self.hash, inc.block
+                    );
+                }
+                self.block_hash = inc.block_hash;
+                if head + 1 >= inc.block + depth && canonical {
+                    if inc.success {
+                        BatchStatus::Confirmed { block: inc.block }
+                    } else {
+                        BatchStatus::Failed { block: inc.block }
+                    }
+                } else {
+                    BatchStatus::Included { block: inc.block }
+                }
+            }
+        };
+        if next != self.status {
+            println!("[{:>4}s] {}  {} → {}", elapsed, self.hash, self.status.label(), next.describe());
+            self.status = next;
+        }
+    }
+}
+
+/// 批量监听交易直至全部最终确认或超时
+///
+/// 哈希来源（可组合，自动去重）：
+/// - 命令参数：`monitor-batch <hash> <hash> ...`
+/// - 参数 `-`：从标准输入读取，每行一个（空行与 # 开头的行忽略）
+/// - MONITOR_HASH_FILE：文件路径，格式同标准输入
+///
+/// 0x 开头的哈希按 EVM（EVM_NETWORK）监听，否则按 Tron（TRON_NETWORK）监听，不可混用。
+///
+/// 其他环境变量：
+/// - MONITOR_TIMEOUT_SEC（可选）：整批超时秒数，默认 600
+/// - MONITOR_POLL_MS（可选）：轮询间隔毫秒，默认 3000
+/// - MONITOR_CONCURRENCY（可选）：同时进行的 RPC 查询数，默认 8
+/// - MONITOR_MIN_CONFIRMATIONS（可选，EVM）：最少确认数，默认取网络的 finality_depth，未配置时为 1；Tron 以固化区块为准
+pub async fn run_monitor_batch(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let hashes = collect_hashes(args)?;
+    if hashes.is_empty() {
+        eprintln!("请通过参数、标准输入（参数 -）或 MONITOR_HASH_FILE 提供要监听的交易哈希。");
+        eprintln!("示例: cargo run -- monitor-batch 0xabc... 0xdef...");
+        eprintln!("      cat payouts.txt | cargo run -- monitor-batch -");
+        eprintln!("      export MONITOR_HASH_FILE=payouts.txt && cargo run -- monitor-batch");
+        return Ok(());
+    }
+    let is_evm = hashes.iter().all(|h| h.starts_with("0x") || h.starts_with("0X"));
+    if !is_evm && hashes.iter().any(|h| h.starts_with("0x") || h.starts_with("0X")) {
+        return Err("不能混用 EVM 与 Tron 交易哈希，请分开运行".into());
+    }
+
+    let timeout_sec: u64 = env_number("MONITOR_TIMEOUT_SEC")?.unwrap_or(600);
+    let poll_ms: u64 = env_number("MONITOR_POLL_MS")?.unwrap_or(3000);
+    let concurrency = env_number::<usize>("MONITOR_CONCURRENCY")?
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .max(1);
+
+    let (backend, depth) = if is_evm {
+        let network = config::current_evm_network()?;
+        println!("=== 批量监听 EVM 交易（{}）===", network.name());
+        let min_confirmations = env_number("MONITOR_MIN_CONFIRMATIONS")?
+            .unwrap_or(network.finality_depth.unwrap_or(1))
+            .max(1);
+        println!("最终确认: {} 个确认", min_confirmations);
+        (Backend::Evm(EvmRpc::new(config::evm_rpc_url(&network).await)), min_confirmations)
+    } else {
+        let network = config::current_tron_network();
+        println!("=== 批量监听 Tron 交易（{}）===", network.name());
+        println!("最终确认: 所在区块已固化");
+        (Backend::Tron(TronApi::new(config::tron_api_url(network))), 1)
+    };
+    println!("交易数: {}，并发: {}，轮询: {}ms，超时: {}s", hashes.len(), concurrency, poll_ms, timeout_sec);
+    println!();
+
+    let mut entries: Vec<Entry> = hashes
+        .into_iter()
+        .map(|hash| Entry { hash, status: BatchStatus::Pending, block_hash: String::new() })
+        .collect();
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(timeout_sec);
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+
+    loop {
+        match backend.head().await {
+            Ok(head) => poll_round(&backend, &mut entries, head, depth, &semaphore, started).await,
+            Err(e) => println!("⚠ 查询链头失败，稍后重试: {}", e),
+        }
+        if entries.iter().all(|e| e.status.is_final()) || Instant::now() >= deadline {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+    }
+
+    print_summary(&entries, started.elapsed().as_secs());
+    Ok(())
+}
+
+/// 一轮查询：所有未完结交易并发查询（受信号量限制），按完成顺序更新并输出
+async fn poll_round(
+    backend: &Backend,
+    entries: &mut [Entry],
+    head: u64,
+    depth: u64,
+    semaphore: &Arc<Semaphore>,
+    started: Instant,
+) {
+    let mut tasks = JoinSet::new();
+    for (i, entry) in entries.iter().enumerate().filter(|(_, e)| !e.status.is_final()) {
+        let (backend, hash, semaphore) = (backend.clone(), entry.hash.clone(), semaphore.clone());
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            (i, query(&backend, &hash, head, depth).await)
+        });
+    }
+
+    let (mut failures, mut first_error) = (0usize, None);
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((i, Ok((inclusion, canonical)))) => entries[i].update(inclusion, canonical, head, depth, started),
+            Ok((_, Err(e))) => {
+                failures += 1;
+                first_error.get_or_insert_with(|| e.to_string());
+            }
+            Err(e) => {
+                failures += 1;
+                first_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+    }
+    if let Some(e) = first_error {
+        println!("⚠ 本轮 {} 笔查询失败，下轮重试（首个错误: {}）", failures, e);
+    }
+}
+
+/// 查询单笔交易的上链位置；达到确认数时再复核所在区块仍在规范链上（未达到时复核结果无意义，记为 false）
+async fn query(backend: &Backend, hash: &str, head: u64, depth: u64) -> Result<(Option<Inclusion>, bool), RpcError> {
+    let inclusion = backend.inclusion(hash).await?;
+    let canonical = match &inclusion {
+        Some(inc) if head + 1 >= inc.block + depth => backend.is_canonical(inc).await?,
+        _ => false,
+    };
+    Ok((inclusion, canonical))
+}
+
+/// 汇总：按状态计数，并列出未成功的交易便于跟进
+fn print_summary(entries: &[Entry], elapsed_sec: u64) {
+    println!();
+    println!("=== 汇总（{} 笔，用时 {}s）===", entries.len(), elapsed_sec);
+    println!("{:<8} {:>6}", "状态", "数量");
+    for (label, note) in [
+        ("成功", ""),
+        ("失败", ""),
+        ("已上链", "  （超时时未达到最终确认）"),
+        ("未上链", "  （超时时仍未查到）"),
+    ] {
+        let count = entries.iter().filter(|e| e.status.label() == label).count();
+        println!("{:<8} {:>6}{}", label, count, if count > 0 { note } else { "" });
+    }
+
+    let unresolved: Vec<&Entry> = entries
+        .iter()
+        .filter(|e| !matches!(e.status, BatchStatus::Confirmed { .. }))
+        .collect();
+    if !unresolved.is_empty() {
+        println!();
+        println!("[需跟进]");
+        for e in unresolved {
+            println!("  {}  {}", e.hash, e.status.describe());
+        }
+    }
+}
+
+/// 合并参数、标准输入与 MONITOR_HASH_FILE 中的哈希，保持顺序去重
+fn collect_hashes(args: &[String]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut raw: Vec<String> = Vec::new();
+    for arg in args {
+        if arg == "-" {
+            for line in std::io::stdin().lock().lines() {
+                raw.push(line?);
+            }
+        } else {
+            raw.push(arg.clone());
+        }
+    }
+    if let Ok(path) = env::var("MONITOR_HASH_FILE") {
+        let content = std::fs::read_to_string(&path).map_err(|e| format!("读取 MONITOR_HASH_FILE（{}）失败: {}", path, e))?;
+        raw.extend(content.lines().map(|l| l.to_string()));
+    }
+
+    let mut seen = HashSet::new();
+    Ok(raw
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| l.to_string())
+        .filter(|h| seen.insert(h.to_lowercase()))
+        .collect())
+}
+
+/// 读取可选的数字环境变量；格式错误时报错
+pub fn env_number<T: std::str::FromStr>(key: &str) -> Result<Option<T>, String> {
+    match env::var(key) {
+        Ok(v) => v
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("{} 应为非负整数，当前: {}", key, v)),
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,1377 @@
+//! 批量出款：`batch-send` 读取 CSV（recipient, amount, token, memo），逐行校验并预览合计与费用后批量签名广播
+//!
+//! 不加 `--send` 时只校验与预览；加 `--send` 才签名广播。每行状态写入结果 CSV（默认 `<输入文件名>.results.csv`），
+//! 每次状态变化后整体重写。交易在广播前即以「已签名」状态记下交易哈希与签名数据（EVM 哈希由 Keccak-256 计算，
+//! Tron 为 txID），中断后重新运行同一命令会先核对这些交易的链上状态：已广播的只监听不重发，
+//! 只有确认不会再上链的行才会重新构建发送。
+//!
+//! - EVM：按行顺序逐笔分配 nonce（见 nonce_manager）并依次广播，某笔广播失败即停止后续发送，避免 nonce 空缺卡住整批
+//! - Tron：每批 N 行并发构建、签名、广播（交易约 60 秒过期，不预先签好整批）
+//! - 确认阶段与 monitor-batch 相同：所有已广播交易共用一个轮询循环，直至最终确认或超时
+
+use crate::abi;
+use crate::batch_monitor::{self, Backend};
+use crate::config::{self, EvmNetworkConfig};
+use crate::evm_chain::{self, signer_address, ChainIdentity};
+use crate::evm_fees::{self, FeeSettings};
+use crate::evm_rpc::{CallOutcome, EvmRpc};
+use crate::ledger::{self, TxInfo};
+use crate::nonce_manager;
+use crate::rpc::RpcError;
+use crate::sdk::{EvmSdk, TronSdk};
+use crate::trc20::{self, FeeLimitMode, Trc20Estimate};
+use crate::tron_rpc::TronApi;
+use chains_sdk::chain::evm::{ethereum_address_from_private_key, sign_ethereum_transaction};
+use chains_sdk::chain::tron::sign_tron_transaction;
+use chains_sdk::rpc::chains::tron::TronNetwork;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// 同时进行的模拟 / Tron 发送 / 确认查询数默认值
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// 结果 CSV 表头
+const RESULT_HEADER: &str = "row,recipient,amount,token,memo,status,tx_hash,nonce,error,signed_tx";
+
+/// 单行的发送状态（结果 CSV 的 status 列）
+#[derive(Clone, Copy, PartialEq)]
+enum RowStatus {
+    /// 尚未发送
+    Pending,
+    /// 已签名并记下哈希，广播结果未知（发送中断）
+    Signed,
+    /// 已广播，等待最终确认
+    Sent,
+    /// 已最终确认且执行成功
+    Confirmed,
+    /// 已最终确认但执行失败
+    Failed,
+    /// 广播被节点拒绝，下次运行时重新发送
+    Rejected,
+    /// 无法确认是否已发出（如 nonce 已被其他交易占用），不再自动处理，需人工核对
+    Unknown,
+}
+
+impl RowStatus {
+    fn key(&self) -> &'static str {
+        match self {
+            RowStatus::Pending => "pending",
+            RowStatus::Signed => "signed",
+            RowStatus::Sent => "sent",
+            RowStatus::Confirmed => "confirmed",
+            RowStatus::Failed => "failed",
+            RowStatus::Rejected => "rejected",
+            RowStatus::Unknown => "unknown",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [
+            RowStatus::Pending,
+            RowStatus::Signed,
+            RowStatus::Sent,
+            RowStatus::Confirmed,
+            RowStatus::Failed,
+            RowStatus::Rejected,
+            RowStatus::Unknown,
+        ]
+        .into_iter()
+        .find(|st| st.key() == s.trim())
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            RowStatus::Pending => "未发送",
+            RowStatus::Signed => "已签名",
+            RowStatus::Sent => "已广播",
+            RowStatus::Confirmed => "成功",
+            RowStatus::Failed => "失败",
+            RowStatus::Rejected => "广播被拒",
+            RowStatus::Unknown => "待核对",
+        }
+    }
+
+    /// 本次运行需要（重新）构建发送
+    fn needs_send(&self) -> bool {
+        matches!(self, RowStatus::Pending | RowStatus::Rejected)
+    }
+}
+
+/// 代币：原生币或合约
+struct Asset {
+    /// 合约地址；原生币为 None
+    contract: Option<String>,
+    symbol: String,
+    decimals: u32,
+}
+
+/// CSV 中的一行出款及其发送状态
+struct Payout {
+    /// 输入文件中的行号（结果 CSV 以此与输入对应）
+    line: usize,
+    recipient: String,
+    amount: String,
+    token: String,
+    memo: String,
+    /// 解析后的代币（assets 下标）
+    asset: Option<usize>,
+    /// 金额（最小单位）
+    raw: u128,
+    /// 校验与模拟发现的问题
+    issues: Vec<String>,
+    status: RowStatus,
+    tx_hash: String,
+    /// EVM 分配的 nonce（重发时沿用，保证同一行最多一笔上链）
+    nonce: Option<u128>,
+    error: String,
+    /// 已签名交易（EVM 为十六进制，Tron 为 JSON），用于中断后重新广播
+    signed_tx: String,
+    /// Tron TRC20 模拟得到的能量
+    energy: i64,
+}
+
+/// 命令参数
+struct Options {
+    path: String,
+    send: bool,
+    results: Option<String>,
+    concurrency: usize,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Option<Self>, String> {
+        let (mut path, mut send, mut results, mut concurrency) = (None, false, None, DEFAULT_CONCURRENCY);
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--send" => send = true,
+                "--results" => results = Some(iter.next().ok_or("--results 需要文件路径")?.clone()),
+                "--concurrency" => {
+                    let v = iter.next().ok_or("--concurrency 需要一个整数参数")?;
+                    concurrency = v
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| format!("--concurrency 应为正整数，当前: {}", v))?;
+                }
+                other if other.starts_with("--") => return Err(format!("未知参数 {}", other)),
+                other => path = Some(other.to_string()),
+            }
+        }
+        Ok(path.map(|path| Self { path, send, results, concurrency }))
+    }
+
+    /// 结果文件：--results，默认与输入同目录的 `<文件名>.results.csv`
+    fn results_path(&self) -> String {
+        self.results.clone().unwrap_or_else(|| {
+            let stem = self.path.strip_suffix(".csv").unwrap_or(&self.path);
+            format!("{}.results.csv", stem)
+        })
+    }
+}
+
+/// `batch-send <payouts.csv> [--send] [--results PATH] [--concurrency N]`
+///
+/// CSV 每行 `recipient,amount,token,memo`（首行为 recipient 开头的表头时跳过，空行与 # 开头的行忽略）：
+/// - recipient：0x 开头为 EVM（EVM_NETWORK），T 开头为 Tron（TRON_NETWORK），同一文件不可混用
+/// - amount：人类可读金额（如 12.5），按代币精度精确换算，小数位不得超过精度
+/// - token：空或原生币符号（ETH / TRX）为原生币，USDT 为当前网络默认稳定币，其他为合约地址
+/// - memo：只写入结果 CSV，不上链
+///
+/// 环境变量：
+/// - ETH_PRIVATE_KEY / TRON_PRIVATE_KEY：发送方私钥（发送方即私钥对应地址；设置了 ETH_FROM_ADDRESS / TRON_FROM_ADDRESS 时须一致）
+/// - EVM 费用变量同 eth-transfer（EVM_FEE_PRESET 等，见 evm_fees），nonce 记录见 nonce_manager
+/// - TRC20_FEE_LIMIT、TRC20_FEE_MARGIN_PCT：同 tron-full-flow，auto 时按每行模拟的能量计算
+/// - MONITOR_TIMEOUT_SEC、MONITOR_POLL_MS、MONITOR_MIN_CONFIRMATIONS：确认阶段参数，同 monitor-batch
+pub async fn run_batch_send(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let opts = match Options::parse(args)? {
+        Some(o) => o,
+        None => {
+            eprintln!("用法: cargo run -- batch-send <payouts.csv> [--send] [--results PATH] [--concurrency N]");
+            eprintln!("CSV 列: recipient,amount,token,memo（token 为空表示原生币，USDT 表示网络默认稳定币，或填合约地址）");
+            eprintln!("示例: cargo run -- batch-send payouts.csv            # 只校验与预览");
+            eprintln!("      cargo run -- batch-send payouts.csv --send     # 发送；中断后重新运行同一命令继续");
+            return Ok(());
+        }
+    };
+    let text = std::fs::read_to_string(&opts.path).map_err(|e| format!("读取 {} 失败: {}", opts.path, e))?;
+    let mut rows = parse_payouts(&text)?;
+    if rows.is_empty() {
+        println!("{} 中没有出款行", opts.path);
+        return Ok(());
+    }
+    let results_path = opts.results_path();
+    let restored = restore_results(&mut rows, &results_path)?;
+
+    let is_evm = rows.iter().all(|r| r.recipient.starts_with("0x") || r.recipient.starts_with("0X"));
+    if !is_evm && rows.iter().any(|r| r.recipient.starts_with("0x") || r.recipient.starts_with("0X")) {
+        return Err("不能在同一文件中混用 EVM 与 Tron 收款地址，请分开运行".into());
+    }
+    let wallet = match Wallet::from_env(is_evm).await? {
+        Some(w) => w,
+        None => return Ok(()),
+    };
+
+    println!("=== 批量出款（{}，{}）===", wallet.network_name(), if opts.send { "发送" } else { "仅校验与预览" });
+    println!("输入: {}（{} 行）", opts.path, rows.len());
+    println!("结果: {}{}", results_path, if restored > 0 { format!("（已恢复 {} 行的发送记录）", restored) } else { String::new() });
+    println!("发送方: {}", wallet.signer());
+    println!("并发: {}", opts.concurrency);
+
+    // 1. 逐行校验
+    println!("\n1. 校验收款地址、代币与金额...");
+    let assets = wallet.resolve_assets(&mut rows).await;
+    for row in rows.iter_mut() {
+        validate_row(row, &wallet, &assets);
+    }
+    let invalid = print_issues(&rows);
+    if invalid > 0 {
+        println!("❌ {} 行校验失败，请修正 CSV 后重新运行（未发送任何交易）", invalid);
+        return Ok(());
+    }
+    warn_duplicates(&rows);
+    println!("   {} 行全部通过", rows.len());
+
+    // 2. 恢复上次中断的交易：已广播的只监听，确认不会上链的才重新发送
+    if rows.iter().any(|r| matches!(r.status, RowStatus::Signed | RowStatus::Sent | RowStatus::Rejected)) {
+        if opts.send {
+            println!("\n2. 核对上次发送的交易...");
+            for i in 0..rows.len() {
+                if matches!(rows[i].status, RowStatus::Signed | RowStatus::Sent | RowStatus::Rejected) {
+                    wallet.reconcile(&mut rows[i]).await;
+                    save_results(&results_path, &rows)?;
+                }
+            }
+        } else {
+            println!("\n2. 上次发送的交易将在 --send 时先核对链上状态，未上链的才会重发");
+        }
+    }
+    print_status_counts(&rows);
+
+    // 3. 合计、模拟与费用预估
+    println!("\n3. 合计与费用预估（待发送 {} 行）...", rows.iter().filter(|r| r.status.needs_send()).count());
+    print_totals(&rows, &assets);
+    wallet.simulate(&mut rows, &assets, opts.concurrency).await;
+    let failed = print_issues(&rows);
+    if failed > 0 {
+        println!("❌ {} 行模拟执行失败或未完成，请处理后重新运行（未发送任何交易）", failed);
+        return Ok(());
+    }
+    if !wallet.check_funds(&rows, &assets).await? {
+        return Ok(());
+    }
+
+    if !opts.send {
+        println!("\n✅ 校验与预览完成。确认无误后加 --send 发送: cargo run -- batch-send {} --send", opts.path);
+        return Ok(());
+    }
+
+    // 4. 发送
+    let to_send = rows.iter().filter(|r| r.status.needs_send()).count();
+    if to_send > 0 {
+        println!("\n4. 发送 {} 行...", to_send);
+        wallet.send_all(&mut rows, &assets, &results_path, opts.concurrency).await?;
+    }
+
+    // 5. 等待最终确认
+    if rows.iter().any(|r| r.status == RowStatus::Sent) {
+        println!("\n5. 等待最终确认...");
+        monitor(&wallet, &mut rows, &results_path, opts.concurrency).await?;
+    }
+
+    print_summary(&rows, &assets, &results_path);
+    Ok(())
+}
+
+/// 解析一行 CSV：逗号分隔，字段可用双引号包裹，引号内 "" 表示一个引号
+fn parse_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let (mut fields, mut field, mut in_quotes) = (Vec::new(), String::new(), false);
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("引号未闭合（字段内不支持换行）".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// 写出 CSV 字段：含逗号、引号或换行时加引号
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// 读取出款 CSV；列数不对的行记为校验问题，由校验阶段统一报告
+fn parse_payouts(text: &str) -> Result<Vec<Payout>, String> {
+    let mut rows = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let fields = parse_csv_line(line).map_err(|e| format!("第 {} 行: {}", i + 1, e))?;
+        if rows.is_empty() && fields[0].trim().eq_ignore_ascii_case("recipient") {
+            continue;
+        }
+        let field = |n: usize| fields.get(n).map(|f| f.trim().to_string()).unwrap_or_default();
+        let mut issues = Vec::new();
+        if fields.len() < 2 || fields.len() > 4 {
+            issues.push(format!("应为 2~4 列（recipient,amount,token,memo），实际 {} 列；memo 含逗号时请加引号", fields.len()));
+        }
+        rows.push(Payout {
+            line: i + 1,
+            recipient: field(0),
+            amount: field(1),
+            token: field(2),
+            memo: field(3),
+            asset: None,
+            raw: 0,
+            issues,
+            status: RowStatus::Pending,
+            tx_hash: String::new(),
+            nonce: None,
+            error: String::new(),
+            signed_tx: String::new(),
+            energy: 0,
+        });
+    }
+    Ok(rows)
+}
+
+/// 从上次的结果 CSV 恢复各行状态，返回恢复的非 pending 行数；输入已修改（行不对应）时报错
+fn restore_results(rows: &mut [Payout], path: &str) -> Result<usize, String> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("读取结果文件 {} 失败: {}", path, e)),
+    };
+    apply_results(rows, &text)
+        .map_err(|e| format!("结果文件 {} {}；如输入 CSV 已修改，请用 --results 指定新的结果文件", path, e))
+}
+
+/// 按结果 CSV 文本（首行为表头）恢复各行状态，返回恢复的非 pending 行数
+fn apply_results(rows: &mut [Payout], text: &str) -> Result<usize, String> {
+    let mut restored = 0;
+    for (i, line) in text.lines().enumerate().skip(1) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let bad = |why: &str| format!("第 {} 行{}", i + 1, why);
+        let f = parse_csv_line(line).map_err(|e| bad(&format!("无法解析: {}", e)))?;
+        if f.len() != 10 {
+            return Err(bad("列数不正确"));
+        }
+        let status = RowStatus::parse(&f[5]).ok_or_else(|| bad(&format!("状态 {} 无法识别", f[5])))?;
+        let row = f[0]
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| rows.iter_mut().find(|r| r.line == n))
+            .filter(|r| r.recipient == f[1] && r.amount == f[2] && r.token == f[3])
+            .ok_or_else(|| bad("与输入 CSV 不对应"))?;
+        row.status = status;
+        row.tx_hash = f[6].clone();
+        row.nonce = if f[7].is_empty() { None } else { Some(f[7].parse().map_err(|_| bad("nonce 无法解析"))?) };
+        row.error = f[8].clone();
+        row.signed_tx = f[9].clone();
+        if status != RowStatus::Pending {
+            restored += 1;
+        }
+    }
+    Ok(restored)
+}
+
+/// 整体重写结果 CSV（先写临时文件再改名，中断时不会留下半个文件）
+fn save_results(path: &str, rows: &[Payout]) -> Result<(), String> {
+    let mut body = String::from(RESULT_HEADER);
+    body.push('\n');
+    for r in rows {
+        let fields = [
+            r.line.to_string(),
+            r.recipient.clone(),
+            r.amount.clone(),
+            r.token.clone(),
+            r.memo.clone(),
+            r.status.key().to_string(),
+            r.tx_hash.clone(),
+            r.nonce.map(|n| n.to_string()).unwrap_or_default(),
+            r.error.clone(),
+            r.signed_tx.clone(),
+        ];
+        body.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        body.push('\n');
+    }
+    let tmp = format!("{}.tmp", path);
+    std::fs::write(&tmp, body)
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|e| format!("写入结果文件 {} 失败: {}", path, e))
+}
+
+/// 校验收款地址与金额（代币已在 resolve_assets 中解析）
+fn validate_row(row: &mut Payout, wallet: &Wallet, assets: &[Asset]) {
+    if let Err(e) = wallet.check_address(&row.recipient) {
+        row.issues.push(e);
+    }
+    if let Some(asset) = row.asset.map(|i| &assets[i]) {
+        match abi::parse_units(&row.amount, asset.decimals) {
+            Ok(0) => row.issues.push("金额不能为 0".to_string()),
+            Ok(raw) => row.raw = raw,
+            Err(e) => row.issues.push(format!("金额 {}", e)),
+        }
+    }
+}
+
+/// 打印各行问题，返回有问题的行数
+fn print_issues(rows: &[Payout]) -> usize {
+    let bad: Vec<&Payout> = rows.iter().filter(|r| !r.issues.is_empty()).collect();
+    for r in &bad {
+        println!("   ❌ 第 {} 行（{}）: {}", r.line, r.recipient, r.issues.join("；"));
+    }
+    bad.len()
+}
+
+/// 同一收款地址、代币、金额出现多次时提示（常见的重复粘贴）
+fn warn_duplicates(rows: &[Payout]) {
+    let mut seen: BTreeMap<(String, Option<usize>, u128), usize> = BTreeMap::new();
+    for r in rows {
+        let key = (r.recipient.to_lowercase(), r.asset, r.raw);
+        match seen.get(&key) {
+            Some(first) => println!("   ⚠ 第 {} 行与第 {} 行的收款地址、代币与金额相同，请确认不是重复行", r.line, first),
+            None => {
+                seen.insert(key, r.line);
+            }
+        }
+    }
+}
+
+fn print_status_counts(rows: &[Payout]) {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for r in rows.iter().filter(|r| r.status != RowStatus::Pending) {
+        *counts.entry(r.status.label()).or_default() += 1;
+    }
+    if !counts.is_empty() {
+        let parts: Vec<String> = counts.iter().map(|(l, n)| format!("{} {}", l, n)).collect();
+        println!("   已有记录: {}", parts.join("，"));
+    }
+}
+
+/// 每种代币的笔数与合计（全部 / 待发送）
+fn print_totals(rows: &[Payout], assets: &[Asset]) {
+    for (i, asset) in assets.iter().enumerate() {
+        let of_asset: Vec<&Payout> = rows.iter().filter(|r| r.asset == Some(i)).collect();
+        if of_asset.is_empty() {
+            continue;
+        }
+        let total: u128 = of_asset.iter().map(|r| r.raw).sum();
+        let pending: Vec<&&Payout> = of_asset.iter().filter(|r| r.status.needs_send()).collect();
+        let pending_total: u128 = pending.iter().map(|r| r.raw).sum();
+        println!(
+            "   {}{}: {} 笔，合计 {}；待发送 {} 笔，{}",
+            asset.symbol,
+            asset.contract.as_ref().map(|c| format!("（{}）", c)).unwrap_or_default(),
+            of_asset.len(),
+            abi::format_units(total, asset.decimals),
+            pending.len(),
+            abi::format_units(pending_total, asset.decimals)
+        );
+    }
+}
+
+/// 待发送行中各代币的合计（最小单位）
+fn pending_totals(rows: &[Payout], assets: &[Asset]) -> Vec<u128> {
+    (0..assets.len())
+        .map(|i| rows.iter().filter(|r| r.asset == Some(i) && r.status.needs_send()).map(|r| r.raw).sum())
+        .collect()
+}
+
+/// 确认阶段：所有已广播交易共用一个轮询循环（同 monitor-batch），最终状态即时写入结果 CSV
+async fn monitor(wallet: &Wallet, rows: &mut [Payout], results_path: &str, concurrency: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let timeout_sec: u64 = batch_monitor::env_number("MONITOR_TIMEOUT_SEC")?.unwrap_or(600);
+    let poll_ms: u64 = batch_monitor::env_number("MONITOR_POLL_MS")?.unwrap_or(3000);
+    let (backend, depth) = wallet.backend()?;
+    println!("   {} 笔，最终确认: {}，轮询: {}ms，超时: {}s", rows.iter().filter(|r| r.status == RowStatus::Sent).count(), wallet.finality_label(depth), poll_ms, timeout_sec);
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(timeout_sec);
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    loop {
+        match backend.head().await {
+            Ok(head) => {
+                let mut tasks = JoinSet::new();
+                for (i, row) in rows.iter().enumerate().filter(|(_, r)| r.status == RowStatus::Sent) {
+                    let (backend, hash, semaphore) = (backend.clone(), row.tx_hash.clone(), semaphore.clone());
+                    tasks.spawn(async move {
+                        let _permit = semaphore.acquire_owned().await;
+                        let inclusion = match backend.inclusion(&hash).await {
+                            Ok(Some(inc)) if head + 1 >= inc.block + depth => inc,
+                            _ => return (i, None),
+                        };
+                        // 达到确认数后复核区块哈希：回执所在区块已被重组掉时继续等待重新打包
+                        (i, Some((backend.is_canonical(&inclusion).await, inclusion)))
+                    });
+                }
+                let mut changed = false;
+                while let Some(joined) = tasks.join_next().await {
+                    let (i, inclusion) = match joined {
+                        Ok((i, Some((Ok(true), inc)))) => (i, inc),
+                        Ok((i, Some((Ok(false), inc)))) => {
+                            let row = &rows[i];
+                            println!(
+                                "[{:>4}s] ⚠ 第 {} 行 {} 区块重组：区块 {} 已不在规范链上，等待重新打包",
+                                started.elapsed().as_secs(),
+                                row.line,
+                                row.tx_hash,
+                                inc.block
+                            );
+                            continue;
+                        }
+                        _ => continue,
+                    };
+                    let row = &mut rows[i];
+                    row.status = if inclusion.success { RowStatus::Confirmed } else { RowStatus::Failed };
+                    println!(
+                        "[{:>4}s] 第 {} 行 {}  {}（区块 {}）",
+                        started.elapsed().as_secs(),
+                        row.line,
+                        row.tx_hash,
+                        row.status.label(),
+                        inclusion.block
+                    );
+                    ledger::record_final(&backend, &row.tx_hash, inclusion.success).await;
+                    changed = true;
+                }
+                if changed {
+                    save_results(results_path, rows)?;
+                }
+            }
+            Err(e) => println!("⚠ 查询链头失败，稍后重试: {}", e),
+        }
+        if rows.iter().all(|r| r.status != RowStatus::Sent) || Instant::now() >= deadline {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+    }
+    Ok(())
+}
+
+/// 汇总：按状态计数，列出需跟进的行
+fn print_summary(rows: &[Payout], assets: &[Asset], results_path: &str) {
+    println!();
+    println!("=== 汇总（{} 行）===", rows.len());
+    for status in [
+        RowStatus::Confirmed,
+        RowStatus::Failed,
+        RowStatus::Sent,
+        RowStatus::Signed,
+        RowStatus::Rejected,
+        RowStatus::Unknown,
+        RowStatus::Pending,
+    ] {
+        let count = rows.iter().filter(|r| r.status == status).count();
+        if count > 0 {
+            println!("{:<8} {:>6}", status.label(), count);
+        }
+    }
+    let follow_up: Vec<&Payout> = rows.iter().filter(|r| r.status != RowStatus::Confirmed).collect();
+    if !follow_up.is_empty() {
+        println!();
+        println!("[需跟进]（重新运行同一命令可继续：已广播的只监听，未上链的才重发）");
+        for r in follow_up {
+            let asset = r.asset.map(|i| &assets[i]);
+            println!(
+                "  第 {} 行  {}  {} {}  {}{}{}",
+                r.line,
+                r.recipient,
+                asset.map(|a| abi::format_units(r.raw, a.decimals)).unwrap_or_else(|| r.amount.clone()),
+                asset.map(|a| a.symbol.as_str()).unwrap_or(""),
+                r.status.label(),
+                if r.tx_hash.is_empty() { String::new() } else { format!("  {}", r.tx_hash) },
+                if r.error.is_empty() { String::new() } else { format!("  {}", r.error) }
+            );
+        }
+    }
+    println!("\n结果已写入 {}", results_path);
+}
+
+/// Tron 私钥对应地址：Tron 与以太坊地址同为公钥 Keccak-256 的后 20 字节，仅前缀不同（0x41）
+fn tron_signer_address(private_key: &str) -> Option<String> {
+    let eth = ethereum_address_from_private_key(private_key).ok()?;
+    abi::tron_address_from_hex(&format!("41{}", abi::strip_0x(&eth))).ok()
+}
+
+/// 当前毫秒时间戳（Tron 交易过期时间比较用）
+fn now_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+/// 按链区分的发送方
+enum Wallet {
+    Evm(Box<EvmWallet>),
+    Tron(TronWallet),
+}
+
+struct EvmWallet {
+    network: EvmNetworkConfig,
+    rpc: EvmRpc,
+    sdk: EvmSdk,
+    private_key: String,
+    signer: String,
+    chain_id: u64,
+    fees: FeeSettings,
+}
+
+struct TronWallet {
+    network: TronNetwork,
+    api: TronApi,
+    private_key: String,
+    signer: String,
+    fee_mode: FeeLimitMode,
+}
+
+impl Wallet {
+    /// 读取私钥与网络配置；缺少私钥或链 ID 不符时打印原因并返回 None
+    async fn from_env(is_evm: bool) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let key_var = if is_evm { "ETH_PRIVATE_KEY" } else { "TRON_PRIVATE_KEY" };
+        let private_key = match env::var(key_var) {
+            Ok(v) => v,
+            Err(_) => {
+                eprintln!("未设置 {}（batch-send 的发送方为私钥对应地址，预览也需要）。", key_var);
+                eprintln!("示例: export {}=你的64位十六进制私钥", key_var);
+                return Ok(None);
+            }
+        };
+        if is_evm {
+            let network = config::current_evm_network()?;
+            let rpc_url = config::evm_rpc_url(&network).await;
+            let rpc = EvmRpc::new(rpc_url.clone());
+            let identity = ChainIdentity::check(&rpc, &network).await;
+            let chain_id = match identity.require_for_signing() {
+                Ok(id) => id,
+                Err(e) => {
+                    identity.print();
+                    println!("❌ {}", e);
+                    return Ok(None);
+                }
+            };
+            let signer = signer_address(&private_key, "");
+            if signer.is_empty() {
+                return Err("ETH_PRIVATE_KEY 无法推导地址，请检查私钥格式".into());
+            }
+            if let Ok(from) = env::var("ETH_FROM_ADDRESS") {
+                if !from.eq_ignore_ascii_case(&signer) {
+                    return Err(format!("ETH_FROM_ADDRESS {} 与 ETH_PRIVATE_KEY 对应地址 {} 不一致", from, signer).into());
+                }
+            }
+            let fees = FeeSettings::from_env(&rpc).await?;
+            Ok(Some(Wallet::Evm(Box::new(EvmWallet {
+                sdk: EvmSdk::new(rpc_url),
+                network,
+                rpc,
+                private_key,
+                signer,
+                chain_id,
+                fees,
+            }))))
+        } else {
+            let network = config::current_tron_network();
+            let signer = tron_signer_address(&private_key).ok_or("TRON_PRIVATE_KEY 无法推导地址，请检查私钥格式")?;
+            if let Ok(from) = env::var("TRON_FROM_ADDRESS") {
+                if from.trim() != signer {
+                    return Err(format!("TRON_FROM_ADDRESS {} 与 TRON_PRIVATE_KEY 对应地址 {} 不一致", from, signer).into());
+                }
+            }
+            Ok(Some(Wallet::Tron(TronWallet {
+                network,
+                api: TronApi::new(config::tron_api_url(network)),
+                private_key,
+                signer,
+                fee_mode: trc20::fee_limit_mode()?,
+            })))
+        }
+    }
+
+    fn network_name(&self) -> String {
+        match self {
+            Wallet::Evm(w) => w.network.name().to_string(),
+            Wallet::Tron(w) => w.network.name().to_string(),
+        }
+    }
+
+    fn signer(&self) -> &str {
+        match self {
+            Wallet::Evm(w) => &w.signer,
+            Wallet::Tron(w) => &w.signer,
+        }
+    }
+
+    fn check_address(&self, addr: &str) -> Result<(), String> {
+        match self {
+            Wallet::Evm(_) => {
+                let bytes = abi::hex_to_bytes(addr).ok().filter(|b| b.len() == 20 && abi::strip_0x(addr).len() == 40);
+                match bytes {
+                    None => Err(format!("收款地址 {} 不是 0x + 40 位十六进制", addr)),
+                    Some(b) if b.iter().all(|x| *x == 0) => Err("收款地址为零地址".to_string()),
+                    Some(_) => Ok(()),
+                }
+            }
+            Wallet::Tron(_) => abi::tron_address_to_bytes(addr).map(|_| ()),
+        }
+    }
+
+    /// 解析各行 token 列并查询合约符号与精度；无法识别或查询失败的记为该行问题
+    async fn resolve_assets(&self, rows: &mut [Payout]) -> Vec<Asset> {
+        let (native, native_decimals) = match self {
+            Wallet::Evm(w) => (w.network.native_symbol.clone(), 18),
+            Wallet::Tron(_) => ("TRX".to_string(), 6),
+        };
+        let mut assets = vec![Asset { contract: None, symbol: native.clone(), decimals: native_decimals }];
+        let mut failed: BTreeMap<String, String> = BTreeMap::new();
+        for row in rows.iter_mut() {
+            let token = row.token.trim();
+            let contract = if token.is_empty() || token.eq_ignore_ascii_case(&native) {
+                row.asset = Some(0);
+                continue;
+            } else if token.eq_ignore_ascii_case("USDT") {
+                match self.default_stablecoin() {
+                    Some(c) => c,
+                    None => {
+                        row.issues.push(format!("当前网络 {} 未配置默认稳定币，请填写合约地址", self.network_name()));
+                        continue;
+                    }
+                }
+            } else if let Err(e) = self.check_address(token) {
+                row.issues.push(format!("代币 {} 无法识别（应为空、{}、USDT 或合约地址）: {}", token, native, e));
+                continue;
+            } else {
+                token.to_string()
+            };
+
+            if let Some(i) = assets.iter().position(|a| a.contract.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(&contract))) {
+                row.asset = Some(i);
+                continue;
+            }
+            if let Some(e) = failed.get(&contract.to_lowercase()) {
+                row.issues.push(e.clone());
+                continue;
+            }
+            match self.token_meta(&contract).await {
+                Ok((symbol, decimals)) => {
+                    assets.push(Asset { contract: Some(contract), symbol, decimals });
+                    row.asset = Some(assets.len() - 1);
+                }
+                Err(e) => {
+                    let msg = format!("查询代币 {} 精度失败: {}", contract, e);
+                    row.issues.push(msg.clone());
+                    failed.insert(contract.to_lowercase(), msg);
+                }
+            }
+        }
+        assets
+    }
+
+    fn default_stablecoin(&self) -> Option<String> {
+        match self {
+            Wallet::Evm(w) => w.network.stablecoin.clone(),
+            Wallet::Tron(w) => Some(w.network.usdt_contract().to_string()),
+        }
+    }
+
+    /// 代币符号与精度；符号查询失败时以合约地址代替
+    async fn token_meta(&self, contract: &str) -> Result<(String, u32), Box<dyn std::error::Error>> {
+        match self {
+            Wallet::Evm(w) => {
+                let decimals = w.sdk.erc20_decimals(contract).await?;
+                let symbol = w.sdk.erc20_symbol(contract).await.unwrap_or_else(|_| contract.to_string());
+                Ok((symbol, decimals as u32))
+            }
+            Wallet::Tron(w) => {
+                let sdk = TronSdk::new(w.network);
+                let decimals = sdk.trc20_decimals(contract).await?;
+                let symbol = sdk.trc20_symbol(contract).await.unwrap_or_else(|_| contract.to_string());
+                Ok((symbol, decimals as u32))
+            }
+        }
+    }
+
+    /// 代币余额（最小单位）
+    async fn balance(&self, asset: &Asset) -> Result<u128, Box<dyn std::error::Error>> {
+        match (self, &asset.contract) {
+            (Wallet::Evm(w), None) => Ok(w.rpc.get_balance(&w.signer, "latest").await?),
+            (Wallet::Evm(w), Some(c)) => {
+                let raw = w.sdk.erc20_balance_of(&w.signer, c).await?;
+                Ok(raw.trim().parse().map_err(|_| format!("代币余额无法解析: {}", raw))?)
+            }
+            (Wallet::Tron(w), None) => Ok(w.api.account_balance(&w.signer).await?.max(0) as u128),
+            (Wallet::Tron(w), Some(c)) => {
+                let raw = TronSdk::new(w.network).trc20_balance_of(&w.signer, c).await?;
+                Ok(raw.trim().parse().map_err(|_| format!("代币余额无法解析: {}", raw))?)
+            }
+        }
+    }
+
+    /// 并发模拟每笔待发送的转账（EVM eth_call @ pending / Tron triggerconstantcontract），回滚原因记为该行问题
+    async fn simulate(&self, rows: &mut [Payout], assets: &[Asset], concurrency: usize) {
+        let semaphore = Arc::new(Semaphore::new(concurrency));
+        let (mut tasks, mut pending) = (JoinSet::new(), BTreeSet::new());
+        for (i, row) in rows.iter().enumerate().filter(|(_, r)| r.status.needs_send()) {
+            let contract = row.asset.and_then(|a| assets[a].contract.clone());
+            let (recipient, raw, semaphore) = (row.recipient.clone(), row.raw, semaphore.clone());
+            match self {
+                Wallet::Evm(w) => {
+                    let (rpc, from) = (w.rpc.clone(), w.signer.clone());
+                    tasks.spawn(async move {
+                        let _permit = semaphore.acquire_owned().await;
+                        (i, simulate_evm(&rpc, &from, &recipient, contract.as_deref(), raw).await)
+                    });
+                }
+                Wallet::Tron(w) => {
+                    let Some(contract) = contract else { continue };
+                    let (api, from) = (w.api.clone(), w.signer.clone());
+                    tasks.spawn(async move {
+                        let _permit = semaphore.acquire_owned().await;
+                        let sim = api.simulate_trc20_transfer(&from, &contract, &recipient, raw).await;
+                        (i, sim.map(|s| (s.revert, s.energy_used)).map_err(|e| e.to_string()))
+                    });
+                }
+            }
+            pending.insert(i);
+        }
+        // 未能完成模拟的行同样记为问题，阻止 --send：不能确认不会回滚的交易不发送；
+        // 任务异常退出时拿不到行号，按派发后没有结果的行处理
+        let mut unverified = 0;
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((i, Ok((revert, energy)))) => {
+                    pending.remove(&i);
+                    rows[i].energy = energy;
+                    if let Some(reason) = revert {
+                        rows[i].issues.push(format!("模拟执行失败: {}", reason));
+                    }
+                }
+                Ok((i, Err(e))) => {
+                    pending.remove(&i);
+                    unverified += 1;
+                    rows[i].issues.push(format!("模拟调用未完成: {}", e));
+                }
+                Err(_) => {}
+            }
+        }
+        for i in pending {
+            unverified += 1;
+            rows[i].issues.push("模拟调用未完成: 查询任务异常退出".to_string());
+        }
+        if unverified > 0 {
+            println!("   ⚠ {} 行模拟调用未完成（网络错误），网络恢复后重新运行", unverified);
+        }
+    }
+
+    /// 余额与费用预估；余额不足时打印原因并返回 false
+    async fn check_funds(&self, rows: &[Payout], assets: &[Asset]) -> Result<bool, Box<dyn std::error::Error>> {
+        let totals = pending_totals(rows, assets);
+        let fee = match self {
+            Wallet::Evm(w) => w.estimate_fees(rows, assets).await?,
+            Wallet::Tron(w) => w.estimate_fees(rows, assets).await?,
+        };
+        let mut ok = true;
+        for (i, asset) in assets.iter().enumerate() {
+            let need = totals[i] + if i == 0 { fee } else { 0 };
+            if need == 0 {
+                continue;
+            }
+            let balance = self.balance(asset).await?;
+            let enough = balance >= need;
+            println!(
+                "   {} 余额 {}，需要 {}{} {}",
+                asset.symbol,
+                abi::format_units(balance, asset.decimals),
+                abi::format_units(need, asset.decimals),
+                if i == 0 && fee > 0 { "（含手续费）" } else { "" },
+                if enough { "✓" } else { "❌ 不足" }
+            );
+            ok &= enough;
+        }
+        if !ok {
+            println!("❌ 余额不足，请充值后重新运行（未发送任何交易）");
+        }
+        Ok(ok)
+    }
+
+    /// 核对上次中断时已签名 / 已广播 / 被拒的交易，决定只监听、重新广播还是重新发送
+    async fn reconcile(&self, row: &mut Payout) {
+        if row.tx_hash.is_empty() {
+            row.status = RowStatus::Pending;
+            return;
+        }
+        match self.known(&row.tx_hash).await {
+            Ok(true) => {
+                println!("   第 {} 行 {} 已在链上或交易池，继续监听", row.line, row.tx_hash);
+                row.status = RowStatus::Sent;
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                // 无法确认时记为已签名：本次既不重发也不监听，下次运行再核对
+                println!("   ⚠ 第 {} 行 {} 查询失败，本次跳过: {}", row.line, row.tx_hash, e);
+                row.status = RowStatus::Signed;
+                row.error = format!("核对失败: {}", e);
+                return;
+            }
+        }
+        if !row.signed_tx.is_empty() {
+            match self.broadcast(&row.signed_tx).await {
+                Ok(_) => {
+                    println!("   第 {} 行 {} 未查到，已重新广播原签名交易", row.line, row.tx_hash);
+                    row.status = RowStatus::Sent;
+                    row.error.clear();
+                    return;
+                }
+                Err(e) => row.error = format!("重新广播失败: {}", e),
+            }
+        }
+        match self {
+            Wallet::Evm(w) => match (row.nonce, w.rpc.get_transaction_count(&w.signer, "latest").await) {
+                (Some(n), Ok(latest)) if latest > n => {
+                    println!("   ⚠ 第 {} 行的 nonce {} 已被其他交易使用，原交易 {} 不会再上链，请人工核对是否已付款", row.line, n, row.tx_hash);
+                    row.status = RowStatus::Unknown;
+                    row.error = format!("nonce {} 已被其他交易使用", n);
+                }
+                (_, Ok(_)) => {
+                    // nonce 未被使用：沿用同一 nonce 重发，原交易与新交易最多一笔上链
+                    println!("   第 {} 行未上链，将以 nonce {} 重新发送", row.line, row.nonce.map(|n| n.to_string()).unwrap_or_else(|| "新".to_string()));
+                    row.status = RowStatus::Pending;
+                }
+                (_, Err(e)) => {
+                    row.status = RowStatus::Signed;
+                    row.error = format!("查询 nonce 失败: {}", e);
+                }
+            },
+            Wallet::Tron(w) => {
+                // 过期后的交易不会再被打包；等到过期后复查一次，仍未上链才重发
+                let expiration = serde_json::from_str::<Value>(&row.signed_tx)
+                    .ok()
+                    .and_then(|v| v.pointer("/raw_data/expiration").and_then(|e| e.as_i64()))
+                    .unwrap_or(0);
+                let wait_ms = expiration + 3000 - now_ms();
+                if wait_ms > 0 {
+                    println!("   第 {} 行未查到，等待原交易过期（{}s）后复查...", row.line, wait_ms / 1000 + 1);
+                    tokio::time::sleep(Duration::from_millis(wait_ms as u64)).await;
+                }
+                match w.api.get_transaction_by_id(&row.tx_hash).await {
+                    Ok(v) if v.get("txID").is_some() => row.status = RowStatus::Sent,
+                    Ok(_) => {
+                        println!("   第 {} 行原交易已过期未上链，将重新发送", row.line);
+                        row.status = RowStatus::Pending;
+                    }
+                    Err(e) => {
+                        row.status = RowStatus::Signed;
+                        row.error = format!("复查失败: {}", e);
+                    }
+                }
+            }
+        }
+        if row.status == RowStatus::Pending {
+            row.tx_hash.clear();
+            row.signed_tx.clear();
+        }
+    }
+
+    /// 交易是否已被节点知晓（已上链或在交易池）
+    async fn known(&self, hash: &str) -> Result<bool, RpcError> {
+        match self {
+            Wallet::Evm(w) => Ok(!w.rpc.get_transaction(hash).await?.is_null()),
+            Wallet::Tron(w) => Ok(w.api.get_transaction_by_id(hash).await?.get("txID").is_some()),
+        }
+    }
+
+    /// 广播已签名交易，返回节点给出的交易哈希
+    async fn broadcast(&self, signed: &str) -> Result<String, Box<dyn std::error::Error>> {
+        match self {
+            Wallet::Evm(w) => Ok(w.sdk.send(signed).await?),
+            Wallet::Tron(w) => Ok(TronSdk::new(w.network).send(signed).await?),
+        }
+    }
+
+    async fn send_all(&self, rows: &mut [Payout], assets: &[Asset], results_path: &str, concurrency: usize) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Wallet::Evm(w) => w.send_all(self, rows, assets, results_path).await,
+            Wallet::Tron(w) => w.send_all(rows, assets, results_path, concurrency).await,
+        }
+    }
+
+    /// 确认阶段的查询后端与最终确认深度
+    fn backend(&self) -> Result<(Backend, u64), String> {
+        match self {
+            Wallet::Evm(w) => {
+                let depth = batch_monitor::env_number("MONITOR_MIN_CONFIRMATIONS")?
+                    .unwrap_or(w.network.finality_depth.unwrap_or(1))
+                    .max(1);
+                Ok((Backend::Evm(w.rpc.clone()), depth))
+            }
+            Wallet::Tron(w) => Ok((Backend::Tron(w.api.clone()), 1)),
+        }
+    }
+
+    fn finality_label(&self, depth: u64) -> String {
+        match self {
+            Wallet::Evm(_) => format!("{} 个确认", depth),
+            Wallet::Tron(_) => "所在区块已固化".to_string(),
+        }
+    }
+}
+
+/// EVM 转账模拟：ERC20 为 transfer(to, amount)，原生币为带 value 的空调用（收款方为合约时可能拒收）
+async fn simulate_evm(rpc: &EvmRpc, from: &str, to: &str, contract: Option<&str>, raw: u128) -> Result<(Option<String>, i64), String> {
+    let call = match contract {
+        Some(contract) => {
+            let mut to_bytes = [0u8; 20];
+            to_bytes.copy_from_slice(&abi::hex_to_bytes(to)?);
+            let mut data = vec![0xa9, 0x05, 0x9c, 0xbb];
+            data.extend_from_slice(&abi::encode_address(&to_bytes));
+            data.extend_from_slice(&abi::encode_uint(raw));
+            json!({ "from": from, "to": contract, "data": format!("0x{}", hex::encode(data)) })
+        }
+        None => json!({ "from": from, "to": to, "value": abi::to_hex_quantity(raw) }),
+    };
+    match rpc.eth_call(&call, "pending").await {
+        Ok(CallOutcome::Success) => Ok((None, 0)),
+        Ok(CallOutcome::Reverted(reason)) => Ok((Some(reason), 0)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+impl EvmWallet {
+    /// 构建单笔转账并应用费用设置（from 为私钥对应地址）
+    async fn build(&self, row: &Payout, asset: &Asset) -> Result<String, Box<dyn std::error::Error>> {
+        let amount = row.raw.to_string();
+        let tx_json = match &asset.contract {
+            None => self.sdk.build_native_transfer(&self.signer, &row.recipient, &amount).await?,
+            Some(c) => self.sdk.erc20_build_transfer(&self.signer, &row.recipient, c, &amount).await?,
+        };
+        self.fees.apply(&tx_json)
+    }
+
+    /// 每种代币以第一笔待发送行构建样例交易，按 gas 上限 × 最高单价估算手续费上限（最小单位）
+    async fn estimate_fees(&self, rows: &[Payout], assets: &[Asset]) -> Result<u128, Box<dyn std::error::Error>> {
+        self.fees.print();
+        let mut total = 0u128;
+        for (i, asset) in assets.iter().enumerate() {
+            let pending: Vec<&Payout> = rows.iter().filter(|r| r.asset == Some(i) && r.status.needs_send()).collect();
+            let Some(sample) = pending.first() else { continue };
+            let tx: Value = serde_json::from_str(&self.build(sample, asset).await?)?;
+            let gas = tx.get("gas").or_else(|| tx.get("gasLimit")).and_then(abi::parse_quantity).unwrap_or(0);
+            let price = tx
+                .get("maxFeePerGas")
+                .or_else(|| tx.get("gasPrice"))
+                .and_then(abi::parse_quantity)
+                .unwrap_or(0);
+            let subtotal = gas * price * pending.len() as u128;
+            println!(
+                "   {} 转账: {} 笔 × gas 上限 {} × {} gwei ≈ 最多 {} {}",
+                asset.symbol,
+                pending.len(),
+                gas,
+                evm_fees::format_gwei(price),
+                abi::format_units(subtotal, 18),
+                self.network.native_symbol
+            );
+            total += subtotal;
+        }
+        Ok(total)
+    }
+
+    /// 按行顺序逐笔：构建 → 分配 nonce → 签名（记下哈希）→ 广播；任一步失败即停止，避免后续 nonce 空缺
+    async fn send_all(&self, wallet: &Wallet, rows: &mut [Payout], assets: &[Asset], results_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        // 沿用旧 nonce 的行先发，补上空缺
+        let mut order: Vec<usize> = (0..rows.len()).filter(|&i| rows[i].status.needs_send()).collect();
+        order.sort_by_key(|&i| (rows[i].nonce.is_none(), rows[i].nonce, i));
+        for i in order {
+            let row = &mut rows[i];
+            let asset = &assets[row.asset.unwrap_or(0)];
+            let tx_json = match self.build(row, asset).await {
+                Ok(t) => t,
+                Err(e) => {
+                    println!("   ❌ 第 {} 行构建失败: {}", row.line, e);
+                    row.status = RowStatus::Rejected;
+                    row.error = format!("构建失败: {}", e);
+                    save_results(results_path, rows)?;
+                    println!("   已停止发送后续行，处理原因后重新运行同一命令继续");
+                    return Ok(());
+                }
+            };
+            let entry = ledger::track(&TxInfo::evm(&self.network, &self.signer, &tx_json).token(&asset.symbol, asset.decimals));
+            let nonce = nonce_manager::reserve(&self.rpc, self.network.name(), &self.signer, row.nonce).await?;
+            let signed = nonce_manager::set_nonce(&tx_json, nonce)
+                .and_then(|tx| evm_chain::bind_chain_id(&tx, self.chain_id))
+                .and_then(|tx| Ok(sign_ethereum_transaction(&tx, &self.private_key)?))
+                .and_then(|signed| Ok((ledger::evm_tx_hash(&signed)?, signed)));
+            let (tx_hash, signed) = match signed {
+                Ok(s) => s,
+                Err(e) => {
+                    nonce_manager::release(self.network.name(), &self.signer, nonce).await;
+                    return Err(format!("第 {} 行签名失败: {}", row.line, e).into());
+                }
+            };
+            row.tx_hash = tx_hash;
+            row.nonce = Some(nonce);
+            entry.signed(&row.tx_hash, Some(nonce));
+            row.signed_tx = signed;
+            row.status = RowStatus::Signed;
+            save_results(results_path, rows)?;
+
+            let row = &mut rows[i];
+            match wallet.broadcast(&row.signed_tx).await {
+                Ok(hash) => {
+                    if !hash.eq_ignore_ascii_case(&row.tx_hash) {
+                        println!("   ⚠ 节点返回的哈希 {} 与本地计算的 {} 不一致，以节点为准", hash, row.tx_hash);
+                        row.tx_hash = hash;
+                    }
+                    println!("   第 {} 行 nonce {} → {}", row.line, nonce, row.tx_hash);
+                    nonce_manager::record(self.network.name(), &self.signer, nonce, &row.tx_hash).await;
+                    entry.broadcast(&row.tx_hash);
+                    row.status = RowStatus::Sent;
+                    row.error.clear();
+                    save_results(results_path, rows)?;
+                }
+                Err(e) => {
+                    println!("   ❌ 第 {} 行广播失败: {}", row.line, e);
+                    entry.rejected(&e.to_string());
+                    nonce_manager::release(self.network.name(), &self.signer, nonce).await;
+                    row.status = RowStatus::Rejected;
+                    row.error = format!("广播失败: {}", e);
+                    save_results(results_path, rows)?;
+                    println!("   已停止发送后续行（避免 nonce 空缺），处理原因后重新运行同一命令继续");
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TronWallet {
+    /// TRC20 按模拟能量合计估算需燃烧的 TRX（扣除账户可用能量）；TRX 转账只消耗带宽
+    async fn estimate_fees(&self, rows: &[Payout], assets: &[Asset]) -> Result<u128, Box<dyn std::error::Error>> {
+        let pending: Vec<&Payout> = rows.iter().filter(|r| r.status.needs_send()).collect();
+        let energy: i64 = pending.iter().map(|r| r.energy).sum();
+        if pending.iter().any(|r| r.asset.is_some_and(|a| assets[a].contract.is_none())) {
+            println!("   TRX 转账消耗带宽，免费带宽用尽后按字节燃烧 TRX（未计入下方合计）");
+        }
+        if energy == 0 {
+            return Ok(0);
+        }
+        let est = Trc20Estimate {
+            revert: None,
+            energy_used: energy,
+            energy_available: self.api.available_energy(&self.signer).await?,
+            energy_price: self.api.energy_price().await?,
+            balance_sun: 0,
+        };
+        let burn = (est.energy_used - est.energy_available).max(0) * est.energy_price;
+        println!("   fee_limit 模式: {}", self.fee_mode.describe());
+        println!(
+            "   TRC20 转账: 预估能量合计 {}（可用能量 {}，单价 {} sun）≈ 燃烧 {} TRX",
+            est.energy_used,
+            est.energy_available,
+            est.energy_price,
+            abi::format_units(burn as u128, 6)
+        );
+        Ok(burn as u128)
+    }
+
+    /// 每批 concurrency 行：并发构建签名 → 记下 txID → 并发广播
+    async fn send_all(&self, rows: &mut [Payout], assets: &[Asset], results_path: &str, concurrency: usize) -> Result<(), Box<dyn std::error::Error>> {
+        let energy_price = self.api.energy_price().await?;
+        let order: Vec<usize> = (0..rows.len()).filter(|&i| rows[i].status.needs_send()).collect();
+        for chunk in order.chunks(concurrency) {
+            let mut tasks = JoinSet::new();
+            for &i in chunk {
+                let row = &rows[i];
+                let contract = assets[row.asset.unwrap_or(0)].contract.clone();
+                let fee_limit = contract.as_ref().map(|_| {
+                    let est = Trc20Estimate {
+                        revert: None,
+                        energy_used: row.energy,
+                        energy_available: 0,
+                        energy_price,
+                        balance_sun: 0,
+                    };
+                    self.fee_mode.resolve(Some(&est))
+                });
+                let (network, from, to, raw, key) = (self.network, self.signer.clone(), row.recipient.clone(), row.raw, self.private_key.clone());
+                tasks.spawn(async move {
+                    let result: Result<String, String> = async {
+                        let sdk = TronSdk::new(network);
+                        let tx_json = match contract {
+                            Some(c) => sdk.trc20_build_transfer(&from, &to, &c, &raw.to_string(), fee_limit).await,
+                            None => {
+                                let sun = i64::try_from(raw).map_err(|_| "金额超出范围".to_string())?;
+                                sdk.trx_build_transfer(&from, &to, sun).await
+                            }
+                        }
+                        .map_err(|e| format!("构建失败: {}", e))?;
+                        sign_tron_transaction(&tx_json, &key).map_err(|e| format!("签名失败: {}", e))
+                    }
+                    .await;
+                    (i, result)
+                });
+            }
+            let mut signed_rows = Vec::new();
+            let mut entries = BTreeMap::new();
+            while let Some(joined) = tasks.join_next().await {
+                let (i, result) = joined.map_err(|e| e.to_string())?;
+                let row = &mut rows[i];
+                let tx_id = result.and_then(|signed| {
+                    let v: Value = serde_json::from_str(&signed).map_err(|e| format!("签名交易解析失败: {}", e))?;
+                    let id = v.get("txID").and_then(|t| t.as_str()).ok_or("签名交易缺少 txID")?.to_string();
+                    // 紧凑序列化，保证结果 CSV 中每行一条记录
+                    Ok((id, v.to_string()))
+                });
+                match tx_id {
+                    Ok((id, signed)) => {
+                        let asset = &assets[row.asset.unwrap_or(0)];
+                        let entry = ledger::track(&TxInfo::tron(self.network, &signed).token(&asset.symbol, asset.decimals));
+                        entry.signed(&id, None);
+                        entries.insert(i, entry);
+                        row.tx_hash = id;
+                        row.signed_tx = signed;
+                        row.status = RowStatus::Signed;
+                        signed_rows.push(i);
+                    }
+                    Err(e) => {
+                        println!("   ❌ 第 {} 行{}", row.line, e);
+                        row.status = RowStatus::Rejected;
+                        row.error = e;
+                    }
+                }
+            }
+            save_results(results_path, rows)?;
+
+            let mut tasks = JoinSet::new();
+            for &i in &signed_rows {
+                let (network, signed) = (self.network, rows[i].signed_tx.clone());
+                tasks.spawn(async move {
+                    (i, TronSdk::new(network).send(&signed).await)
+                });
+            }
+            while let Some(joined) = tasks.join_next().await {
+                let (i, result) = joined.map_err(|e| e.to_string())?;
+                let row = &mut rows[i];
+                match result {
+                    Ok(_) => {
+                        println!("   第 {} 行 → {}", row.line, row.tx_hash);
+                        if let Some(entry) = entries.get(&i) {
+                            entry.broadcast(&row.tx_hash);
+                        }
+                        row.status = RowStatus::Sent;
+                        row.error.clear();
+                    }
+                    Err(e) => {
+                        println!("   ❌ 第 {} 行广播失败: {}", row.line, e);
+                        if let Some(entry) = entries.get(&i) {
+                            entry.rejected(&e);
+                        }
+                        row.status = RowStatus::Rejected;
+                        row.error = format!("广播失败: {}", e);
+                    }
+                }
+            }
+            save_results(results_path, rows)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_line_quotes() {
+        assert_eq!(parse_csv_line("a,b,,d").unwrap(), ["a", "b", "", "d"]);
+        assert_eq!(parse_csv_line(r#"TXyz,1.5,USDT,"rent, May""#).unwrap(), ["TXyz", "1.5", "USDT", "rent, May"]);
+        assert_eq!(parse_csv_line(r#"a,"say ""hi""",c"#).unwrap(), ["a", r#"say "hi""#, "c"]);
+        assert_eq!(parse_csv_line(r#"a, "b,c""#).unwrap(), ["a", "b,c"]);
+        assert_eq!(parse_csv_line(r#""""#).unwrap(), [""]);
+        assert!(parse_csv_line(r#"a,"b"#).is_err());
+    }
+
+    #[test]
+    fn csv_field_round_trip() {
+        for s in ["plain", "a,b", r#"q"uote"#, ""] {
+            assert_eq!(parse_csv_line(&csv_field(s)).unwrap(), [s]);
+        }
+    }
+
+    fn payouts() -> Vec<Payout> {
+        parse_payouts("recipient,amount,token,memo\n0xaaa,1.5,,first\n# 注释\n0xbbb,2,USDT,\"a, b\"\n0xccc,3,,\n").unwrap()
+    }
+
+    fn results(lines: &[&str]) -> String {
+        let mut text = format!("{}\n", RESULT_HEADER);
+        for line in lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text
+    }
+
+    #[test]
+    fn results_restore_rows() {
+        let mut rows = payouts();
+        assert_eq!(rows.iter().map(|r| r.line).collect::<Vec<_>>(), [2, 4, 5]);
+        let text = results(&[
+            "2,0xaaa,1.5,,first,confirmed,0xh1,7,,",
+            "4,0xbbb,2,USDT,\"a, b\",signed,0xh2,8,,0xf86b",
+            "",
+            "5,0xccc,3,,,pending,,,,",
+        ]);
+        assert_eq!(apply_results(&mut rows, &text), Ok(2));
+        assert_eq!(rows[0].status.key(), "confirmed");
+        assert_eq!((rows[0].tx_hash.as_str(), rows[0].nonce), ("0xh1", Some(7)));
+        assert_eq!(rows[1].status.key(), "signed");
+        assert_eq!(rows[1].signed_tx, "0xf86b");
+        assert_eq!((rows[2].status.key(), rows[2].nonce), ("pending", None));
+    }
+
+    #[test]
+    fn results_partial_file_leaves_rest_pending() {
+        let mut rows = payouts();
+        let text = results(&["4,0xbbb,2,USDT,\"a, b\",rejected,,,\"nonce too low, retry\","]);
+        assert_eq!(apply_results(&mut rows, &text), Ok(1));
+        assert_eq!(rows[1].status.key(), "rejected");
+        assert_eq!(rows[1].error, "nonce too low, retry");
+        assert_eq!(rows[0].status.key(), "pending");
+        assert_eq!(apply_results(&mut payouts(), RESULT_HEADER), Ok(0));
+    }
+
+    #[test]
+    fn results_reject_mismatched_input() {
+        let err = |lines: &[&str]| apply_results(&mut payouts(), &results(lines)).unwrap_err();
+        assert!(err(&["2,0xaaa,9,,first,sent,0xh1,7,,"]).contains("与输入 CSV 不对应"));
+        assert!(err(&["3,0xaaa,1.5,,first,sent,0xh1,7,,"]).contains("与输入 CSV 不对应"));
+        assert!(err(&["2,0xaaa,1.5,,first,sent"]).contains("列数不正确"));
+        assert!(err(&["2,0xaaa,1.5,,first,done,0xh1,7,,"]).contains("状态 done 无法识别"));
+        assert!(err(&["2,0xaaa,1.5,,first,sent,0xh1,x,,"]).starts_with("第 2 行nonce 无法解析"));
+    }
+}
@@ -2,12 +2,21 @@
 //!
 //! 环境变量说明：
 //! - TRON_NETWORK: nile | mainnet | shasta（默认 nile）
-//! - EVM_NETWORK: sepolia | arbitrum-sepolia | arbitrum-one | mainnet（默认 sepolia）
-//! - EVM_RPC_URL: 覆盖 EVM RPC，未设置时从 SDK 备选列表健康检查选取
+//! - EVM_NETWORK: sepolia | arbitrum-sepolia | arbitrum-one | mainnet 或 EVM_NETWORKS_FILE 中的自定义网络（默认 sepolia，未知名称报错）
+//! - EVM_NETWORKS_FILE: 自定义 EVM 网络定义（JSON，默认 evm-networks.json，不存在时忽略）
+//! - EVM_RPC_URL: 覆盖 EVM RPC（可逗号分隔多个作为故障转移备用），未设置时从网络备选列表健康检查选取；签名前以 eth_chainId 校验，与 EVM_NETWORK 不一致时拒绝签名（见 evm_chain）
+//! - TRON_RPC_URL: 覆盖 Tron HTTP API（SDK 未覆盖的接口使用，可逗号分隔多个作为故障转移备用），未设置时按网络使用 TronGrid 及公共备用节点
+//! - TRON_MONITOR_TIMEOUT_SEC / TRON_MONITOR_POLL_MS: Tron 交易等待固化的超时秒数与轮询间隔（默认 120s / 3000ms）
+//! - NONCE_STATE_FILE: EVM 本地 nonce 状态文件（默认 .nonce-state.json）
+//! - LEDGER_FILE: 本地交易记录（默认 .tx-ledger.jsonl，见 ledger）
+//! - RPC_PROBE_FILE / RPC_PROBE_TTL_SEC: `rpc probe` 选出的最快健康端点（默认 .rpc-probe.json，有效期 3600s），未设置 EVM_RPC_URL / TRON_RPC_URL 时优先使用
+//! - RPC_MAX_ATTEMPTS、RPC_BACKOFF_MS、RPC_BREAKER_THRESHOLD、RPC_LOG 等：原始 RPC 的重试、退避与熔断（见 rpc）
 
+use crate::rpc;
 use chains_sdk::rpc::chains::evm::{EvmNetwork, EvmRpcProvider};
 use chains_sdk::rpc::chains::tron::TronNetwork;
 use chains_sdk::rpc::RpcProvider;
+use serde_json::Value;
 use std::env;
 use std::time::Duration;
 
@@ -17,36 +26,338 @@ pub const TRON_EXAMPLE_ADDR: &str = "TG2D8vTp4xHBB2vhVbgHK2AhA2p9wY4q9M";
 /// EVM 示例地址（Sepolia，0x 格式，与 run_verify.py 一致）
 pub const EVM_EXAMPLE_ADDR: &str = "0x3CCD11B6c4B5Ca62d2B29C949B23e0550d64f0b9";
 
+/// 全部 Tron 网络
+pub const TRON_NETWORKS: [TronNetwork; 3] = [TronNetwork::Nile, TronNetwork::Mainnet, TronNetwork::Shasta];
+
 /// 从环境变量 TRON_NETWORK 解析当前 Tron 网络（nile | mainnet | shasta），默认 nile
+///
+/// SDK 的 Tron 调用走该网络的 TronGrid 节点，在此登记链族以便 TRON_RPC_RATE_LIMIT 对其生效。
 pub fn current_tron_network() -> TronNetwork {
-    match env::var("TRON_NETWORK").as_deref() {
+    let network = match env::var("TRON_NETWORK").as_deref() {
         Ok("mainnet") => TronNetwork::Mainnet,
         Ok("shasta") => TronNetwork::Shasta,
         _ => TronNetwork::Nile,
+    };
+    rpc::register_family("tron", &[trongrid_url(network).to_string()]);
+    network
+}
+
+/// Tron 网络在 TRON_NETWORK 中的名称
+pub fn tron_network_key(network: TronNetwork) -> &'static str {
+    match network {
+        TronNetwork::Mainnet => "mainnet",
+        TronNetwork::Nile => "nile",
+        TronNetwork::Shasta => "shasta",
+    }
+}
+
+/// Tron 网络的链 ID（创世区块哈希的末 4 字节，TIP-474）
+pub fn tron_chain_id(network: TronNetwork) -> u64 {
+    match network {
+        TronNetwork::Mainnet => 0x2b66_53dc,
+        TronNetwork::Nile => 0xcd86_90dc,
+        TronNetwork::Shasta => 0x94a9_059e,
+    }
+}
+
+/// 网络的 TronGrid 节点（SDK 的 Tron 调用固定使用）
+pub fn trongrid_url(network: TronNetwork) -> &'static str {
+    match network {
+        TronNetwork::Mainnet => "https://api.trongrid.io",
+        TronNetwork::Nile => "https://nile.trongrid.io",
+        TronNetwork::Shasta => "https://api.shasta.trongrid.io",
+    }
+}
+
+/// 网络内置的 Tron HTTP API 节点：TronGrid 在前，其后为公共备用节点
+pub fn tron_known_urls(network: TronNetwork) -> Vec<String> {
+    let backups: &[&str] = match network {
+        TronNetwork::Mainnet => &["https://tron-rpc.publicnode.com"],
+        TronNetwork::Nile => &["https://api.nileex.io"],
+        TronNetwork::Shasta => &[],
+    };
+    std::iter::once(trongrid_url(network))
+        .chain(backups.iter().copied())
+        .map(|u| u.to_string())
+        .collect()
+}
+
+/// Tron HTTP API 候选节点：TRON_RPC_URL（可逗号分隔多个），未设置时为内置节点，`rpc probe` 选出的节点排在最前
+pub fn tron_api_urls(network: TronNetwork) -> Vec<String> {
+    if let Ok(urls) = env::var("TRON_RPC_URL") {
+        return split_urls(&urls);
+    }
+    prefer_probed(tron_known_urls(network), "tron", tron_network_key(network))
+}
+
+/// 网络的全部 Tron HTTP API 节点：TRON_RPC_URL 中的节点在前，其后为内置节点（去重），供 `consistency` 逐节点比对
+pub fn tron_all_urls(network: TronNetwork) -> Vec<String> {
+    let mut urls = env::var("TRON_RPC_URL").map(|u| split_urls(&u)).unwrap_or_default();
+    for url in tron_known_urls(network) {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// Tron HTTP API 地址（triggerconstantcontract、getaccountresource 等 SDK 未覆盖的接口使用）；其余候选登记为故障转移备用
+pub fn tron_api_url(network: TronNetwork) -> String {
+    let urls = tron_api_urls(network);
+    let primary = urls.first().cloned().unwrap_or_default();
+    rpc::register_fallbacks(&primary, &urls, "tron");
+    primary
+}
+
+/// 逗号分隔的 URL 列表，去掉空项与末尾的 /
+fn split_urls(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|u| u.trim().trim_end_matches('/').to_string())
+        .filter(|u| !u.is_empty())
+        .collect()
+}
+
+/// Tron 交易等待固化的超时秒数（TRON_MONITOR_TIMEOUT_SEC，默认 120）
+pub fn tron_monitor_timeout_sec() -> u64 {
+    env::var("TRON_MONITOR_TIMEOUT_SEC")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(120)
+}
+
+/// Tron 交易等待固化的轮询间隔毫秒（TRON_MONITOR_POLL_MS，默认 3000）
+pub fn tron_monitor_poll_ms() -> u64 {
+    env::var("TRON_MONITOR_POLL_MS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(3000)
+}
+
+/// EVM 本地 nonce 状态文件路径（NONCE_STATE_FILE，默认当前目录下 .nonce-state.json）
+pub fn nonce_state_path() -> String {
+    env::var("NONCE_STATE_FILE").unwrap_or_else(|_| ".nonce-state.json".to_string())
+}
+
+/// 本地交易记录文件路径（LEDGER_FILE，默认当前目录下 .tx-ledger.jsonl）
+pub fn ledger_path() -> String {
+    env::var("LEDGER_FILE").unwrap_or_else(|_| ".tx-ledger.jsonl".to_string())
+}
+
+/// `rpc probe` 选择结果文件路径（RPC_PROBE_FILE，默认当前目录下 .rpc-probe.json）
+pub fn rpc_probe_path() -> String {
+    env::var("RPC_PROBE_FILE").unwrap_or_else(|_| ".rpc-probe.json".to_string())
+}
+
+/// `rpc probe` 选择结果的有效期秒数（RPC_PROBE_TTL_SEC，默认 3600；0 表示不使用探测结果）
+pub fn rpc_probe_ttl_sec() -> u64 {
+    env::var("RPC_PROBE_TTL_SEC")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(3600)
+}
+
+/// `rpc probe` 为网络选出且仍在有效期内的端点
+fn probed_endpoint(family: &str, key: &str) -> Option<String> {
+    let text = std::fs::read_to_string(rpc_probe_path()).ok()?;
+    let v: Value = serde_json::from_str(&text).ok()?;
+    let entry = v.get(family)?.get(key)?;
+    let probed_at = entry.get("probed_at").and_then(|t| t.as_u64())?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if now.saturating_sub(probed_at) >= rpc_probe_ttl_sec() {
+        return None;
+    }
+    entry.get("url").and_then(|u| u.as_str()).map(|u| u.to_string())
+}
+
+/// 把探测选出的端点移到候选列表最前（仅当它仍在列表中）
+fn prefer_probed(mut urls: Vec<String>, family: &str, key: &str) -> Vec<String> {
+    if let Some(i) = probed_endpoint(family, key).and_then(|p| urls.iter().position(|u| *u == p)) {
+        let url = urls.remove(i);
+        urls.insert(0, url);
+    }
+    urls
+}
+
+/// 内置 EVM 网络（EVM_NETWORK 名称、SDK 网络、链 ID、区块浏览器）
+const BUILTIN_EVM_NETWORKS: [(&str, EvmNetwork, u64, &str); 4] = [
+    ("sepolia", EvmNetwork::Sepolia, 11_155_111, "https://sepolia.etherscan.io"),
+    ("arbitrum-sepolia", EvmNetwork::ArbitrumSepolia, 421_614, "https://sepolia.arbiscan.io"),
+    ("arbitrum-one", EvmNetwork::ArbitrumOne, 42_161, "https://arbiscan.io"),
+    ("mainnet", EvmNetwork::Mainnet, 1, "https://etherscan.io"),
+];
+
+/// EVM 网络定义：内置网络来自 SDK EvmNetwork，自定义网络来自 EVM_NETWORKS_FILE
+#[derive(Clone)]
+pub struct EvmNetworkConfig {
+    /// EVM_NETWORK 使用的名称（如 sepolia、base-sepolia）
+    pub key: String,
+    /// 显示名称（内置网络为 SDK 名称，同时用作本地 nonce 状态的键）
+    pub display_name: String,
+    /// 链 ID（签名前与节点 eth_chainId 比对）
+    pub chain_id: u64,
+    /// 备选 RPC，未设置 EVM_RPC_URL 时依次健康检查
+    pub rpc_urls: Vec<String>,
+    /// 原生币符号
+    pub native_symbol: String,
+    /// 区块浏览器根地址
+    pub explorer_url: Option<String>,
+    /// 默认稳定币合约（未设置 ERC20_CONTRACT_ADDRESS 时使用）
+    pub stablecoin: Option<String>,
+    /// 最终确认深度；未配置时各命令使用自身默认值
+    pub finality_depth: Option<u64>,
+}
+
+impl EvmNetworkConfig {
+    fn builtin(key: &str, network: EvmNetwork, chain_id: u64, explorer: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            display_name: network.name().to_string(),
+            chain_id,
+            rpc_urls: network.urls().iter().map(|u| u.to_string()).collect(),
+            native_symbol: "ETH".to_string(),
+            explorer_url: Some(explorer.to_string()),
+            stablecoin: Some(network.usdt_contract().to_string()),
+            finality_depth: None,
+        }
+    }
+
+    /// 解析 EVM_NETWORKS_FILE 中的一项
+    fn custom(key: &str, v: &Value) -> Result<Self, String> {
+        let err = |field: &str| format!("EVM_NETWORKS_FILE 中网络 {} 的 {} 缺失或格式错误", key, field);
+        let chain_id = v.get("chain_id").and_then(|c| c.as_u64()).ok_or_else(|| err("chain_id"))?;
+        let rpc_urls: Vec<String> = v
+            .get("rpc_urls")
+            .and_then(|u| u.as_array())
+            .map(|a| a.iter().filter_map(|u| u.as_str()).map(|u| u.to_string()).collect())
+            .unwrap_or_default();
+        if rpc_urls.is_empty() {
+            return Err(err("rpc_urls"));
+        }
+        let text = |field: &str| v.get(field).and_then(|t| t.as_str()).map(|t| t.to_string());
+        let finality_depth = match v.get("finality_depth") {
+            None => None,
+            Some(d) => Some(d.as_u64().filter(|d| *d > 0).ok_or_else(|| err("finality_depth"))?),
+        };
+        Ok(Self {
+            key: key.to_string(),
+            display_name: text("name").unwrap_or_else(|| key.to_string()),
+            chain_id,
+            rpc_urls,
+            native_symbol: text("native_symbol").unwrap_or_else(|| "ETH".to_string()),
+            explorer_url: text("explorer_url").map(|u| u.trim_end_matches('/').to_string()),
+            stablecoin: text("stablecoin"),
+            finality_depth,
+        })
+    }
+
+    /// 显示名称
+    pub fn name(&self) -> &str {
+        &self.display_name
+    }
+
+    /// 区块浏览器中的交易链接
+    pub fn tx_url(&self, tx_hash: &str) -> Option<String> {
+        self.explorer_url.as_ref().map(|u| format!("{}/tx/{}", u, tx_hash))
+    }
+
+    /// ERC20 合约：ERC20_CONTRACT_ADDRESS，未设置时取网络默认稳定币；第二项表示是否来自网络默认
+    pub fn erc20_contract(&self) -> Result<(String, bool), String> {
+        if let Ok(c) = env::var("ERC20_CONTRACT_ADDRESS") {
+            return Ok((c, false));
+        }
+        self.stablecoin
+            .clone()
+            .map(|c| (c, true))
+            .ok_or_else(|| format!("网络 {} 未配置默认稳定币合约，请设置 ERC20_CONTRACT_ADDRESS", self.key))
     }
 }
 
-/// 从环境变量 EVM_NETWORK 解析当前 EVM 网络，默认 sepolia
-pub fn current_evm_network() -> EvmNetwork {
-    match env::var("EVM_NETWORK").as_deref() {
-        Ok("arbitrum-sepolia") => EvmNetwork::ArbitrumSepolia,
-        Ok("arbitrum-one") => EvmNetwork::ArbitrumOne,
-        Ok("mainnet") => EvmNetwork::Mainnet,
-        _ => EvmNetwork::Sepolia,
+/// 自定义 EVM 网络文件路径（EVM_NETWORKS_FILE，默认当前目录下 evm-networks.json，不存在时视为无自定义网络）
+fn evm_networks_path() -> (String, bool) {
+    match env::var("EVM_NETWORKS_FILE") {
+        Ok(p) => (p, true),
+        Err(_) => ("evm-networks.json".to_string(), false),
     }
 }
 
-/// 未设置 EVM_RPC_URL 时，从 SDK 备选列表中选第一个可用的 RPC（单次健康检查 8 秒超时）
-pub async fn evm_rpc_url(network: EvmNetwork) -> String {
-    if let Ok(url) = env::var("EVM_RPC_URL") {
-        return url;
+/// 全部可用 EVM 网络：内置网络在前，自定义网络按名称排序在后
+pub fn evm_networks() -> Result<Vec<EvmNetworkConfig>, String> {
+    let mut networks: Vec<EvmNetworkConfig> = BUILTIN_EVM_NETWORKS
+        .iter()
+        .map(|(key, network, chain_id, explorer)| EvmNetworkConfig::builtin(key, *network, *chain_id, explorer))
+        .collect();
+    let (path, explicit) = evm_networks_path();
+    let text = match std::fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(_) if !explicit => return Ok(networks),
+        Err(e) => return Err(format!("读取 EVM_NETWORKS_FILE（{}）失败: {}", path, e)),
+    };
+    let v: Value = serde_json::from_str(&text).map_err(|e| format!("{} 不是合法 JSON: {}", path, e))?;
+    let defs = v
+        .as_object()
+        .ok_or_else(|| format!("{} 应为以网络名称为键的 JSON 对象", path))?;
+    for (key, def) in defs {
+        if networks.iter().any(|n| &n.key == key) {
+            return Err(format!("{} 中的网络 {} 与内置网络重名", path, key));
+        }
+        networks.push(EvmNetworkConfig::custom(key, def)?);
+    }
+    Ok(networks)
+}
+
+/// 从环境变量 EVM_NETWORK 解析当前 EVM 网络，默认 sepolia；名称未知时报错并列出可用网络
+pub fn current_evm_network() -> Result<EvmNetworkConfig, String> {
+    let key = env::var("EVM_NETWORK").unwrap_or_else(|_| "sepolia".to_string());
+    let networks = evm_networks()?;
+    let names: Vec<&str> = networks.iter().map(|n| n.key.as_str()).collect();
+    let names = names.join(" | ");
+    networks
+        .iter()
+        .find(|n| n.key == key.trim())
+        .cloned()
+        .ok_or_else(|| format!("未知的 EVM_NETWORK: {}（可选: {}）", key, names))
+}
+
+/// 网络的全部 EVM RPC 节点：EVM_RPC_URL 中的节点在前，其后为网络备选列表（去重），供 `consistency` 逐节点比对
+pub fn evm_all_urls(network: &EvmNetworkConfig) -> Vec<String> {
+    let mut urls = env::var("EVM_RPC_URL").map(|u| split_urls(&u)).unwrap_or_default();
+    for url in &network.rpc_urls {
+        if !urls.contains(url) {
+            urls.push(url.clone());
+        }
+    }
+    urls
+}
+
+/// EVM RPC 主节点：EVM_RPC_URL（可逗号分隔多个，取第一个）；未设置时优先使用 `rpc probe` 选出的节点，
+/// 否则从网络备选列表中选第一个可用的 RPC（单次健康检查 8 秒超时）
+///
+/// 同一列表中的其余节点登记为故障转移备用（见 rpc::with_failover）。
+pub async fn evm_rpc_url(network: &EvmNetworkConfig) -> String {
+    if let Ok(urls) = env::var("EVM_RPC_URL") {
+        let urls = split_urls(&urls);
+        let primary = urls.first().cloned().unwrap_or_default();
+        rpc::register_fallbacks(&primary, &urls, "evm");
+        return primary;
+    }
+    if let Some(primary) = probed_endpoint("evm", &network.key).filter(|p| network.rpc_urls.contains(p)) {
+        rpc::register_fallbacks(&primary, &network.rpc_urls, "evm");
+        return primary;
     }
-    for url in network.urls() {
-        let p = EvmRpcProvider::new((*url).to_string());
+    let mut primary = network.rpc_urls.first().cloned().unwrap_or_default();
+    for url in &network.rpc_urls {
+        let p = EvmRpcProvider::new(url.clone());
         match tokio::time::timeout(Duration::from_secs(8), p.health_check()).await {
-            Ok(Ok(true)) => return url.to_string(),
+            Ok(Ok(true)) => {
+                primary = url.clone();
+                break;
+            }
             _ => {}
         }
     }
-    network.url().to_string()
+    rpc::register_fallbacks(&primary, &network.rpc_urls, "evm");
+    primary
 }
@@ -0,0 +1,341 @@
+//! 多节点一致性检查：`consistency <地址> [代币合约...]` 对同一网络的每个端点查询同一地址的状态并比对
+//!
+//! EVM：取各端点链头的最小值作为共同区块（或 `--block N`），在该区块上查询区块哈希、原生币余额（eth_getBalance）、
+//! nonce（eth_getTransactionCount）与代币 balanceOf（eth_call）。这些是 SDK `get_balance` / `erc20_balance_of` 的原始 RPC
+//! 等价调用——SDK 接口既不能指定区块，也不能指定端点。
+//! Tron 节点不提供历史状态，改为经 /walletsolidity 读取各端点最新固化区块上的 TRX 余额与 TRC20 balanceOf
+//! （对应 SDK `get_balance` / `trc20_balance_of`），并记录读取前后的固化高度。
+//!
+//! 每项结果按取值分组，与多数结果不同或查询失败的端点列为可疑端点，
+//! 用于在信任某个节点做入账判定之前发现滞后或返回陈旧状态的节点。
+
+use crate::abi;
+use crate::config;
+use crate::evm_rpc::EvmRpc;
+use crate::rpc;
+use crate::tron_rpc::TronApi;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::env;
+use tokio::task::JoinSet;
+
+/// balanceOf(address)
+const BALANCE_OF_SELECTOR: &str = "70a08231";
+
+/// 一项检查在各端点的结果（与端点列表同序）
+struct Check {
+    name: String,
+    values: Vec<Result<String, String>>,
+}
+
+impl Check {
+    /// 按取值分组，端点数多的在前
+    fn groups(&self) -> Vec<(&str, Vec<usize>)> {
+        let mut map: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (i, v) in self.values.iter().enumerate() {
+            if let Ok(v) = v {
+                map.entry(v.as_str()).or_default().push(i);
+            }
+        }
+        let mut groups: Vec<(&str, Vec<usize>)> = map.into_iter().collect();
+        groups.sort_by_key(|g| std::cmp::Reverse(g.1.len()));
+        groups
+    }
+
+    /// 各端点取值相同且全部查询成功
+    fn consistent(&self) -> bool {
+        self.groups().len() <= 1 && self.values.iter().all(|v| v.is_ok())
+    }
+
+    /// 可疑端点：查询失败，或不在唯一的多数分组中（最大分组并列时全部不一致的端点都可疑）
+    fn suspects(&self) -> Vec<usize> {
+        let groups = self.groups();
+        let majority = match groups.as_slice() {
+            [first, second, ..] if first.1.len() == second.1.len() => None,
+            [first, ..] => Some(&first.1),
+            [] => None,
+        };
+        (0..self.values.len())
+            .filter(|i| self.values[*i].is_err() || (groups.len() > 1 && majority.is_none_or(|m| !m.contains(i))))
+            .collect()
+    }
+
+    fn print(&self, urls: &[String]) {
+        let groups = self.groups();
+        if self.consistent() {
+            let value = groups.first().map(|g| g.0).unwrap_or("-");
+            println!("[{}] ✓ {}（{} 个端点一致）", self.name, value, urls.len());
+            return;
+        }
+        println!("[{}] ⚠ 不一致", self.name);
+        for (value, members) in &groups {
+            let names: Vec<&str> = members.iter().map(|i| urls[*i].as_str()).collect();
+            println!("  {}  ← {}", value, names.join(", "));
+        }
+        for (i, v) in self.values.iter().enumerate() {
+            if let Err(e) = v {
+                println!("  ❌ {}: {}", urls[i], e);
+            }
+        }
+    }
+}
+
+/// `consistency` 入口：0x 开头的地址按 EVM_NETWORK 检查，否则按 TRON_NETWORK 检查
+pub async fn run_consistency(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut block = None;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let value = if arg == "--block" {
+            iter.next().map(|v| v.as_str()).unwrap_or("")
+        } else if let Some(v) = arg.strip_prefix("--block=") {
+            v
+        } else {
+            positional.push(arg.trim().to_string());
+            continue;
+        };
+        block = Some(
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("--block 应为非负整数，当前: {}", value))?,
+        );
+    }
+    let (address, tokens) = match positional.split_first() {
+        Some((address, tokens)) => (address.clone(), tokens.to_vec()),
+        None => {
+            eprintln!("用法: cargo run -- consistency <地址> [代币合约...] [--block N]");
+            eprintln!("  对当前网络的每个 RPC 端点查询同一地址的余额等状态并比对，列出返回不一致的端点");
+            eprintln!("  0x 开头按 EVM_NETWORK（同一区块），否则按 TRON_NETWORK（各端点最新固化区块）");
+            eprintln!("  未指定代币时使用 ERC20_CONTRACT_ADDRESS / TRC20_CONTRACT_ADDRESS 或网络默认稳定币");
+            return Ok(());
+        }
+    };
+    if address.starts_with("0x") || address.starts_with("0X") {
+        check_evm(&address, tokens, block).await
+    } else {
+        if block.is_some() {
+            println!("Tron 节点不提供历史状态，忽略 --block，按各端点最新固化区块比对");
+        }
+        check_tron(&address, tokens).await
+    }
+}
+
+/// EVM：在共同区块上逐端点查询
+async fn check_evm(address: &str, mut tokens: Vec<String>, block: Option<u64>) -> Result<(), Box<dyn std::error::Error>> {
+    let owner: [u8; 20] = abi::hex_to_bytes(address)?
+        .try_into()
+        .map_err(|_| format!("EVM 地址应为 20 字节: {}", address))?;
+    let network = config::current_evm_network()?;
+    if tokens.is_empty() {
+        if let Ok((contract, _)) = network.erc20_contract() {
+            tokens.push(contract);
+        }
+    }
+    let urls = config::evm_all_urls(&network);
+    rpc::register_family("evm", &urls);
+
+    println!("=== 多节点一致性检查（EVM {}）===", network.name());
+    println!("地址: {}", address);
+    println!("端点: {} 个", urls.len());
+
+    let mut tasks = JoinSet::new();
+    for (i, url) in urls.iter().enumerate() {
+        let rpc = EvmRpc::direct(url.clone());
+        tasks.spawn(async move { (i, rpc.block_number().await.map_err(|e| e.to_string())) });
+    }
+    let mut heads: Vec<Result<u64, String>> = urls.iter().map(|_| Err(String::new())).collect();
+    while let Some(joined) = tasks.join_next().await {
+        let (i, head) = joined?;
+        heads[i] = head;
+    }
+    let source = if block.is_some() { "--block 指定" } else { "各端点链头的最小值" };
+    let block = match block.or_else(|| heads.iter().filter_map(|h| h.as_ref().ok()).min().copied()) {
+        Some(b) => b,
+        None => {
+            println!("❌ 所有端点的链头查询均失败");
+            for (url, head) in urls.iter().zip(&heads) {
+                if let Err(e) = head {
+                    println!("  {}: {}", url, e);
+                }
+            }
+            return Ok(());
+        }
+    };
+    println!("比对区块: {}（{}）", block, source);
+    println!();
+    println!("[链头]");
+    for (url, head) in urls.iter().zip(&heads) {
+        match head {
+            Ok(h) => println!("  {}  {}（领先比对区块 {}）", url, h, h.saturating_sub(block)),
+            Err(e) => println!("  {}  ❌ {}", url, e),
+        }
+    }
+
+    let mut names = vec![
+        format!("区块哈希 #{}", block),
+        format!("{} 余额（wei）", network.native_symbol),
+        "nonce".to_string(),
+    ];
+    names.extend(tokens.iter().map(|t| format!("代币 {} balanceOf", t)));
+
+    let mut tasks = JoinSet::new();
+    for (i, url) in urls.iter().enumerate() {
+        let (rpc, address, tokens) = (EvmRpc::direct(url.clone()), address.to_string(), tokens.clone());
+        tasks.spawn(async move {
+            let tag = abi::to_hex_quantity(block as u128);
+            let mut values = Vec::new();
+            values.push(match rpc.get_block_by_number(block, false).await {
+                Ok(b) => b
+                    .get("hash")
+                    .and_then(|h| h.as_str())
+                    .map(|h| h.to_lowercase())
+                    .ok_or_else(|| "节点没有该区块".to_string()),
+                Err(e) => Err(e.to_string()),
+            });
+            values.push(rpc.get_balance(&address, &tag).await.map(|w| w.to_string()).map_err(|e| e.to_string()));
+            values.push(
+                rpc.get_transaction_count(&address, &tag)
+                    .await
+                    .map(|n| n.to_string())
+                    .map_err(|e| e.to_string()),
+            );
+            for token in &tokens {
+                let data = format!("0x{}{}", BALANCE_OF_SELECTOR, hex::encode(abi::encode_address(&owner)));
+                values.push(match rpc.call(&json!({ "to": token, "data": data }), &tag).await {
+                    Ok(out) => abi::word(&out, 0)
+                        .and_then(abi::uint_bytes_to_u128)
+                        .map(|b| b.to_string())
+                        .ok_or_else(|| format!("balanceOf 返回值无法解析: 0x{}", hex::encode(&out))),
+                    Err(e) => Err(e.to_string()),
+                });
+            }
+            (i, values)
+        });
+    }
+    let checks = collect_checks(names, &urls, tasks).await?;
+    print_report(&urls, &checks, None);
+    Ok(())
+}
+
+/// Tron：逐端点读取最新固化状态
+async fn check_tron(address: &str, mut tokens: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let owner = abi::tron_address_to_bytes(address)?;
+    let network = config::current_tron_network();
+    if tokens.is_empty() {
+        tokens.push(env::var("TRC20_CONTRACT_ADDRESS").unwrap_or_else(|_| network.usdt_contract().to_string()));
+    }
+    let urls = config::tron_all_urls(network);
+    rpc::register_family("tron", &urls);
+
+    println!("=== 多节点一致性检查（Tron {}）===", network.name());
+    println!("地址: {}", address);
+    println!("端点: {} 个", urls.len());
+    println!("比对状态: 各端点最新固化区块（/walletsolidity）");
+    println!();
+
+    let mut names = vec!["TRX 余额（sun）".to_string()];
+    names.extend(tokens.iter().map(|t| format!("TRC20 {} balanceOf", t)));
+    names.push("固化高度（读取前→后）".to_string());
+
+    let mut tasks = JoinSet::new();
+    for (i, url) in urls.iter().enumerate() {
+        let (api, address, tokens) = (TronApi::direct(url.clone()), address.to_string(), tokens.clone());
+        tasks.spawn(async move {
+            let before = api.solidified_block_number().await;
+            let mut values = Vec::new();
+            values.push(
+                api.solidified_account_balance(&address)
+                    .await
+                    .map(|b| b.to_string())
+                    .map_err(|e| e.to_string()),
+            );
+            for token in &tokens {
+                let param = hex::encode(abi::encode_address(&owner));
+                values.push(match api.solidified_constant_call(&address, token, "balanceOf(address)", &param).await {
+                    Ok(out) => abi::word(&out, 0)
+                        .and_then(abi::uint_bytes_to_u128)
+                        .map(|b| b.to_string())
+                        .ok_or_else(|| format!("balanceOf 返回值无法解析: {}", hex::encode(&out))),
+                    Err(e) => Err(e.to_string()),
+                });
+            }
+            let after = api.solidified_block_number().await;
+            values.push(match (before, after) {
+                (Ok(b), Ok(a)) if a == b => Ok(b.to_string()),
+                (Ok(b), Ok(a)) => Ok(format!("{}→{}", b, a)),
+                (Err(e), _) | (_, Err(e)) => Err(e.to_string()),
+            });
+            (i, values)
+        });
+    }
+    let mut checks = collect_checks(names, &urls, tasks).await?;
+    let heights = checks.pop();
+    print_report(&urls, &checks, heights.as_ref());
+    Ok(())
+}
+
+/// 汇总各端点任务的结果为按检查项组织的列表
+async fn collect_checks(
+    names: Vec<String>,
+    urls: &[String],
+    mut tasks: JoinSet<(usize, Vec<Result<String, String>>)>,
+) -> Result<Vec<Check>, Box<dyn std::error::Error>> {
+    let mut checks: Vec<Check> = names
+        .into_iter()
+        .map(|name| Check { name, values: urls.iter().map(|_| Err("未查询".to_string())).collect() })
+        .collect();
+    while let Some(joined) = tasks.join_next().await {
+        let (i, values) = joined?;
+        for (check, value) in checks.iter_mut().zip(values) {
+            check.values[i] = value;
+        }
+    }
+    Ok(checks)
+}
+
+/// 输出各项结果与结论；heights 为 Tron 的固化高度（仅作参考，不计入一致性）
+fn print_report(urls: &[String], checks: &[Check], heights: Option<&Check>) {
+    if let Some(heights) = heights {
+        println!("[{}]", heights.name);
+        for (url, h) in urls.iter().zip(&heights.values) {
+            match h {
+                Ok(h) => println!("  {}  {}", url, h),
+                Err(e) => println!("  {}  ❌ {}", url, e),
+            }
+        }
+    }
+    for check in checks {
+        check.print(urls);
+    }
+
+    println!();
+    println!("=== 结论 ===");
+    if urls.len() < 2 {
+        println!("⚠ 只有 {} 个端点，无法比对；可在 EVM_RPC_URL / TRON_RPC_URL 中以逗号追加端点", urls.len());
+        return;
+    }
+    let inconsistent = checks.iter().filter(|c| !c.consistent()).count();
+    if inconsistent == 0 {
+        println!("✅ {} 项检查在 {} 个端点上一致", checks.len(), urls.len());
+        return;
+    }
+    println!("⚠ {} / {} 项检查不一致", inconsistent, checks.len());
+    let mut suspects: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for check in checks {
+        for i in check.suspects() {
+            suspects.entry(i).or_default().push(check.name.as_str());
+        }
+    }
+    if !suspects.is_empty() {
+        println!("可疑端点（与多数结果不同或查询失败，入账判定前勿使用）:");
+        for (i, names) in suspects {
+            println!("  {}  {}", urls[i], names.join("、"));
+        }
+    }
+    if let Some(heights) = heights {
+        if heights.groups().len() > 1 {
+            println!("各端点固化高度不同，余额差异可能来自高度差，可稍后重新运行确认");
+        }
+    }
+}
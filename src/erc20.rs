@@ -2,21 +2,30 @@
 //!
 //! 只读查询、构建交易、全自动流程（构建 → 签名 → 广播 → 监听）
 
+use crate::arbitrum;
 use crate::config;
-use chains_sdk::chain::evm::{ethereum_address_from_private_key, sign_ethereum_transaction, EvmChain};
-use chains_sdk::Blockchain;
-use chains_sdk::rpc::chains::evm::{EvmNetwork, EvmRpcProvider};
-use chains_sdk::transaction::{TransactionMonitor, TransactionSender, TransactionStatus};
+use crate::evm_chain::{self, ChainIdentity};
+pub use crate::evm_chain::signer_address;
+use crate::evm_fees::{self, FeeSettings};
+use crate::evm_monitor::{self, ConfirmationStatus};
+use crate::evm_replace::{self, AutoBump};
+use crate::evm_rpc::{self, EvmRpc};
+use crate::ledger::{self, TxInfo};
+use crate::nonce_manager;
+use crate::receipt;
+use crate::sdk::EvmSdk;
+use chains_sdk::chain::evm::{ethereum_address_from_private_key, sign_ethereum_transaction};
 use std::env;
-use std::sync::Arc;
 
-/// 监听 ETH / ERC20 交易确认（Sepolia）
+/// 监听 ETH / ERC20 交易确认（Sepolia），结束后打印交易回执（区块、确认数、gas 与手续费、失败原因、代币事件）
 ///
 /// 环境变量：
 /// - TX_HASH：交易哈希（0x 格式，必填）
 /// - EVM_RPC_URL：可选，默认 Sepolia 备选 RPC
 /// - MONITOR_TIMEOUT_SEC：可选，超时秒数，默认 120
-/// - MONITOR_MIN_CONFIRMATIONS：可选，最少确认数，默认 1
+/// - MONITOR_MIN_CONFIRMATIONS：可选，最少确认数，默认取网络的 finality_depth，未配置时为 1
+///
+/// 确认计数感知区块重组：所在区块被替换时打印重组并重置确认数（见 evm_monitor）。
 pub async fn run_eth_monitor() -> Result<(), Box<dyn std::error::Error>> {
     let tx_hash = match env::var("TX_HASH") {
         Ok(v) => v,
@@ -27,11 +36,9 @@ pub async fn run_eth_monitor() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let network = config::current_evm_network();
-    let rpc_url = config::evm_rpc_url(network).await;
-    let provider = EvmRpcProvider::new(rpc_url.clone());
-    let chain = EvmChain::new(chains_sdk::types::ChainType::Ethereum, Arc::new(provider));
-    let monitor = TransactionMonitor::new(Arc::new(chain));
+    let network = config::current_evm_network()?;
+    let rpc_url = config::evm_rpc_url(&network).await;
+    let rpc = EvmRpc::new(rpc_url.clone());
 
     let timeout_sec = env::var("MONITOR_TIMEOUT_SEC")
         .ok()
@@ -40,22 +47,19 @@ pub async fn run_eth_monitor() -> Result<(), Box<dyn std::error::Error>> {
     let min_confirmations = env::var("MONITOR_MIN_CONFIRMATIONS")
         .ok()
         .and_then(|s| s.parse().ok())
-        .unwrap_or(1);
+        .unwrap_or(network.finality_depth.unwrap_or(1));
 
     println!("=== 监听 ETH/ERC20 交易（{}）===", network.name());
     println!("交易哈希: {}", tx_hash);
     println!("RPC:      {}", rpc_url);
+    ChainIdentity::check(&rpc, &network).await.print();
     println!("超时:     {}s，最少确认: {}", timeout_sec, min_confirmations);
     println!();
 
-    let status = monitor
-        .wait_for_confirmation_with_timeout(&tx_hash, timeout_sec, Some(3000), Some(min_confirmations))
-        .await?;
-
-    match status {
-        TransactionStatus::Confirmed => println!("✅ 交易已确认!"),
-        TransactionStatus::Failed => println!("❌ 交易失败!"),
-        TransactionStatus::Pending => println!("⏳ 超时仍未确认"),
+    let status = evm_monitor::wait_for_confirmation(&rpc, &tx_hash, timeout_sec, 3000, min_confirmations).await;
+    print_confirmation_status(&status);
+    if matches!(status, ConfirmationStatus::Confirmed | ConfirmationStatus::Failed) {
+        receipt::print_evm_receipt(&rpc_url, &tx_hash).await;
     }
 
     Ok(())
@@ -67,23 +71,23 @@ pub async fn run_eth_monitor() -> Result<(), Box<dyn std::error::Error>> {
 /// - ETH_ADDRESS：要查询的地址（0x 格式），未设置时使用示例地址
 /// - EVM_RPC_URL：可选
 pub async fn run_eth_balance() -> Result<(), Box<dyn std::error::Error>> {
-    let network = config::current_evm_network();
-    let rpc_url = config::evm_rpc_url(network).await;
+    let network = config::current_evm_network()?;
+    let rpc_url = config::evm_rpc_url(&network).await;
     let address = env::var("ETH_ADDRESS").unwrap_or_else(|_| config::EVM_EXAMPLE_ADDR.to_string());
 
-    let provider = EvmRpcProvider::new(rpc_url.clone());
-    let chain = EvmChain::new(chains_sdk::types::ChainType::Ethereum, Arc::new(provider.clone()));
+    let sdk = EvmSdk::new(rpc_url.clone());
 
-    println!("=== 原生 ETH 余额（{}）===", network.name());
+    println!("=== 原生 {} 余额（{}）===", network.native_symbol, network.name());
     println!("地址: {}", address);
     println!("RPC:  {}", rpc_url);
+    ChainIdentity::check(&EvmRpc::new(rpc_url.clone()), &network).await.print();
 
-    match chain.get_balance(&address).await {
+    match sdk.native_balance(&address).await {
         Ok(wei) => {
             let wei_u128: u128 = wei.parse::<u128>().unwrap_or(0);
             let eth = wei_u128 as f64 / 1e18;
             println!("余额(wei): {}", wei);
-            println!("余额(ETH): {:.18}", eth);
+            println!("余额({}): {:.18}", network.native_symbol, eth);
         }
         Err(e) => println!("查询失败: {}", e),
     }
@@ -91,14 +95,19 @@ pub async fn run_eth_balance() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-/// 全自动原生 ETH 转账：构建 → 签名 → 广播 → 监听
+/// 全自动原生 ETH 转账：构建 → 模拟 → 签名 → 广播 → 监听
+///
+/// 签名前以 eth_call 在 pending 区块模拟执行，会回滚时打印原因并中止。
 ///
 /// 环境变量：
 /// - ETH_PRIVATE_KEY：发送方私钥（64 位十六进制）
 /// - ETH_FROM_ADDRESS、ETH_TO_ADDRESS：可选
 /// - ETH_AMOUNT_WEI：转账金额（wei，字符串），默认 "1000000000000000"（0.001 ETH）
+/// - EVM_FEE_PRESET、EVM_MAX_FEE_GWEI、EVM_PRIORITY_FEE_GWEI、EVM_GAS_PRICE_GWEI、EVM_GAS_LIMIT：可选，费用控制（见 evm_fees）
+/// - EVM_AUTO_BUMP_SEC、EVM_AUTO_BUMP_MAX、EVM_BUMP_PCT：可选，超时未上链自动提价重发（见 evm_replace）
+/// - NONCE_STATE_FILE：可选，本地 nonce 状态文件（见 nonce_manager）；命令参数 `--nonce N` 显式指定 nonce
 /// - EVM_RPC_URL：可选
-pub async fn run_eth_transfer() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run_eth_transfer(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
     let private_key = match env::var("ETH_PRIVATE_KEY") {
         Ok(v) => v,
         Err(_) => {
@@ -107,62 +116,112 @@ pub async fn run_eth_transfer() -> Result<(), Box<dyn std::error::Error>> {
             return Ok(());
         }
     };
-    let network = config::current_evm_network();
-    let rpc_url = config::evm_rpc_url(network).await;
+    let network = config::current_evm_network()?;
+    let rpc_url = config::evm_rpc_url(&network).await;
     let from_addr = env::var("ETH_FROM_ADDRESS").unwrap_or_else(|_| config::EVM_EXAMPLE_ADDR.to_string());
     let to_addr = env::var("ETH_TO_ADDRESS").unwrap_or_else(|_| config::EVM_EXAMPLE_ADDR.to_string());
     let value_wei = env::var("ETH_AMOUNT_WEI").unwrap_or_else(|_| "1000000000000000".to_string());
 
-    let provider = EvmRpcProvider::new(rpc_url.clone());
-    let chain = EvmChain::new(chains_sdk::types::ChainType::Ethereum, Arc::new(provider.clone()));
+    let sdk = EvmSdk::new(rpc_url.clone());
 
-    println!("=== 全自动原生 ETH 转账（{}，构建 → 签名 → 广播 → 监听）===", network.name());
+    println!("=== 全自动原生 {} 转账（{}，构建 → 模拟 → 签名 → 广播 → 监听）===", network.native_symbol, network.name());
+    let identity = ChainIdentity::check(&EvmRpc::new(rpc_url.clone()), &network).await;
+    identity.print();
+    let chain_id = match identity.require_for_signing() {
+        Ok(id) => id,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
     println!("发送方: {}", from_addr);
     println!("接收方: {}", to_addr);
     println!("金额(wei): {}", value_wei);
 
+    let fees = FeeSettings::from_env(&EvmRpc::new(rpc_url.clone())).await?;
+    let auto_bump = AutoBump::from_env()?;
+    let explicit_nonce = nonce_manager::parse_nonce_arg(args)?;
+
     println!("\n1. 构建原生 ETH 转账交易...");
-    let tx_json = chain
-        .evm_build_native_transfer(&provider, &from_addr, &to_addr, &value_wei, None)
-        .await?;
+    let tx_json = sdk.build_native_transfer(&from_addr, &to_addr, &value_wei).await?;
+    let tx_json = fees.apply(&tx_json)?;
     println!("   构建成功");
+    fees.print();
+    let signer = signer_address(&private_key, &from_addr);
 
-    println!("2. 使用 ETH_PRIVATE_KEY 签名...");
-    let signed_hex = sign_ethereum_transaction(&tx_json, &private_key)?;
+    println!("2. 模拟执行（eth_call @ pending）...");
+    if let Some(reason) = evm_rpc::simulate_built_tx(&EvmRpc::new(rpc_url.clone()), &tx_json, &signer).await? {
+        println!("❌ 模拟执行失败，交易将会回滚，已中止（未签名未广播）: {}", reason);
+        return Ok(());
+    }
+    println!("   模拟通过");
+    // 模拟通过后才写入交易记录：中止的交易不会签名广播，不应留下未完结的“已构建”记录
+    let entry = ledger::track(&TxInfo::evm(&network, &signer, &tx_json));
+    evm_fees::print_worst_case_fee(&tx_json);
+
+    let rpc = EvmRpc::new(rpc_url.clone());
+    arbitrum::print_fee_preview(&rpc, &tx_json).await;
+    let nonce = nonce_manager::reserve(&rpc, network.name(), &signer, explicit_nonce).await?;
+    println!("   nonce: {}，链 ID: {}", nonce, chain_id);
+
+    println!("3. 使用 ETH_PRIVATE_KEY 签名...");
+    // 预留 nonce 之后任一步失败都要释放，否则后续交易会跳过该 nonce 而卡住
+    let signed = nonce_manager::set_nonce(&tx_json, nonce)
+        .and_then(|tx| evm_chain::bind_chain_id(&tx, chain_id))
+        .and_then(|tx| Ok(sign_ethereum_transaction(&tx, &private_key)?))
+        .and_then(|signed| Ok((ledger::evm_tx_hash(&signed)?, signed)));
+    let (signed_hash, signed_hex) = match signed {
+        Ok(s) => s,
+        Err(e) => {
+            nonce_manager::release(network.name(), &signer, nonce).await;
+            return Err(e);
+        }
+    };
     println!("   签名成功");
+    entry.signed(&signed_hash, Some(nonce));
 
-    let chain_arc = Arc::new(chain);
-    let sender = TransactionSender::new(chain_arc.clone());
-    let monitor = TransactionMonitor::new(chain_arc);
-    println!("3. 广播交易...");
-    let tx_hash = match sender.send(&signed_hex).await {
+    println!("4. 广播交易...");
+    let tx_hash = match sdk.send(&signed_hex).await {
         Ok(h) => h,
         Err(e) => {
             println!("❌ 广播失败: {}", e);
+            entry.rejected(&e.to_string());
+            nonce_manager::release(network.name(), &signer, nonce).await;
             let msg = e.to_string();
             if msg.contains("insufficient funds") || msg.contains("balance 0") {
                 println!();
-                println!("提示: 发送方地址需要原生 ETH 支付转账金额 + gas。");
-                if network == EvmNetwork::ArbitrumSepolia {
-                    println!("Arbitrum Sepolia 水龙头: https://faucet.quicknode.com/arbitrum/sepolia");
-                } else {
-                    println!("Sepolia 水龙头: https://sepoliafaucet.com 或 https://www.alchemy.com/faucets/ethereum-sepolia");
+                println!("提示: 发送方地址需要原生 {} 支付转账金额 + gas。", network.native_symbol);
+                match network.key.as_str() {
+                    "arbitrum-sepolia" => println!("Arbitrum Sepolia 水龙头: https://faucet.quicknode.com/arbitrum/sepolia"),
+                    "sepolia" => println!("Sepolia 水龙头: https://sepoliafaucet.com 或 https://www.alchemy.com/faucets/ethereum-sepolia"),
+                    _ => {}
                 }
             }
             return Ok(());
         }
     };
     println!("   交易哈希: {}", tx_hash);
-
-    println!("4. 等待确认（超时 120s，轮询 3s）...");
-    let status = monitor
-        .wait_for_confirmation_with_timeout(&tx_hash, 120, Some(3000), Some(1))
-        .await?;
-    match status {
-        TransactionStatus::Confirmed => println!("✅ 交易已确认!"),
-        TransactionStatus::Failed => println!("❌ 交易失败!"),
-        TransactionStatus::Pending => println!("⏳ 超时仍未确认"),
+    if let Some(url) = network.tx_url(&tx_hash) {
+        println!("   浏览器: {}", url);
     }
+    nonce_manager::record(network.name(), &signer, nonce, &tx_hash).await;
+    entry.broadcast(&tx_hash);
+
+    let min_confirmations = network.finality_depth.unwrap_or(1);
+    match &auto_bump {
+        Some(b) => println!(
+            "5. 等待 {} 个确认（超时 120s，轮询 3s；超过 {}s 未上链自动提价，最多 {} 次）...",
+            min_confirmations, b.wait_sec, b.max_bumps
+        ),
+        None => println!("5. 等待 {} 个确认（超时 120s，轮询 3s）...", min_confirmations),
+    }
+    let (mined, status) =
+        evm_replace::wait_for_any(&rpc, &private_key, vec![tx_hash.clone()], 120, 3000, min_confirmations, auto_bump).await;
+    if mined != tx_hash {
+        println!("   上链交易为提价后的 {}", mined);
+    }
+    print_confirmation_status(&status);
+    ledger::finish_evm(&rpc, &mined, &status).await;
 
     Ok(())
 }
@@ -170,56 +229,50 @@ pub async fn run_eth_transfer() -> Result<(), Box<dyn std::error::Error>> {
 /// ERC20 查询与构建交易示例（不签名不广播）
 ///
 /// 环境变量：
-/// - ERC20_CONTRACT_ADDRESS：代币合约地址（0x 格式），未设置时使用 SDK EvmNetwork::Sepolia.usdt_contract()
-/// - EVM_RPC_URL：可选，未设置时从 SDK EvmNetwork::Sepolia.urls() 依次尝试直到可用
+/// - ERC20_CONTRACT_ADDRESS：代币合约地址（0x 格式），未设置时使用当前网络的默认稳定币合约
+/// - EVM_RPC_URL：可选，未设置时从当前网络的备选 RPC 依次尝试直到可用
 pub async fn run_erc20_demo() -> Result<(), Box<dyn std::error::Error>> {
-    let network = config::current_evm_network();
-    let rpc_url = config::evm_rpc_url(network).await;
-    let from_sdk = env::var("ERC20_CONTRACT_ADDRESS").is_err();
-    let contract = env::var("ERC20_CONTRACT_ADDRESS")
-        .unwrap_or_else(|_| network.usdt_contract().to_string());
+    let network = config::current_evm_network()?;
+    let rpc_url = config::evm_rpc_url(&network).await;
+    let (contract, from_network) = network.erc20_contract()?;
 
-    let provider = EvmRpcProvider::new(rpc_url.clone());
-    let chain = EvmChain::new(chains_sdk::types::ChainType::Ethereum, Arc::new(provider.clone()));
+    let sdk = EvmSdk::new(rpc_url.clone());
 
     println!("=== ERC20 代币功能示例（{}）===", network.name());
     println!("地址: {}", config::EVM_EXAMPLE_ADDR);
-    if from_sdk {
-        println!("合约: {}（来自 SDK {} 默认）", contract, network.name());
+    if from_network {
+        println!("合约: {}（网络 {} 默认稳定币）", contract, network.name());
     } else {
         println!("合约: {}", contract);
     }
     println!("RPC:  {}", rpc_url);
 
-    match chain.erc20_balance_of(&provider, config::EVM_EXAMPLE_ADDR, &contract).await {
+    match sdk.erc20_balance_of(config::EVM_EXAMPLE_ADDR, &contract).await {
         Ok(balance) => println!("余额: {}", balance),
         Err(e) => println!("查询余额失败: {}", e),
     }
 
-    match chain.erc20_symbol(&provider, &contract).await {
+    match sdk.erc20_symbol(&contract).await {
         Ok(symbol) => println!("符号: {}", symbol),
         Err(e) => println!("查询符号失败: {}", e),
     }
 
-    match chain.erc20_decimals(&provider, &contract).await {
+    match sdk.erc20_decimals(&contract).await {
         Ok(decimals) => println!("精度: {}", decimals),
         Err(e) => println!("查询精度失败: {}", e),
     }
 
-    match chain.erc20_name(&provider, &contract).await {
+    match sdk.erc20_name(&contract).await {
         Ok(name) => println!("名称: {}", name),
         Err(e) => println!("查询名称失败: {}", e),
     }
 
-    match chain.erc20_total_supply(&provider, &contract).await {
+    match sdk.erc20_total_supply(&contract).await {
         Ok(supply) => println!("总供应量: {}", supply),
         Err(e) => println!("查询总供应失败: {}", e),
     }
 
-    match chain
-        .erc20_build_transfer(&provider, config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract, "0", None)
-        .await
-    {
+    match sdk.erc20_build_transfer(config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract, "0").await {
         Ok(tx_json) => {
             println!("构建 ERC20 转账交易成功 (未签名未发送)");
             println!("交易 JSON 长度: {} 字节", tx_json.len());
@@ -232,13 +285,11 @@ pub async fn run_erc20_demo() -> Result<(), Box<dyn std::error::Error>> {
 
 /// 按 SDK 验证 ERC20 API（Sepolia 测试网）
 pub async fn run_verify_erc20() -> Result<(), Box<dyn std::error::Error>> {
-    let network = config::current_evm_network();
-    let rpc_url = config::evm_rpc_url(network).await;
-    let contract = env::var("ERC20_CONTRACT_ADDRESS")
-        .unwrap_or_else(|_| network.usdt_contract().to_string());
+    let network = config::current_evm_network()?;
+    let rpc_url = config::evm_rpc_url(&network).await;
+    let (contract, _) = network.erc20_contract()?;
 
-    let provider = EvmRpcProvider::new(rpc_url.clone());
-    let chain = EvmChain::new(chains_sdk::types::ChainType::Ethereum, Arc::new(provider.clone()));
+    let sdk = EvmSdk::new(rpc_url.clone());
 
     println!("=== 根据 SDK 验证 ERC20 API（{}）===", network.name());
     println!("地址: {}", config::EVM_EXAMPLE_ADDR);
@@ -248,67 +299,56 @@ pub async fn run_verify_erc20() -> Result<(), Box<dyn std::error::Error>> {
     let mut ok = 0;
     let mut fail = 0;
 
-    if chain.erc20_balance_of(&provider, config::EVM_EXAMPLE_ADDR, &contract).await.is_ok() {
+    if sdk.erc20_balance_of(config::EVM_EXAMPLE_ADDR, &contract).await.is_ok() {
         println!("✅ erc20_balance_of");
         ok += 1;
     } else {
         println!("❌ erc20_balance_of");
         fail += 1;
     }
-    if chain.erc20_symbol(&provider, &contract).await.is_ok() {
+    if sdk.erc20_symbol(&contract).await.is_ok() {
         println!("✅ erc20_symbol");
         ok += 1;
     } else {
         println!("❌ erc20_symbol");
         fail += 1;
     }
-    if chain.erc20_decimals(&provider, &contract).await.is_ok() {
+    if sdk.erc20_decimals(&contract).await.is_ok() {
         println!("✅ erc20_decimals");
         ok += 1;
     } else {
         println!("❌ erc20_decimals");
         fail += 1;
     }
-    if chain.erc20_name(&provider, &contract).await.is_ok() {
+    if sdk.erc20_name(&contract).await.is_ok() {
         println!("✅ erc20_name");
         ok += 1;
     } else {
         println!("❌ erc20_name");
         fail += 1;
     }
-    if chain.erc20_total_supply(&provider, &contract).await.is_ok() {
+    if sdk.erc20_total_supply(&contract).await.is_ok() {
         println!("✅ erc20_total_supply");
         ok += 1;
     } else {
         println!("❌ erc20_total_supply");
         fail += 1;
     }
-    if chain
-        .erc20_allowance(&provider, config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract)
-        .await
-        .is_ok()
-    {
+    if sdk.erc20_allowance(config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract).await.is_ok() {
         println!("✅ erc20_allowance");
         ok += 1;
     } else {
         println!("❌ erc20_allowance");
         fail += 1;
     }
-    if chain
-        .erc20_token_info(&provider, config::EVM_EXAMPLE_ADDR, &contract)
-        .await
-        .is_ok()
-    {
+    if sdk.erc20_token_info(config::EVM_EXAMPLE_ADDR, &contract).await.is_ok() {
         println!("✅ erc20_token_info");
         ok += 1;
     } else {
         println!("❌ erc20_token_info");
         fail += 1;
     }
-    match chain
-        .erc20_build_transfer(&provider, config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract, "0", None)
-        .await
-    {
+    match sdk.erc20_build_transfer(config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract, "0").await {
         Ok(tx_json) => {
             if serde_json::from_str::<serde_json::Value>(&tx_json).is_ok() {
                 println!("✅ erc20_build_transfer");
@@ -323,10 +363,7 @@ pub async fn run_verify_erc20() -> Result<(), Box<dyn std::error::Error>> {
             fail += 1;
         }
     }
-    match chain
-        .erc20_build_approve(&provider, config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract, "0", None)
-        .await
-    {
+    match sdk.erc20_build_approve(config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract, "0").await {
         Ok(tx_json) => {
             if serde_json::from_str::<serde_json::Value>(&tx_json).is_ok() {
                 println!("✅ erc20_build_approve");
@@ -341,10 +378,7 @@ pub async fn run_verify_erc20() -> Result<(), Box<dyn std::error::Error>> {
             fail += 1;
         }
     }
-    match chain
-        .erc20_build_transfer_from(&provider, config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract, "0", None)
-        .await
-    {
+    match sdk.erc20_build_transfer_from(config::EVM_EXAMPLE_ADDR, config::EVM_EXAMPLE_ADDR, &contract, "0").await {
         Ok(tx_json) => {
             if serde_json::from_str::<serde_json::Value>(&tx_json).is_ok() {
                 println!("✅ erc20_build_transfer_from");
@@ -369,6 +403,16 @@ pub async fn run_verify_erc20() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// 打印监听结果
+fn print_confirmation_status(status: &ConfirmationStatus) {
+    match status {
+        ConfirmationStatus::Confirmed => println!("✅ 交易已确认!"),
+        ConfirmationStatus::Failed => println!("❌ 交易失败!"),
+        ConfirmationStatus::Pending => println!("⏳ 超时仍未确认"),
+        ConfirmationStatus::Reorged => println!("⚠ 交易所在区块已被重组，超时时交易不在规范链上（可能已被丢弃或仍在交易池）"),
+    }
+}
+
 /// 将“代币数量”（如 120 USDT）按精度换算为最小单位（raw）。
 /// 例如 decimals=6 时 120 → 120_000_000
 fn human_amount_to_raw(human: &str, decimals: u8) -> Result<String, Box<dyn std::error::Error>> {
@@ -384,15 +428,21 @@ fn human_amount_to_raw(human: &str, decimals: u8) -> Result<String, Box<dyn std:
     Ok(raw_u128.to_string())
 }
 
-/// 全自动 ERC20 流程：构建 → 签名 → 广播 → 监听
+/// 全自动 ERC20 流程：构建 → 模拟 → 签名 → 广播 → 监听
+///
+/// 构建前检查代币余额是否足够；签名前以 eth_call 模拟执行，
+/// 会回滚（余额不足、代币暂停、黑名单等）时打印解析后的原因并中止。
 ///
 /// 环境变量：
 /// - ETH_PRIVATE_KEY：发送方私钥，64 位十六进制
 /// - ERC20_CONTRACT_ADDRESS：代币合约地址
 /// - ETH_FROM_ADDRESS、ETH_TO_ADDRESS：可选
 /// - ERC20_AMOUNT：代币数量（人类可读），如 120 表示 120 USDT（按合约精度换算）；默认 "0"
+/// - EVM_FEE_PRESET、EVM_MAX_FEE_GWEI、EVM_PRIORITY_FEE_GWEI、EVM_GAS_PRICE_GWEI、EVM_GAS_LIMIT：可选，费用控制（见 evm_fees）
+/// - EVM_AUTO_BUMP_SEC、EVM_AUTO_BUMP_MAX、EVM_BUMP_PCT：可选，超时未上链自动提价重发（见 evm_replace）
+/// - NONCE_STATE_FILE：可选，本地 nonce 状态文件（见 nonce_manager）；命令参数 `--nonce N` 显式指定 nonce
 /// - EVM_RPC_URL：可选
-pub async fn run_full_flow_erc20() -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run_full_flow_erc20(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
     let private_key = match env::var("ETH_PRIVATE_KEY") {
         Ok(v) => v,
         Err(_) => {
@@ -401,19 +451,29 @@ pub async fn run_full_flow_erc20() -> Result<(), Box<dyn std::error::Error>> {
             return Ok(());
         }
     };
-    let network = config::current_evm_network();
-    let rpc_url = config::evm_rpc_url(network).await;
-    let contract = env::var("ERC20_CONTRACT_ADDRESS")
-        .unwrap_or_else(|_| network.usdt_contract().to_string());
+    let network = config::current_evm_network()?;
+    let rpc_url = config::evm_rpc_url(&network).await;
+    let (contract, _) = network.erc20_contract()?;
     let from_addr = env::var("ETH_FROM_ADDRESS").unwrap_or_else(|_| config::EVM_EXAMPLE_ADDR.to_string());
     let to_addr = env::var("ETH_TO_ADDRESS").unwrap_or_else(|_| config::EVM_EXAMPLE_ADDR.to_string());
     let amount_human = env::var("ERC20_AMOUNT").unwrap_or_else(|_| "0".to_string());
 
-    let provider = EvmRpcProvider::new(rpc_url.clone());
-    let chain = EvmChain::new(chains_sdk::types::ChainType::Ethereum, Arc::new(provider.clone()));
+    let sdk = EvmSdk::new(rpc_url.clone());
 
-    let decimals = chain.erc20_decimals(&provider, &contract).await?;
+    let identity = ChainIdentity::check(&EvmRpc::new(rpc_url.clone()), &network).await;
+    let chain_id = match identity.require_for_signing() {
+        Ok(id) => id,
+        Err(e) => {
+            identity.print();
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+
+    let decimals = sdk.erc20_decimals(&contract).await?;
     let amount_raw = human_amount_to_raw(&amount_human, decimals)?;
+    // 符号查询失败时交易记录以合约地址作为代币名（同 batch-send）
+    let symbol = sdk.erc20_symbol(&contract).await.unwrap_or_else(|_| contract.clone());
 
     let key_address = ethereum_address_from_private_key(&private_key)
         .unwrap_or_else(|_| "?".to_string());
@@ -421,11 +481,12 @@ pub async fn run_full_flow_erc20() -> Result<(), Box<dyn std::error::Error>> {
     let key_normalized = key_address.trim_start_matches("0x").to_lowercase();
     let key_matches_from = from_normalized == key_normalized;
 
-    println!("=== 全自动 ERC20 流程（构建 → 签名 → 广播 → 监听）===");
+    println!("=== 全自动 ERC20 流程（{}，构建 → 模拟 → 签名 → 广播 → 监听）===", network.name());
+    identity.print();
     println!("发送方: {}", from_addr);
     println!("私钥对应地址: {} {}", key_address, if key_matches_from { "✓" } else { "⚠ 与发送方不一致" });
     println!("接收方: {}", to_addr);
-    println!("合约:   {}", contract);
+    println!("合约:   {}（{}）", contract, symbol);
     println!("金额:   {}（= {} 最小单位，精度 {}）", amount_human, amount_raw, decimals);
     if !key_matches_from {
         println!();
@@ -435,53 +496,112 @@ pub async fn run_full_flow_erc20() -> Result<(), Box<dyn std::error::Error>> {
         println!();
     }
 
+    // 代币余额预检查：链上从「私钥对应地址」转出代币
+    let signer = signer_address(&private_key, &from_addr);
+    let token_balance = sdk.erc20_balance_of(&signer, &contract).await?;
+    let balance_raw: u128 = token_balance
+        .trim()
+        .parse()
+        .map_err(|_| format!("代币余额无法解析: {}", token_balance))?;
+    let amount_raw_u128: u128 = amount_raw
+        .parse()
+        .map_err(|_| format!("转账金额超出数值范围: {}", amount_raw))?;
+    println!("代币余额: {}（最小单位，地址 {}）", token_balance, signer);
+    if balance_raw < amount_raw_u128 {
+        println!("❌ 代币余额不足: 余额 {} < 转账金额 {}（最小单位），已中止", balance_raw, amount_raw_u128);
+        return Ok(());
+    }
+
+    let fees = FeeSettings::from_env(&EvmRpc::new(rpc_url.clone())).await?;
+    let auto_bump = AutoBump::from_env()?;
+    let explicit_nonce = nonce_manager::parse_nonce_arg(args)?;
+
     println!("\n1. 构建 ERC20 转账交易...");
-    let tx_json = chain
-        .erc20_build_transfer(&provider, &from_addr, &to_addr, &contract, &amount_raw, None)
-        .await?;
+    let tx_json = sdk.erc20_build_transfer(&from_addr, &to_addr, &contract, &amount_raw).await?;
+    let tx_json = fees.apply(&tx_json)?;
     println!("   构建成功");
+    fees.print();
 
-    println!("2. 使用 ETH_PRIVATE_KEY 签名...");
-    let signed_hex = sign_ethereum_transaction(&tx_json, &private_key)?;
+    println!("2. 模拟执行（eth_call @ pending）...");
+    if let Some(reason) = evm_rpc::simulate_built_tx(&EvmRpc::new(rpc_url.clone()), &tx_json, &signer).await? {
+        println!("❌ 模拟执行失败，交易将会回滚，已中止（未签名未广播）: {}", reason);
+        return Ok(());
+    }
+    println!("   模拟通过");
+    let entry = ledger::track(&TxInfo::evm(&network, &signer, &tx_json).token(&symbol, decimals as u32));
+    evm_fees::print_worst_case_fee(&tx_json);
+
+    let rpc = EvmRpc::new(rpc_url.clone());
+    arbitrum::print_fee_preview(&rpc, &tx_json).await;
+    let nonce = nonce_manager::reserve(&rpc, network.name(), &signer, explicit_nonce).await?;
+    println!("   nonce: {}，链 ID: {}", nonce, chain_id);
+
+    println!("3. 使用 ETH_PRIVATE_KEY 签名...");
+    // 预留 nonce 之后任一步失败都要释放，否则后续交易会跳过该 nonce 而卡住
+    let signed = nonce_manager::set_nonce(&tx_json, nonce)
+        .and_then(|tx| evm_chain::bind_chain_id(&tx, chain_id))
+        .and_then(|tx| Ok(sign_ethereum_transaction(&tx, &private_key)?))
+        .and_then(|signed| Ok((ledger::evm_tx_hash(&signed)?, signed)));
+    let (signed_hash, signed_hex) = match signed {
+        Ok(s) => s,
+        Err(e) => {
+            nonce_manager::release(network.name(), &signer, nonce).await;
+            return Err(e);
+        }
+    };
     println!("   签名成功");
+    entry.signed(&signed_hash, Some(nonce));
 
-    let chain_arc = Arc::new(chain);
-    let sender = TransactionSender::new(chain_arc.clone());
-    let monitor = TransactionMonitor::new(chain_arc);
-    println!("3. 广播交易...");
-    let tx_hash = match sender.send(&signed_hex).await {
+    println!("4. 广播交易...");
+    let tx_hash = match sdk.send(&signed_hex).await {
         Ok(h) => h,
         Err(e) => {
             println!("❌ 广播失败: {}", e);
+            entry.rejected(&e.to_string());
+            nonce_manager::release(network.name(), &signer, nonce).await;
             let msg = e.to_string();
             if msg.contains("insufficient funds") || msg.contains("balance 0") {
                 println!();
                 println!("提示: 链上从「私钥对应地址」扣 gas，当前私钥对应: {}", key_address);
                 if !key_matches_from {
                     println!("  您设置的发送方是 {}，与私钥对应地址不一致，请确认 ETH_PRIVATE_KEY 对应有 ETH 的地址。", from_addr);
-                } else if network == EvmNetwork::ArbitrumSepolia {
-                    println!("  请确认该地址在 Arbitrum Sepolia 上有少量原生 ETH：");
-                    println!("  - https://faucet.quicknode.com/arbitrum/sepolia");
                 } else {
-                    println!("  请确认该地址在 Sepolia 上有少量原生 ETH：");
-                    println!("  - https://sepoliafaucet.com");
-                    println!("  - https://www.alchemy.com/faucets/ethereum-sepolia");
+                    println!("  请确认该地址在 {} 上有少量原生 {}", network.name(), network.native_symbol);
+                    match network.key.as_str() {
+                        "arbitrum-sepolia" => println!("  - https://faucet.quicknode.com/arbitrum/sepolia"),
+                        "sepolia" => {
+                            println!("  - https://sepoliafaucet.com");
+                            println!("  - https://www.alchemy.com/faucets/ethereum-sepolia");
+                        }
+                        _ => {}
+                    }
                 }
             }
             return Ok(());
         }
     };
     println!("   交易哈希: {}", tx_hash);
-
-    println!("4. 等待确认（超时 120s，轮询 3s）...");
-    let status = monitor
-        .wait_for_confirmation_with_timeout(&tx_hash, 120, Some(3000), Some(1))
-        .await?;
-    match status {
-        TransactionStatus::Confirmed => println!("✅ 交易已确认!"),
-        TransactionStatus::Failed => println!("❌ 交易失败!"),
-        TransactionStatus::Pending => println!("⏳ 超时仍未确认"),
+    if let Some(url) = network.tx_url(&tx_hash) {
+        println!("   浏览器: {}", url);
+    }
+    nonce_manager::record(network.name(), &signer, nonce, &tx_hash).await;
+    entry.broadcast(&tx_hash);
+
+    let min_confirmations = network.finality_depth.unwrap_or(1);
+    match &auto_bump {
+        Some(b) => println!(
+            "5. 等待 {} 个确认（超时 120s，轮询 3s；超过 {}s 未上链自动提价，最多 {} 次）...",
+            min_confirmations, b.wait_sec, b.max_bumps
+        ),
+        None => println!("5. 等待 {} 个确认（超时 120s，轮询 3s）...", min_confirmations),
+    }
+    let (mined, status) =
+        evm_replace::wait_for_any(&rpc, &private_key, vec![tx_hash.clone()], 120, 3000, min_confirmations, auto_bump).await;
+    if mined != tx_hash {
+        println!("   上链交易为提价后的 {}", mined);
     }
+    print_confirmation_status(&status);
+    ledger::finish_evm(&rpc, &mined, &status).await;
 
     Ok(())
 }
@@ -0,0 +1,173 @@
+//! 代币事件日志解析：ERC20 / TRC20 的 Transfer、Approval（EVM 回执 logs 与 Tron gettransactioninfobyid log）
+
+use crate::abi;
+use crate::sdk::{EvmSdk, TronSdk};
+use chains_sdk::rpc::chains::tron::TronNetwork;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Transfer(address,address,uint256) 事件 topic0
+pub const TRANSFER_TOPIC: &str = "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+/// Approval(address,address,uint256) 事件 topic0
+const APPROVAL_TOPIC: &str = "8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TokenEventKind {
+    Transfer,
+    Approval,
+}
+
+/// 解析后的代币事件；地址为所在链的显示格式（EVM 0x / Tron Base58）
+pub struct TokenEvent {
+    pub kind: TokenEventKind,
+    /// 代币合约
+    pub token: String,
+    /// Transfer 的转出方 / Approval 的授权方
+    pub from: String,
+    /// Transfer 的接收方 / Approval 的被授权方
+    pub to: String,
+    /// 金额（最小单位）；超出 u128（如无限授权 2^256-1）时为 None
+    pub amount: Option<u128>,
+}
+
+/// 代币显示信息（符号与精度，查询失败时为空）
+pub struct TokenMeta {
+    pub symbol: String,
+    pub decimals: Option<u32>,
+}
+
+impl TokenMeta {
+    /// 最小单位 → "1.5 USDT（1500000 最小单位）"；精度未知时只给最小单位
+    pub fn format_amount(&self, amount: Option<u128>) -> String {
+        let symbol = if self.symbol.is_empty() { "?" } else { &self.symbol };
+        match (amount, self.decimals) {
+            (Some(raw), Some(decimals)) => format!("{} {}（{} 最小单位）", abi::format_units(raw, decimals), symbol, raw),
+            (Some(raw), None) => format!("{} 最小单位（{}，精度未知）", raw, symbol),
+            (None, _) => format!("超过 2^128（如无限授权）{}", symbol),
+        }
+    }
+}
+
+/// erc20_token_info 查询代币符号与精度（owner 仅用于 SDK 同时返回的余额）
+pub async fn evm_token_meta(sdk: &EvmSdk, owner: &str, contract: &str) -> TokenMeta {
+    match sdk.erc20_token_info(owner, contract).await {
+        Ok(info) => TokenMeta {
+            symbol: info.symbol.unwrap_or_default(),
+            decimals: info.decimals.map(|d| d as u32),
+        },
+        Err(_) => TokenMeta { symbol: String::new(), decimals: None },
+    }
+}
+
+/// trc20_token_info 查询代币符号与精度（owner 仅用于 SDK 同时返回的余额）
+pub async fn tron_token_meta(sdk: &TronSdk, owner: &str, contract: &str) -> TokenMeta {
+    match sdk.trc20_token_info(owner, contract).await {
+        Ok(info) => TokenMeta {
+            symbol: info.symbol.unwrap_or_default(),
+            decimals: info.decimals.map(|d| d as u32),
+        },
+        Err(_) => TokenMeta { symbol: String::new(), decimals: None },
+    }
+}
+
+/// 解析 EVM 回执 logs 中的 Transfer / Approval
+pub fn decode_evm_logs(receipt: &Value) -> Vec<TokenEvent> {
+    logs(receipt, "logs").iter().filter_map(decode_evm_log).collect()
+}
+
+/// 解析单条 EVM 日志（回执 logs 或 eth_getLogs 结果）
+pub fn decode_evm_log(log: &Value) -> Option<TokenEvent> {
+    let token = log.get("address")?.as_str()?.to_lowercase();
+    decode_log(log, token, |addr| format!("0x{}", hex::encode(addr)))
+}
+
+/// 解析 Tron gettransactioninfobyid 的 log（地址为去掉 41 前缀的十六进制）
+pub fn decode_tron_logs(info: &Value) -> Vec<TokenEvent> {
+    logs(info, "log")
+        .iter()
+        .filter_map(|log| {
+            let raw = log.get("address")?.as_str()?;
+            let token = abi::tron_address_from_hex(&format!("41{}", abi::strip_0x(raw))).ok()?;
+            decode_log(log, token, |addr| {
+                abi::tron_address_from_hex(&format!("41{}", hex::encode(addr))).unwrap_or_default()
+            })
+        })
+        .collect()
+}
+
+fn logs<'a>(v: &'a Value, key: &str) -> &'a [Value] {
+    v.get(key).and_then(|l| l.as_array()).map(|a| a.as_slice()).unwrap_or(&[])
+}
+
+/// topic0 匹配 Transfer / Approval 且有两个 indexed 地址时解析，否则返回 None（如 ERC721 Transfer 的 tokenId 在 topic3）
+fn decode_log(log: &Value, token: String, format_addr: impl Fn(&[u8]) -> String) -> Option<TokenEvent> {
+    let topics: Vec<Vec<u8>> = log
+        .get("topics")?
+        .as_array()?
+        .iter()
+        .filter_map(|t| t.as_str().and_then(|t| abi::hex_to_bytes(t).ok()))
+        .collect();
+    if topics.len() != 3 || topics.iter().any(|t| t.len() != 32) {
+        return None;
+    }
+    let kind = match hex::encode(&topics[0]).as_str() {
+        TRANSFER_TOPIC => TokenEventKind::Transfer,
+        APPROVAL_TOPIC => TokenEventKind::Approval,
+        _ => return None,
+    };
+    let data = abi::hex_to_bytes(log.get("data").and_then(|d| d.as_str()).unwrap_or("")).ok()?;
+    let amount_word = abi::word(&data, 0)?;
+    Some(TokenEvent {
+        kind,
+        token,
+        from: format_addr(&topics[1][12..]),
+        to: format_addr(&topics[2][12..]),
+        amount: abi::uint_bytes_to_u128(amount_word),
+    })
+}
+
+/// 以 erc20_token_info 解析代币符号与精度后打印事件
+pub async fn print_evm_token_events(rpc_url: &str, events: &[TokenEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    let sdk = EvmSdk::new(rpc_url.to_string());
+    let mut metas = HashMap::new();
+    for e in events {
+        if metas.contains_key(&e.token) {
+            continue;
+        }
+        let meta = evm_token_meta(&sdk, &e.to, &e.token).await;
+        metas.insert(e.token.clone(), meta);
+    }
+    print_events(events, &metas);
+}
+
+/// 以 trc20_token_info 解析代币符号与精度后打印事件
+pub async fn print_tron_token_events(network: TronNetwork, events: &[TokenEvent]) {
+    if events.is_empty() {
+        return;
+    }
+    let sdk = TronSdk::new(network);
+    let mut metas = HashMap::new();
+    for e in events {
+        if metas.contains_key(&e.token) {
+            continue;
+        }
+        let meta = tron_token_meta(&sdk, &e.to, &e.token).await;
+        metas.insert(e.token.clone(), meta);
+    }
+    print_events(events, &metas);
+}
+
+fn print_events(events: &[TokenEvent], metas: &HashMap<String, TokenMeta>) {
+    println!("\n[代币事件]");
+    for e in events {
+        let amount = metas[&e.token].format_amount(e.amount);
+        match e.kind {
+            TokenEventKind::Transfer => println!("  Transfer {} → {}: {}", e.from, e.to, amount),
+            TokenEventKind::Approval => println!("  Approval {} 授权 {}: {}", e.from, e.to, amount),
+        }
+        println!("    合约: {}", e.token);
+    }
+}
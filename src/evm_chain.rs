@@ -0,0 +1,100 @@
+//! EVM 链身份：以 eth_chainId 校验节点与所选 EVM_NETWORK 是否为同一条链
+//!
+//! 只读命令在不一致时仅提示；签名前必须确认一致，否则拒绝签名（防止 EVM_RPC_URL 指向其他链时把交易广播到错误的网络）。
+//! 确认后的链 ID 写入待签名交易的 chainId（EIP-155 重放保护）。
+
+use crate::abi;
+use crate::config::EvmNetworkConfig;
+use crate::evm_fees;
+use crate::evm_rpc::EvmRpc;
+use chains_sdk::chain::evm::{ethereum_address_from_private_key, EvmChain};
+use chains_sdk::rpc::chains::evm::EvmRpcProvider;
+use chains_sdk::types::ChainType;
+use serde_json::Value;
+use std::env;
+use std::sync::Arc;
+
+/// SDK 链实例；SDK 的 ChainType 只区分链族，所有 EVM 网络均为 Ethereum，具体网络由链 ID 区分
+pub fn sdk_chain(provider: &EvmRpcProvider) -> EvmChain {
+    EvmChain::new(ChainType::Ethereum, Arc::new(provider.clone()))
+}
+
+/// 链上实际发送方：私钥对应地址（0x 格式），无法推导时退回配置的发送方
+pub fn signer_address(private_key: &str, fallback: &str) -> String {
+    match ethereum_address_from_private_key(private_key) {
+        Ok(addr) => format!("0x{}", addr.trim_start_matches("0x")),
+        Err(_) => fallback.to_string(),
+    }
+}
+
+/// 节点链 ID 校验结果
+pub enum ChainIdentity {
+    /// 节点链 ID 与所选网络一致
+    Verified(u64),
+    /// 节点属于其他链
+    Mismatch { expected: u64, actual: u64 },
+    /// eth_chainId 查询失败
+    Unknown { expected: u64, error: String },
+}
+
+impl ChainIdentity {
+    /// 查询 eth_chainId 并与网络配置的链 ID 比对
+    pub async fn check(rpc: &EvmRpc, network: &EvmNetworkConfig) -> Self {
+        let expected = network.chain_id;
+        match rpc.chain_id().await {
+            Ok(actual) if actual == expected => ChainIdentity::Verified(actual),
+            Ok(actual) => ChainIdentity::Mismatch { expected, actual },
+            Err(e) => ChainIdentity::Unknown { expected, error: e.to_string() },
+        }
+    }
+
+    /// 打印链 ID 行，不一致或查询失败时附提示
+    pub fn print(&self) {
+        match self {
+            ChainIdentity::Verified(id) => println!("链 ID: {} ✓", id),
+            ChainIdentity::Mismatch { expected, actual } => {
+                println!("链 ID: {} ⚠ 与所选网络的 {} 不一致，{}", actual, expected, rpc_source_hint())
+            }
+            ChainIdentity::Unknown { expected, error } => {
+                println!("链 ID: 期望 {}，⚠ eth_chainId 查询失败: {}", expected, error)
+            }
+        }
+    }
+
+    /// 签名前调用：链 ID 已确认一致时返回链 ID，否则返回拒绝签名的原因
+    pub fn require_for_signing(&self) -> Result<u64, String> {
+        match self {
+            ChainIdentity::Verified(id) => Ok(*id),
+            ChainIdentity::Mismatch { expected, actual } => Err(format!(
+                "节点链 ID {} 与所选网络的 {} 不一致，{}，拒绝签名",
+                actual,
+                expected,
+                rpc_source_hint()
+            )),
+            ChainIdentity::Unknown { error, .. } => {
+                Err(format!("无法确认节点链 ID（eth_chainId 失败: {}），拒绝签名", error))
+            }
+        }
+    }
+}
+
+/// 不一致时指出 RPC 来源，便于排查
+fn rpc_source_hint() -> String {
+    match env::var("EVM_RPC_URL") {
+        Ok(url) => format!("请检查 EVM_RPC_URL（{}）与 EVM_NETWORK 是否匹配", url),
+        Err(_) => "请检查 EVM_NETWORK".to_string(),
+    }
+}
+
+/// 把链 ID 写入待签名交易 JSON 的 chainId；SDK 已填入不同的链 ID 时报错
+pub fn bind_chain_id(tx_json: &str, chain_id: u64) -> Result<String, Box<dyn std::error::Error>> {
+    let mut tx: Value = serde_json::from_str(tx_json)?;
+    let obj = tx.as_object_mut().ok_or("交易 JSON 不是对象")?;
+    if let Some(existing) = obj.get("chainId").and_then(abi::parse_quantity) {
+        if existing != chain_id as u128 {
+            return Err(format!("交易 JSON 中的 chainId {} 与节点链 ID {} 不一致", existing, chain_id).into());
+        }
+    }
+    evm_fees::set_quantity(obj, "chainId", chain_id as u128);
+    Ok(serde_json::to_string(&tx)?)
+}
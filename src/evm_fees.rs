@@ -0,0 +1,341 @@
+//! EVM 手续费控制：EIP-1559（maxFeePerGas / maxPriorityFeePerGas）、legacy gasPrice、gas 上限覆盖，
+//! 以及由 eth_feeHistory 百分位推导的 slow / normal / fast 档位
+//!
+//! SDK 的构建接口不暴露费用参数，此处在构建得到的交易 JSON 上改写费用字段后再签名；
+//! 写回时沿用原字段的数值格式（0x 十六进制、十进制字符串或数字）。
+
+use crate::abi;
+use crate::evm_rpc::EvmRpc;
+use crate::rpc::RpcError;
+use serde_json::{json, Value};
+use std::env;
+
+/// eth_feeHistory 取样的区块数
+const FEE_HISTORY_BLOCKS: u64 = 20;
+/// slow / normal / fast 对应的优先费百分位
+const PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+/// 1 gwei = 1e9 wei
+const GWEI: u128 = 1_000_000_000;
+
+/// 费用档位
+#[derive(Clone, Copy)]
+pub enum FeePreset {
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl FeePreset {
+    pub const ALL: [FeePreset; 3] = [FeePreset::Slow, FeePreset::Normal, FeePreset::Fast];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "slow" => Some(FeePreset::Slow),
+            "normal" => Some(FeePreset::Normal),
+            "fast" => Some(FeePreset::Fast),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeePreset::Slow => "slow",
+            FeePreset::Normal => "normal",
+            FeePreset::Fast => "fast",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            FeePreset::Slow => 0,
+            FeePreset::Normal => 1,
+            FeePreset::Fast => 2,
+        }
+    }
+}
+
+/// eth_feeHistory 汇总：下一区块 baseFee 与各档位优先费（取样区块的中位数）
+pub struct FeeHistory {
+    pub next_base_fee: u128,
+    tips: [u128; 3],
+}
+
+impl FeeHistory {
+    /// 查询最近区块的费用历史；节点未返回 reward 时优先费退回 eth_maxPriorityFeePerGas
+    pub async fn fetch(rpc: &EvmRpc) -> Result<Self, RpcError> {
+        let v = rpc.fee_history(FEE_HISTORY_BLOCKS, &PERCENTILES).await?;
+        let next_base_fee = v
+            .get("baseFeePerGas")
+            .and_then(|b| b.as_array())
+            .and_then(|b| b.last())
+            .and_then(abi::parse_quantity)
+            .unwrap_or(0);
+        let rewards: Vec<&Vec<Value>> = v
+            .get("reward")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|row| row.as_array()).collect())
+            .unwrap_or_default();
+        let mut tips = [0u128; 3];
+        if rewards.is_empty() {
+            tips = [rpc.max_priority_fee_per_gas().await?; 3];
+        } else {
+            for (i, tip) in tips.iter_mut().enumerate() {
+                let mut samples: Vec<u128> = rewards.iter().filter_map(|row| row.get(i).and_then(abi::parse_quantity)).collect();
+                samples.sort_unstable();
+                *tip = samples.get(samples.len() / 2).copied().unwrap_or(0);
+            }
+        }
+        Ok(Self { next_base_fee, tips })
+    }
+
+    /// 档位建议：(maxFeePerGas, maxPriorityFeePerGas)；maxFee = 2 × 下一区块 baseFee + 优先费，可承受连续数个满块的 baseFee 上涨
+    pub fn suggest(&self, preset: FeePreset) -> (u128, u128) {
+        let tip = self.tips[preset.index()];
+        (self.next_base_fee * 2 + tip, tip)
+    }
+}
+
+/// 费用模式
+pub enum FeeMode {
+    /// EIP-1559（type 2）
+    Eip1559 { max_fee: u128, priority_fee: u128 },
+    /// legacy（type 0）固定 gasPrice
+    Legacy { gas_price: u128 },
+}
+
+/// 由环境变量解析的费用设置；均未设置时沿用 SDK 构建结果
+///
+/// 环境变量：
+/// - EVM_FEE_PRESET：slow / normal / fast，按 eth_feeHistory 推导 EIP-1559 费用
+/// - EVM_MAX_FEE_GWEI、EVM_PRIORITY_FEE_GWEI：显式指定 maxFeePerGas / maxPriorityFeePerGas（gwei，可带小数），优先于档位
+/// - EVM_GAS_PRICE_GWEI：legacy 模式 gasPrice（gwei），不可与 EIP-1559 变量同时使用
+/// - EVM_GAS_LIMIT：覆盖 gas 上限
+pub struct FeeSettings {
+    pub mode: Option<FeeMode>,
+    pub gas_limit: Option<u128>,
+}
+
+impl FeeSettings {
+    pub async fn from_env(rpc: &EvmRpc) -> Result<Self, Box<dyn std::error::Error>> {
+        let gas_limit = match env::var("EVM_GAS_LIMIT") {
+            Ok(v) => Some(
+                v.trim()
+                    .parse::<u128>()
+                    .ok()
+                    .filter(|g| *g > 0)
+                    .ok_or_else(|| format!("EVM_GAS_LIMIT 应为正整数，当前: {}", v))?,
+            ),
+            Err(_) => None,
+        };
+        let preset = match env::var("EVM_FEE_PRESET") {
+            Ok(v) => Some(FeePreset::parse(&v).ok_or_else(|| format!("EVM_FEE_PRESET 应为 slow / normal / fast，当前: {}", v))?),
+            Err(_) => None,
+        };
+        let max_fee = env_gwei("EVM_MAX_FEE_GWEI")?;
+        let priority_fee = env_gwei("EVM_PRIORITY_FEE_GWEI")?;
+        let gas_price = env_gwei("EVM_GAS_PRICE_GWEI")?;
+
+        if let Some(gas_price) = gas_price {
+            if preset.is_some() || max_fee.is_some() || priority_fee.is_some() {
+                return Err("EVM_GAS_PRICE_GWEI（legacy）不能与 EVM_FEE_PRESET / EVM_MAX_FEE_GWEI / EVM_PRIORITY_FEE_GWEI 同时使用".into());
+            }
+            return Ok(Self { mode: Some(FeeMode::Legacy { gas_price }), gas_limit });
+        }
+        if let (Some(max), Some(tip)) = (max_fee, priority_fee) {
+            if tip > max {
+                return Err(format!(
+                    "EVM_PRIORITY_FEE_GWEI（{}）不能大于 EVM_MAX_FEE_GWEI（{}）",
+                    format_gwei(tip),
+                    format_gwei(max)
+                )
+                .into());
+            }
+        }
+        if preset.is_none() && max_fee.is_none() && priority_fee.is_none() {
+            return Ok(Self { mode: None, gas_limit });
+        }
+
+        // 未显式指定的字段按档位（默认 normal）补齐
+        let (max, tip) = match (max_fee, priority_fee) {
+            (Some(max), Some(tip)) => (max, tip),
+            _ => {
+                let history = FeeHistory::fetch(rpc).await?;
+                let (suggested_max, suggested_tip) = history.suggest(preset.unwrap_or(FeePreset::Normal));
+                let tip = priority_fee.unwrap_or(suggested_tip);
+                let max = max_fee.unwrap_or_else(|| suggested_max.max(history.next_base_fee * 2 + tip));
+                (max, tip)
+            }
+        };
+        let tip = tip.min(max);
+        Ok(Self { mode: Some(FeeMode::Eip1559 { max_fee: max, priority_fee: tip }), gas_limit })
+    }
+
+    /// 是否有任何覆盖项
+    pub fn is_empty(&self) -> bool {
+        self.mode.is_none() && self.gas_limit.is_none()
+    }
+
+    /// 打印将要写入的费用设置
+    pub fn print(&self) {
+        match &self.mode {
+            Some(FeeMode::Eip1559 { max_fee, priority_fee }) => println!(
+                "   费用: EIP-1559，maxFeePerGas {} gwei，maxPriorityFeePerGas {} gwei",
+                format_gwei(*max_fee),
+                format_gwei(*priority_fee)
+            ),
+            Some(FeeMode::Legacy { gas_price }) => println!("   费用: legacy，gasPrice {} gwei", format_gwei(*gas_price)),
+            None => {}
+        }
+        if let Some(gas) = self.gas_limit {
+            println!("   gas 上限: {}", gas);
+        }
+    }
+
+    /// 将费用设置写入 SDK 构建的交易 JSON
+    pub fn apply(&self, tx_json: &str) -> Result<String, Box<dyn std::error::Error>> {
+        if self.is_empty() {
+            return Ok(tx_json.to_string());
+        }
+        let mut tx: Value = serde_json::from_str(tx_json)?;
+        let obj = tx.as_object_mut().ok_or("交易 JSON 不是对象")?;
+        if let Some(gas) = self.gas_limit {
+            let key = if obj.contains_key("gasLimit") { "gasLimit" } else { "gas" };
+            set_quantity(obj, key, gas);
+        }
+        match &self.mode {
+            Some(FeeMode::Eip1559 { max_fee, priority_fee }) => {
+                obj.remove("gasPrice");
+                set_quantity(obj, "maxFeePerGas", *max_fee);
+                set_quantity(obj, "maxPriorityFeePerGas", *priority_fee);
+                // SDK 构建的 legacy 交易可能不带 type，缺省时签名器仍按 legacy 处理，须显式写入
+                set_quantity(obj, "type", 2);
+            }
+            Some(FeeMode::Legacy { gas_price }) => {
+                obj.remove("maxFeePerGas");
+                obj.remove("maxPriorityFeePerGas");
+                set_quantity(obj, "gasPrice", *gas_price);
+                if obj.contains_key("type") {
+                    set_quantity(obj, "type", 0);
+                }
+            }
+            None => {}
+        }
+        Ok(serde_json::to_string(&tx)?)
+    }
+}
+
+/// 签名前打印最坏情况手续费：gas 上限 × 单价上限（maxFeePerGas，legacy 为 gasPrice）
+pub fn print_worst_case_fee(tx_json: &str) {
+    let tx: Value = match serde_json::from_str(tx_json) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    let gas = tx.get("gas").or_else(|| tx.get("gasLimit")).and_then(abi::parse_quantity);
+    let price = tx.get("maxFeePerGas").or_else(|| tx.get("gasPrice")).and_then(abi::parse_quantity);
+    match (gas, price) {
+        (Some(gas), Some(price)) => println!(
+            "   最坏情况手续费: {} ETH（gas 上限 {} × {} gwei）",
+            abi::format_units(gas.saturating_mul(price), 18),
+            gas,
+            format_gwei(price)
+        ),
+        _ => println!("   最坏情况手续费: 未知（构建结果缺少 gas 上限或费用字段）"),
+    }
+}
+
+/// `eth-fees`：打印当前网络 baseFee 与 slow / normal / fast 档位
+pub async fn run_eth_fees() -> Result<(), Box<dyn std::error::Error>> {
+    let network = crate::config::current_evm_network()?;
+    let rpc_url = crate::config::evm_rpc_url(&network).await;
+    let rpc = EvmRpc::new(rpc_url.clone());
+
+    println!("=== EVM 费用档位（{}）===", network.name());
+    println!("RPC: {}", rpc_url);
+    let history = match FeeHistory::fetch(&rpc).await {
+        Ok(h) => h,
+        Err(e) => {
+            println!("❌ 查询 eth_feeHistory 失败: {}", e);
+            return Ok(());
+        }
+    };
+    println!("下一区块 baseFee: {} gwei", format_gwei(history.next_base_fee));
+    println!(
+        "（最近 {} 个区块优先费的第 {}/{}/{} 百分位中位数；maxFee = 2 × baseFee + 优先费）",
+        FEE_HISTORY_BLOCKS, PERCENTILES[0], PERCENTILES[1], PERCENTILES[2]
+    );
+    for preset in FeePreset::ALL {
+        let (max, tip) = history.suggest(preset);
+        println!(
+            "  {:<7} maxFeePerGas {} gwei，maxPriorityFeePerGas {} gwei，21000 gas 最坏 {} ETH",
+            preset.name(),
+            format_gwei(max),
+            format_gwei(tip),
+            abi::format_units(max * 21_000, 18)
+        );
+    }
+    if let Some(prices) = crate::arbitrum::ArbPrices::fetch(&rpc).await {
+        prices.print();
+    }
+    Ok(())
+}
+
+/// wei → gwei 文本
+pub fn format_gwei(wei: u128) -> String {
+    abi::format_units(wei, 9)
+}
+
+/// 按原字段的格式写入数量；新字段及超出 u64 的 JSON 数字使用 0x 十六进制
+pub fn set_quantity(obj: &mut serde_json::Map<String, Value>, key: &str, value: u128) {
+    let formatted = match obj.get(key) {
+        Some(Value::Number(_)) if value <= u64::MAX as u128 => json!(value as u64),
+        Some(Value::String(s)) if !s.starts_with("0x") && !s.starts_with("0X") => json!(value.to_string()),
+        _ => json!(abi::to_hex_quantity(value)),
+    };
+    obj.insert(key.to_string(), formatted);
+}
+
+/// 读取 gwei 数值（可带小数，最多 9 位）并换算为 wei
+fn env_gwei(key: &str) -> Result<Option<u128>, String> {
+    let v = match env::var(key) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
+    let err = || format!("{} 应为 gwei 数值（如 1.5），当前: {}", key, v);
+    let s = v.trim();
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    if frac_part.len() > 9 || (int_part.is_empty() && frac_part.is_empty()) {
+        return Err(err());
+    }
+    let int: u128 = if int_part.is_empty() { 0 } else { int_part.parse().map_err(|_| err())? };
+    let frac: u128 = if frac_part.is_empty() {
+        0
+    } else {
+        format!("{:0<9}", frac_part).parse().map_err(|_| err())?
+    };
+    int.checked_mul(GWEI).and_then(|w| w.checked_add(frac)).map(Some).ok_or_else(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eip1559_sets_type_and_drops_gas_price() {
+        let settings = FeeSettings { mode: Some(FeeMode::Eip1559 { max_fee: 30, priority_fee: 2 }), gas_limit: None };
+        let tx: Value = serde_json::from_str(&settings.apply(r#"{"gasPrice":"0x5","gas":"0x5208"}"#).unwrap()).unwrap();
+        assert_eq!(tx["type"], "0x2");
+        assert_eq!(tx["maxFeePerGas"], "0x1e");
+        assert_eq!(tx["maxPriorityFeePerGas"], "0x2");
+        assert!(tx.get("gasPrice").is_none());
+    }
+
+    #[test]
+    fn set_quantity_keeps_large_values() {
+        let mut obj = serde_json::Map::new();
+        obj.insert("maxFeePerGas".into(), json!(1));
+        set_quantity(&mut obj, "maxFeePerGas", u64::MAX as u128 + 1);
+        assert_eq!(obj["maxFeePerGas"], "0x10000000000000000");
+        set_quantity(&mut obj, "gas", 21000);
+        assert_eq!(obj["gas"], "0x5208");
+    }
+}
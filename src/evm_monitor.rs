@@ -0,0 +1,134 @@
+//! EVM 交易确认监听（感知区块重组）
+//!
+//! SDK 的 wait_for_confirmation_with_timeout 只按区块数计数；此处记录交易所在区块哈希，
+//! 每次轮询复核其仍在规范链上，交易被移出或重新打包进其他区块时报告重组并重置确认数。
+
+use crate::abi;
+use crate::evm_rpc::EvmRpc;
+use crate::rpc::RpcError;
+use std::time::{Duration, Instant};
+
+/// 监听结果
+pub enum ConfirmationStatus {
+    /// 执行成功且达到确认数
+    Confirmed,
+    /// 执行失败（status=0）且达到确认数
+    Failed,
+    /// 超时仍未达到确认数（从未上链，或上链后确认数不足）
+    Pending,
+    /// 曾上链但所在区块被重组，超时时交易不在规范链上
+    Reorged,
+}
+
+/// 当前记录的上链位置
+struct Inclusion {
+    block: u64,
+    hash: String,
+}
+
+/// 轮询直到达到确认数或超时；期间发生的重组会打印并重置确认数，单次查询失败仅提示并继续轮询
+pub async fn wait_for_confirmation(
+    rpc: &EvmRpc,
+    tx_hash: &str,
+    timeout_sec: u64,
+    poll_ms: u64,
+    min_confirmations: u64,
+) -> ConfirmationStatus {
+    let deadline = Instant::now() + Duration::from_secs(timeout_sec);
+    let mut tracker = Tracker { inclusion: None, last_confirmations: 0, reorged: false };
+    loop {
+        match tracker.poll(rpc, tx_hash, min_confirmations).await {
+            Ok(Some(status)) => return status,
+            Ok(None) => {}
+            Err(e) => println!("   ⚠ 查询失败，稍后重试: {}", e),
+        }
+        if Instant::now() >= deadline {
+            return if tracker.reorged && tracker.inclusion.is_none() {
+                ConfirmationStatus::Reorged
+            } else {
+                ConfirmationStatus::Pending
+            };
+        }
+        tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+    }
+}
+
+/// 轮询状态：当前上链位置、已打印的确认数、是否发生过重组
+struct Tracker {
+    inclusion: Option<Inclusion>,
+    last_confirmations: u64,
+    reorged: bool,
+}
+
+impl Tracker {
+    /// 单次轮询；达到确认数时返回最终状态
+    async fn poll(
+        &mut self,
+        rpc: &EvmRpc,
+        tx_hash: &str,
+        min_confirmations: u64,
+    ) -> Result<Option<ConfirmationStatus>, RpcError> {
+        let receipt = rpc.get_transaction_receipt(tx_hash).await?;
+        if receipt.is_null() {
+            if let Some(prev) = self.inclusion.take() {
+                self.reset(&format!(
+                    "交易原在区块 {}（{}）已不在规范链上，等待重新打包",
+                    prev.block, prev.hash
+                ));
+            }
+            return Ok(None);
+        }
+        let block = receipt.get("blockNumber").and_then(abi::parse_quantity).unwrap_or(0) as u64;
+        let hash = receipt
+            .get("blockHash")
+            .and_then(|h| h.as_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if let Some(prev) = self.inclusion.take() {
+            if prev.hash != hash {
+                self.reset(&format!(
+                    "交易从区块 {}（{}）重新打包进区块 {}（{}）",
+                    prev.block, prev.hash, block, hash
+                ));
+            } else {
+                self.inclusion = Some(prev);
+            }
+        }
+
+        // 复核回执中的区块仍是该高度的规范区块（节点索引滞后时回执可能指向已被替换的区块）
+        let canonical = rpc
+            .get_block_by_number(block, false)
+            .await?
+            .get("hash")
+            .and_then(|h| h.as_str())
+            .map(|h| h.to_lowercase());
+        if canonical.as_deref() != Some(hash.as_str()) {
+            if self.inclusion.take().is_some() {
+                self.reset(&format!("区块 {} 的规范哈希已不是 {}", block, hash));
+            }
+            return Ok(None);
+        }
+        if self.inclusion.is_none() {
+            println!("   已上链: 区块 {}（{}）", block, hash);
+            self.inclusion = Some(Inclusion { block, hash });
+        }
+
+        let head = rpc.block_number().await?;
+        let confirmations = (head + 1).saturating_sub(block);
+        if confirmations != self.last_confirmations {
+            println!("   确认数: {}/{}", confirmations.min(min_confirmations), min_confirmations);
+            self.last_confirmations = confirmations;
+        }
+        if confirmations < min_confirmations {
+            return Ok(None);
+        }
+        let success = receipt.get("status").and_then(abi::parse_quantity) == Some(1);
+        Ok(Some(if success { ConfirmationStatus::Confirmed } else { ConfirmationStatus::Failed }))
+    }
+
+    fn reset(&mut self, detail: &str) {
+        println!("   ⚠ 区块重组：{}，确认数重置", detail);
+        self.reorged = true;
+        self.last_confirmations = 0;
+    }
+}
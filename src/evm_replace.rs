@@ -0,0 +1,306 @@
+//! 卡住的 EVM 交易处理：同 nonce 提价重发（`tx speedup`）、同 nonce 0 金额转给自己（`tx cancel`），
+//! 以及转账流程中超过等待时间仍未上链时自动提价（EVM_AUTO_BUMP_SEC）
+//!
+//! 替换交易先由 SDK 构建原生转账模板，再改写 nonce、data、gas 与费用字段后签名广播。
+//! 节点的替换规则要求新交易的 maxFeePerGas 与 maxPriorityFeePerGas（legacy 为 gasPrice）均比原交易高至少 10%。
+
+use crate::abi;
+use crate::evm_chain::{self, signer_address, ChainIdentity};
+use crate::evm_fees::{self, FeeHistory, FeeMode, FeePreset, FeeSettings};
+use crate::evm_monitor::{self, ConfirmationStatus};
+use crate::evm_rpc::EvmRpc;
+use crate::ledger::{self, TxInfo};
+use crate::nonce_manager;
+use crate::sdk::EvmSdk;
+use chains_sdk::chain::evm::sign_ethereum_transaction;
+use serde_json::Value;
+use std::env;
+use std::time::{Duration, Instant};
+
+/// 默认提价比例（%）
+const DEFAULT_BUMP_PCT: u128 = 15;
+/// 节点替换规则要求的最低提价比例（%）
+const MIN_BUMP_PCT: u128 = 10;
+
+/// 替换方式
+#[derive(Clone, Copy)]
+pub enum ReplaceKind {
+    /// 原样重发（同 to / value / data / gas），仅提高费用
+    SpeedUp,
+    /// 0 金额转给自己，占用同一 nonce 使原交易失效
+    Cancel,
+}
+
+impl ReplaceKind {
+    fn name(&self) -> &'static str {
+        match self {
+            ReplaceKind::SpeedUp => "加速",
+            ReplaceKind::Cancel => "取消",
+        }
+    }
+}
+
+/// 自动提价设置
+pub struct AutoBump {
+    /// 最新一笔交易超过该秒数未上链即提价重发
+    pub wait_sec: u64,
+    /// 最多提价次数
+    pub max_bumps: u32,
+}
+
+impl AutoBump {
+    /// EVM_AUTO_BUMP_SEC（未设置则不自动提价）、EVM_AUTO_BUMP_MAX（默认 3）
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let wait_sec = match env::var("EVM_AUTO_BUMP_SEC") {
+            Ok(v) => v
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .filter(|s| *s > 0)
+                .ok_or_else(|| format!("EVM_AUTO_BUMP_SEC 应为正整数（秒），当前: {}", v))?,
+            Err(_) => return Ok(None),
+        };
+        let max_bumps = match env::var("EVM_AUTO_BUMP_MAX") {
+            Ok(v) => v
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("EVM_AUTO_BUMP_MAX 应为非负整数，当前: {}", v))?,
+            Err(_) => 3,
+        };
+        Ok(Some(Self { wait_sec, max_bumps }))
+    }
+}
+
+/// `tx speedup <hash>` / `tx cancel <hash>`：替换仍在交易池中的 EVM 交易并等待确认
+///
+/// 环境变量：
+/// - ETH_PRIVATE_KEY（必填）：原交易发送方的私钥
+/// - EVM_BUMP_PCT（可选）：提价比例，默认 15，最低 10
+/// - EVM_RPC_URL（可选）
+pub async fn run_replace(hash: &str, kind: ReplaceKind) -> Result<(), Box<dyn std::error::Error>> {
+    if !(hash.starts_with("0x") || hash.starts_with("0X")) {
+        eprintln!("仅支持 EVM 交易（0x 开头）；Tron 交易无交易池替换机制。");
+        return Ok(());
+    }
+    let private_key = match env::var("ETH_PRIVATE_KEY") {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("未设置 ETH_PRIVATE_KEY（需为原交易发送方的私钥）。");
+            eprintln!("示例: export ETH_PRIVATE_KEY=你的64位十六进制私钥");
+            return Ok(());
+        }
+    };
+    let network = crate::config::current_evm_network()?;
+    let rpc_url = crate::config::evm_rpc_url(&network).await;
+    let rpc = EvmRpc::new(rpc_url);
+
+    println!("=== {}交易（{}）===", kind.name(), network.name());
+    println!("原交易: {}", hash);
+    let new_hash = match replace(&rpc, &private_key, hash, kind).await {
+        Ok(h) => h,
+        Err(e) => {
+            println!("❌ {}失败: {}", kind.name(), e);
+            return Ok(());
+        }
+    };
+
+    println!("等待确认（原交易与替换交易任一上链，超时 120s，轮询 3s）...");
+    let min_confirmations = network.finality_depth.unwrap_or(1);
+    let (mined, status) =
+        wait_for_any(&rpc, &private_key, vec![hash.to_string(), new_hash.clone()], 120, 3000, min_confirmations, None).await;
+    if mined != new_hash && matches!(status, ConfirmationStatus::Confirmed | ConfirmationStatus::Failed) {
+        println!("⚠ 上链的是原交易 {}，替换未生效", mined);
+    }
+    ledger::finish_evm(&rpc, &mined, &status).await;
+    match status {
+        ConfirmationStatus::Confirmed => println!("✅ 交易已确认!"),
+        ConfirmationStatus::Failed => println!("❌ 交易失败!"),
+        ConfirmationStatus::Pending => println!("⏳ 超时仍未确认，可再次执行 tx speedup {}", new_hash),
+        ConfirmationStatus::Reorged => println!("⚠ 交易所在区块已被重组，超时时交易不在规范链上"),
+    }
+    Ok(())
+}
+
+/// 构建、签名并广播替换交易，返回新交易哈希
+pub async fn replace(
+    rpc: &EvmRpc,
+    private_key: &str,
+    original_hash: &str,
+    kind: ReplaceKind,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let network = crate::config::current_evm_network()?;
+    let chain_id = ChainIdentity::check(rpc, &network).await.require_for_signing()?;
+    let tx = rpc.get_transaction(original_hash).await?;
+    if tx.is_null() {
+        return Err("节点未找到原交易（可能已被交易池丢弃，或不属于当前 EVM_NETWORK）".into());
+    }
+    if let Some(block) = tx.get("blockNumber").and_then(abi::parse_quantity) {
+        return Err(format!("原交易已上链（区块 {}），无需替换", block).into());
+    }
+    let from = tx.get("from").and_then(|f| f.as_str()).unwrap_or("").to_lowercase();
+    let signer = signer_address(private_key, "");
+    if signer.to_lowercase() != from {
+        return Err(format!("ETH_PRIVATE_KEY 对应地址 {} 不是原交易发送方 {}", signer, from).into());
+    }
+    let nonce = tx.get("nonce").and_then(abi::parse_quantity).ok_or("原交易缺少 nonce")?;
+
+    let (to, value, data, gas) = match kind {
+        ReplaceKind::SpeedUp => (
+            tx.get("to")
+                .and_then(|t| t.as_str())
+                .ok_or("暂不支持替换合约创建交易")?
+                .to_string(),
+            tx.get("value").and_then(abi::parse_quantity).unwrap_or(0),
+            tx.get("input").and_then(|i| i.as_str()).unwrap_or("0x").to_string(),
+            tx.get("gas").and_then(abi::parse_quantity),
+        ),
+        ReplaceKind::Cancel => (signer.clone(), 0, "0x".to_string(), None),
+    };
+    let fees = FeeSettings { mode: Some(bumped_fees(rpc, &tx).await?), gas_limit: gas };
+
+    println!("{}: nonce {}，{} → {}", kind.name(), nonce, signer, to);
+    fees.print();
+
+    let sdk = EvmSdk::new(rpc.url().to_string());
+    // 模板只取 SDK 的字段格式与 gas 估算，calldata 随后原样写回；ERC20 transfer 走代币构建，
+    // 避免按“向合约转 0 ETH、无 calldata”估算 gas 时合约回滚
+    let calldata = hex::decode(abi::strip_0x(&data)).unwrap_or_default();
+    let template = match ledger::decode_transfer(&calldata) {
+        Some((recipient, amount)) if value == 0 => {
+            let recipient = format!("0x{}", hex::encode(recipient));
+            sdk.erc20_build_transfer(&signer, &recipient, &to, &amount.to_string()).await?
+        }
+        _ => sdk.build_native_transfer(&signer, &to, &value.to_string()).await
+            .map_err(|e| {
+                if calldata.is_empty() {
+                    e.to_string()
+                } else {
+                    format!("{}（其他合约调用按原生转账构建模板，gas 估算可能回滚；目前只有 ERC20 transfer 走代币构建）", e)
+                }
+            })?,
+    };
+    let tx_json = with_data(&nonce_manager::set_nonce(&fees.apply(&template)?, nonce)?, &data)?;
+    let tx_json = evm_chain::bind_chain_id(&tx_json, chain_id)?;
+    evm_fees::print_worst_case_fee(&tx_json);
+    let entry = ledger::track(&TxInfo::evm(&network, &signer, &tx_json).replaces(original_hash));
+
+    let signed_hex = sign_ethereum_transaction(&tx_json, private_key)?;
+    entry.signed(&ledger::evm_tx_hash(&signed_hex)?, Some(nonce));
+    match sdk.send(&signed_hex).await {
+        Ok(h) => {
+            println!("   替换交易哈希: {}", h);
+            if let Some(url) = network.tx_url(&h) {
+                println!("   浏览器: {}", url);
+            }
+            nonce_manager::record(network.name(), &signer, nonce, &h).await;
+            entry.broadcast(&h);
+            Ok(h)
+        }
+        Err(e) => {
+            let msg = e.to_string();
+            entry.rejected(&msg);
+            if msg.contains("underpriced") {
+                Err(format!("{}（提价不足，请调大 EVM_BUMP_PCT）", msg).into())
+            } else if msg.contains("nonce too low") || msg.contains("already known") {
+                Err(format!("{}（原交易可能已上链或已被替换）", msg).into())
+            } else {
+                Err(msg.into())
+            }
+        }
+    }
+}
+
+/// 等待同 nonce 的一组交易中任一笔上链并达到确认数，返回上链的哈希与状态
+///
+/// auto_bump 为 Some 时，最新一笔超过 wait_sec 仍未上链即自动 speedup（最多 max_bumps 次），总超时相应延长。
+pub async fn wait_for_any(
+    rpc: &EvmRpc,
+    private_key: &str,
+    mut hashes: Vec<String>,
+    timeout_sec: u64,
+    poll_ms: u64,
+    min_confirmations: u64,
+    auto_bump: Option<AutoBump>,
+) -> (String, ConfirmationStatus) {
+    let extra = auto_bump.as_ref().map(|b| b.wait_sec * b.max_bumps as u64).unwrap_or(0);
+    let deadline = Instant::now() + Duration::from_secs(timeout_sec + extra);
+    let mut last_sent = Instant::now();
+    let mut bumps = 0u32;
+    loop {
+        for hash in hashes.iter().rev() {
+            match rpc.get_transaction_receipt(hash).await {
+                Ok(r) if !r.is_null() => {
+                    let remaining = deadline.saturating_duration_since(Instant::now()).as_secs().max(poll_ms / 1000 + 1);
+                    let status = evm_monitor::wait_for_confirmation(rpc, hash, remaining, poll_ms, min_confirmations).await;
+                    return (hash.clone(), status);
+                }
+                Ok(_) => {}
+                Err(e) => println!("   ⚠ 查询失败，稍后重试: {}", e),
+            }
+        }
+        if Instant::now() >= deadline {
+            return (hashes.last().cloned().unwrap_or_default(), ConfirmationStatus::Pending);
+        }
+        if let Some(bump) = &auto_bump {
+            if bumps < bump.max_bumps && last_sent.elapsed() >= Duration::from_secs(bump.wait_sec) {
+                bumps += 1;
+                let latest = hashes.last().cloned().unwrap_or_default();
+                println!("⏫ 超过 {}s 未上链，自动提价重发（第 {}/{} 次）", bump.wait_sec, bumps, bump.max_bumps);
+                match replace(rpc, private_key, &latest, ReplaceKind::SpeedUp).await {
+                    Ok(h) => hashes.push(h),
+                    // 失败也计入次数，last_sent 照常更新：间隔 wait_sec 后再试，不会连续重试
+                    Err(e) if bumps < bump.max_bumps => println!("   ⚠ 自动提价失败，{}s 后重试: {}", bump.wait_sec, e),
+                    Err(e) => println!("   ⚠ 自动提价失败，已达最大次数，继续等待: {}", e),
+                }
+                last_sent = Instant::now();
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+    }
+}
+
+/// 在原交易费用上提价 EVM_BUMP_PCT%，且不低于当前网络 normal 档位
+async fn bumped_fees(rpc: &EvmRpc, tx: &Value) -> Result<FeeMode, Box<dyn std::error::Error>> {
+    let pct = match env::var("EVM_BUMP_PCT") {
+        Ok(v) => v
+            .trim()
+            .parse::<u128>()
+            .map_err(|_| format!("EVM_BUMP_PCT 应为整数百分比，当前: {}", v))?
+            .max(MIN_BUMP_PCT),
+        Err(_) => DEFAULT_BUMP_PCT,
+    };
+    let bump = |v: u128| (v * (100 + pct)).div_ceil(100);
+    let current = match FeeHistory::fetch(rpc).await {
+        Ok(h) => Some(h.suggest(FeePreset::Normal)),
+        Err(e) => {
+            println!("   ⚠ 查询当前费用失败，仅按原交易提价: {}", e);
+            None
+        }
+    };
+    let (cur_max, cur_tip) = current.unwrap_or((0, 0));
+
+    match tx.get("maxFeePerGas").and_then(abi::parse_quantity) {
+        Some(old_max) => {
+            let old_tip = tx.get("maxPriorityFeePerGas").and_then(abi::parse_quantity).unwrap_or(0);
+            let priority_fee = bump(old_tip).max(cur_tip);
+            let max_fee = bump(old_max).max(cur_max).max(priority_fee);
+            Ok(FeeMode::Eip1559 { max_fee, priority_fee })
+        }
+        None => {
+            let old_price = tx.get("gasPrice").and_then(abi::parse_quantity).ok_or("原交易缺少费用字段")?;
+            Ok(FeeMode::Legacy { gas_price: bump(old_price).max(cur_max) })
+        }
+    }
+}
+
+/// 改写模板交易的 data（data 为空时保留模板）
+fn with_data(tx_json: &str, data: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if abi::strip_0x(data).is_empty() {
+        return Ok(tx_json.to_string());
+    }
+    let mut tx: Value = serde_json::from_str(tx_json)?;
+    let obj = tx.as_object_mut().ok_or("交易 JSON 不是对象")?;
+    let key = if obj.contains_key("input") { "input" } else { "data" };
+    obj.insert(key.to_string(), Value::String(data.to_string()));
+    Ok(serde_json::to_string(&tx)?)
+}
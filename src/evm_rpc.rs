@@ -0,0 +1,222 @@
+//! EVM 原始 JSON-RPC 调用（SDK 未覆盖的接口，如 eth_call 预执行模拟）
+
+use crate::abi;
+use crate::rpc::{self, RpcError};
+use serde_json::{json, Value};
+
+/// 预执行结果
+pub enum CallOutcome {
+    /// 执行成功
+    Success,
+    /// 执行会回滚，附解析后的原因
+    Reverted(String),
+}
+
+/// 轻量 EVM JSON-RPC 客户端；失败时按 rpc 模块的策略退避并切换到 config 登记的备用节点
+#[derive(Clone)]
+pub struct EvmRpc {
+    url: String,
+    client: reqwest::Client,
+    /// 只访问 url 本身：不重试、不切换端点
+    direct: bool,
+    /// direct 模式下请求前是否限流
+    throttled: bool,
+}
+
+impl EvmRpc {
+    pub fn new(url: String) -> Self {
+        Self { url, client: rpc::http_client(), direct: false, throttled: true }
+    }
+
+    /// 只访问指定节点的客户端（仍受限流，不重试、不切换端点），供 `rpc probe`、`consistency` 逐节点比较使用
+    pub fn direct(url: String) -> Self {
+        Self { url, client: rpc::http_client(), direct: true, throttled: true }
+    }
+
+    /// direct 客户端不再自行限流：由调用方在计时前取令牌（`rpc probe` 测延迟时避免把限流等待计入）
+    pub fn unthrottled(self) -> Self {
+        Self { throttled: false, ..self }
+    }
+
+    /// 主节点 URL（SDK 调用以此创建 EvmSdk，同样按 rpc 模块的策略切换到备用节点）
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// 发送单个 JSON-RPC 请求，返回 result 字段；失败时按 rpc 模块的策略退避并切换端点
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        if self.direct {
+            if self.throttled {
+                rpc::throttle(&self.url).await;
+            }
+            return self.request_once(self.url.clone(), method, params).await;
+        }
+        rpc::with_failover(&self.url, method, |url| self.request_once(url, method, params.clone())).await
+    }
+
+    async fn request_once(&self, url: String, method: &str, params: Value) -> Result<Value, RpcError> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let resp = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| RpcError::Transport(e.to_string()))?;
+        let status = resp.status();
+        if status.as_u16() == 429 || status.is_server_error() {
+            return Err(RpcError::Transport(format!("HTTP {}", status)));
+        }
+        let v: Value = resp
+            .json()
+            .await
+            .map_err(|e| RpcError::Transport(format!("HTTP {}，响应解析失败: {}", status, e)))?;
+        if let Some(err) = v.get("error") {
+            return Err(RpcError::Rpc {
+                code: err.get("code").and_then(|c| c.as_i64()).unwrap_or(0),
+                message: err.get("message").and_then(|m| m.as_str()).unwrap_or("").to_string(),
+                data: err.get("data").and_then(|d| d.as_str()).map(|s| s.to_string()),
+            });
+        }
+        Ok(v.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// eth_blockNumber：最新区块号
+    pub async fn block_number(&self) -> Result<u64, RpcError> {
+        let v = self.request("eth_blockNumber", json!([])).await?;
+        abi::parse_quantity(&v)
+            .map(|n| n as u64)
+            .ok_or_else(|| RpcError::Transport(format!("eth_blockNumber 返回值无法解析: {}", v)))
+    }
+
+    /// eth_chainId：节点所在链的链 ID
+    pub async fn chain_id(&self) -> Result<u64, RpcError> {
+        let v = self.request("eth_chainId", json!([])).await?;
+        abi::parse_quantity(&v)
+            .map(|n| n as u64)
+            .ok_or_else(|| RpcError::Transport(format!("eth_chainId 返回值无法解析: {}", v)))
+    }
+
+    /// eth_getBlockByNumber；full 为 true 时 transactions 为完整交易对象，否则仅哈希；区块不存在时返回 Null
+    pub async fn get_block_by_number(&self, number: u64, full: bool) -> Result<Value, RpcError> {
+        self.request("eth_getBlockByNumber", json!([abi::to_hex_quantity(number as u128), full]))
+            .await
+    }
+
+    /// eth_getLogs（filter 可用 blockHash 或 fromBlock/toBlock 与 topics）
+    pub async fn get_logs(&self, filter: &Value) -> Result<Vec<Value>, RpcError> {
+        let v = self.request("eth_getLogs", json!([filter])).await?;
+        Ok(v.as_array().cloned().unwrap_or_default())
+    }
+
+    /// eth_getTransactionByHash；未知交易返回 Null
+    pub async fn get_transaction(&self, tx_hash: &str) -> Result<Value, RpcError> {
+        self.request("eth_getTransactionByHash", json!([tx_hash])).await
+    }
+
+    /// eth_getTransactionReceipt；未上链返回 Null
+    pub async fn get_transaction_receipt(&self, tx_hash: &str) -> Result<Value, RpcError> {
+        self.request("eth_getTransactionReceipt", json!([tx_hash])).await
+    }
+
+    /// eth_getTransactionCount：tag 为 latest 时是已上链的交易数（下一个可用 nonce），pending 时含交易池中可执行的交易
+    pub async fn get_transaction_count(&self, address: &str, tag: &str) -> Result<u128, RpcError> {
+        let v = self.request("eth_getTransactionCount", json!([address, tag])).await?;
+        abi::parse_quantity(&v).ok_or_else(|| RpcError::Transport(format!("eth_getTransactionCount 返回值无法解析: {}", v)))
+    }
+
+    /// eth_getBalance：原生币余额（wei），tag 为 latest / pending 或十六进制区块号
+    pub async fn get_balance(&self, address: &str, tag: &str) -> Result<u128, RpcError> {
+        let v = self.request("eth_getBalance", json!([address, tag])).await?;
+        abi::parse_quantity(&v).ok_or_else(|| RpcError::Transport(format!("eth_getBalance 返回值无法解析: {}", v)))
+    }
+
+    /// eth_feeHistory：最近 block_count 个区块的 baseFee（含下一区块）与各百分位的优先费
+    pub async fn fee_history(&self, block_count: u64, percentiles: &[f64]) -> Result<Value, RpcError> {
+        self.request(
+            "eth_feeHistory",
+            json!([abi::to_hex_quantity(block_count as u128), "latest", percentiles]),
+        )
+        .await
+    }
+
+    /// eth_maxPriorityFeePerGas：节点建议的优先费（wei）
+    pub async fn max_priority_fee_per_gas(&self) -> Result<u128, RpcError> {
+        let v = self.request("eth_maxPriorityFeePerGas", json!([])).await?;
+        abi::parse_quantity(&v).ok_or_else(|| RpcError::Transport(format!("eth_maxPriorityFeePerGas 返回值无法解析: {}", v)))
+    }
+
+    /// eth_call 只读调用，返回原始返回数据
+    pub async fn call(&self, call: &Value, block: &str) -> Result<Vec<u8>, RpcError> {
+        let v = self.request("eth_call", json!([call, block])).await?;
+        abi::hex_to_bytes(v.as_str().unwrap_or("0x")).map_err(RpcError::Transport)
+    }
+
+    /// eth_call 预执行；节点返回的 revert / 执行错误转为 CallOutcome::Reverted
+    pub async fn eth_call(&self, call: &Value, block: &str) -> Result<CallOutcome, RpcError> {
+        match self.request("eth_call", json!([call, block])).await {
+            Ok(_) => Ok(CallOutcome::Success),
+            Err(RpcError::Rpc { code, message, data }) if code == 3 || data.is_some() || is_execution_error(&message) => {
+                Ok(CallOutcome::Reverted(revert_reason(&message, data.as_deref())))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// 节点以 -32000 等通用错误码返回的执行失败（revert、余额不足、gas 不足）
+fn is_execution_error(message: &str) -> bool {
+    let m = message.to_lowercase();
+    m.contains("revert") || m.contains("execution") || m.contains("insufficient funds") || m.contains("out of gas")
+}
+
+/// 结合 error.message 与 error.data 给出可读的 revert 原因
+fn revert_reason(message: &str, data: Option<&str>) -> String {
+    match data.map(abi::hex_to_bytes) {
+        Some(Ok(bytes)) if !bytes.is_empty() => abi::decode_revert_reason(&bytes),
+        _ if !message.is_empty() => message.to_string(),
+        _ => abi::decode_revert_reason(&[]),
+    }
+}
+
+/// 由 SDK 构建的交易 JSON 生成 eth_call 调用对象（from/to/value/data/gas）
+///
+/// 数量字段兼容十六进制、十进制字符串与数字；费用字段不带入，避免模拟受 gas 价格影响。
+pub fn call_object_from_tx_json(tx_json: &str, from: &str) -> Result<Value, Box<dyn std::error::Error>> {
+    let tx: Value = serde_json::from_str(tx_json)?;
+    let mut call = json!({ "from": from });
+    if let Some(to) = tx.get("to").and_then(|v| v.as_str()) {
+        call["to"] = json!(to);
+    }
+    if let Some(value) = tx.get("value").and_then(abi::parse_quantity) {
+        call["value"] = json!(abi::to_hex_quantity(value));
+    }
+    if let Some(data) = tx.get("data").or_else(|| tx.get("input")).and_then(|v| v.as_str()) {
+        call["data"] = json!(data);
+    }
+    if let Some(gas) = tx.get("gas").or_else(|| tx.get("gasLimit")).and_then(abi::parse_quantity) {
+        call["gas"] = json!(abi::to_hex_quantity(gas));
+    }
+    Ok(call)
+}
+
+/// 签名前在 pending 区块上模拟已构建的交易；返回 Some(原因) 表示交易会回滚
+///
+/// 模拟调用失败（网络错误等）时返回错误，调用方不签名；设置 EVM_SKIP_SIMULATION=1 时仅提示并返回 None。
+pub async fn simulate_built_tx(rpc: &EvmRpc, tx_json: &str, from: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let call = call_object_from_tx_json(tx_json, from)?;
+    match rpc.eth_call(&call, "pending").await {
+        Ok(CallOutcome::Success) => Ok(None),
+        Ok(CallOutcome::Reverted(reason)) => Ok(Some(reason)),
+        Err(e) if skip_simulation() => {
+            println!("   ⚠ 模拟调用未完成，已按 EVM_SKIP_SIMULATION 跳过模拟: {}", e);
+            Ok(None)
+        }
+        Err(e) => Err(format!("模拟调用未完成，已中止（未签名未广播）: {}；确需跳过模拟可设置 EVM_SKIP_SIMULATION=1", e).into()),
+    }
+}
+
+/// EVM_SKIP_SIMULATION=1：模拟调用失败时不中止
+fn skip_simulation() -> bool {
+    std::env::var("EVM_SKIP_SIMULATION").map(|v| v == "1" || v == "true").unwrap_or(false)
+}
@@ -0,0 +1,939 @@
+//! 本地交易记录：本工具构建、签名、广播的每笔交易及其最终状态追加写入 JSONL 文件（LEDGER_FILE，默认 .tx-ledger.jsonl）
+//!
+//! 每行一个事件：`{"ts": 毫秒时间戳, "event": "built" | "signed" | "broadcast" | "rejected" | "final", ...}`。
+//! 构建时分配记录 id 并写入网络、发送方、接收方、代币与金额，之后的签名 / 广播事件以 id 关联、只写变化的字段；
+//! 最终状态事件由监听结束时写入，以交易哈希关联。文件只追加不改写，终端中途关闭不影响已写入的行（写了一半的末行读取时跳过）。
+//!
+//! `history` 按 id 合并事件后列出交易；`resume` 对已签名或已广播、尚无最终状态的交易重新监听并补写最终状态。
+//! 同 nonce 的另一笔交易（加速、取消、重发）已有最终状态时，其余交易视为已被替换。
+
+use crate::abi;
+use crate::batch_monitor::{self, Backend};
+use crate::config::{self, EvmNetworkConfig};
+use crate::evm_chain::ChainIdentity;
+use crate::evm_monitor::ConfirmationStatus;
+use crate::evm_rpc::EvmRpc;
+use crate::receipt::{EvmReceipt, TronReceipt};
+use crate::rpc::RpcError;
+use crate::tron_account::format_time_ms;
+use crate::tron_monitor::FinalityStatus;
+use crate::tron_rpc::TronApi;
+use chains_sdk::rpc::chains::tron::TronNetwork;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// `history` 默认显示的条数
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+/// `resume` 同时进行的 RPC 查询数默认值
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// 交易状态（合并事件后）
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    /// 已构建，未签名（模拟失败或中途退出）
+    Built,
+    /// 已签名，广播结果未知
+    Signed,
+    /// 已广播，未最终确认
+    Sent,
+    /// 节点拒绝广播
+    Rejected,
+    /// 已最终确认且执行成功
+    Confirmed,
+    /// 已最终确认但执行失败
+    Failed,
+    /// 同 nonce 的另一笔交易已上链
+    Replaced,
+    /// Tron 交易已过期且未上链
+    Expired,
+}
+
+impl Status {
+    fn key(&self) -> &'static str {
+        match self {
+            Status::Built => "built",
+            Status::Signed => "signed",
+            Status::Sent => "sent",
+            Status::Rejected => "rejected",
+            Status::Confirmed => "confirmed",
+            Status::Failed => "failed",
+            Status::Replaced => "replaced",
+            Status::Expired => "expired",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [
+            Status::Built,
+            Status::Signed,
+            Status::Sent,
+            Status::Rejected,
+            Status::Confirmed,
+            Status::Failed,
+            Status::Replaced,
+            Status::Expired,
+        ]
+        .into_iter()
+        .find(|st| st.key() == s)
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Status::Built => "已构建",
+            Status::Signed => "已签名",
+            Status::Sent => "已广播",
+            Status::Rejected => "广播被拒",
+            Status::Confirmed => "成功",
+            Status::Failed => "失败",
+            Status::Replaced => "已被替换",
+            Status::Expired => "已过期",
+        }
+    }
+
+    /// 已签名或已广播、尚无最终结果：resume 需要继续监听
+    fn is_open(&self) -> bool {
+        matches!(self, Status::Signed | Status::Sent)
+    }
+}
+
+/// 构建时记录的交易信息
+pub struct TxInfo {
+    /// evm | tron
+    chain: &'static str,
+    /// 网络名称（EVM_NETWORK / TRON_NETWORK 的取值）
+    network: String,
+    from: String,
+    to: String,
+    /// 代币符号；符号未知的代币为合约地址
+    token: String,
+    contract: Option<String>,
+    /// 按精度格式化的金额；精度未知时为最小单位
+    amount: String,
+    /// 最小单位金额
+    raw: u128,
+    /// 非转账的 Tron 合约类型（质押、代理等）
+    action: Option<String>,
+    /// 同 nonce 替换的原交易哈希（tx speedup / cancel 与自动提价）
+    replaces: Option<String>,
+    /// Tron 交易过期时间（毫秒）
+    expiration: Option<i64>,
+}
+
+impl TxInfo {
+    /// 从 SDK 构建的 EVM 交易 JSON 提取：data 为 ERC20 transfer 时记录代币接收方与金额，否则记录原生币金额
+    pub fn evm(network: &EvmNetworkConfig, from: &str, tx_json: &str) -> Self {
+        let tx: Value = serde_json::from_str(tx_json).unwrap_or(Value::Null);
+        let to = tx.get("to").and_then(|t| t.as_str()).unwrap_or("").to_string();
+        let value = tx.get("value").and_then(abi::parse_quantity).unwrap_or(0);
+        let data = tx
+            .get("data")
+            .or_else(|| tx.get("input"))
+            .and_then(|d| d.as_str())
+            .and_then(|d| abi::hex_to_bytes(d).ok())
+            .unwrap_or_default();
+        let mut info = Self {
+            chain: "evm",
+            network: network.key.clone(),
+            from: from.to_string(),
+            to: to.clone(),
+            token: network.native_symbol.clone(),
+            contract: None,
+            amount: abi::format_units(value, 18),
+            raw: value,
+            action: None,
+            replaces: None,
+            expiration: None,
+        };
+        if let Some((recipient, raw)) = decode_transfer(&data) {
+            info.to = format!("0x{}", hex::encode(recipient));
+            info.token = to.clone();
+            info.contract = Some(to);
+            info.amount = raw.to_string();
+            info.raw = raw;
+        }
+        info
+    }
+
+    /// 从 Tron 交易 JSON（raw_data.contract[0]）提取：TRX / TRC10 / TRC20 转账，其余合约只记录类型与金额
+    pub fn tron(network: TronNetwork, tx_json: &str) -> Self {
+        let tx: Value = serde_json::from_str(tx_json).unwrap_or(Value::Null);
+        let contract_type = tx
+            .pointer("/raw_data/contract/0/type")
+            .and_then(|t| t.as_str())
+            .unwrap_or("")
+            .to_string();
+        let p = tx.pointer("/raw_data/contract/0/parameter/value").cloned().unwrap_or(Value::Null);
+        let address = |field: &str| {
+            let hex_addr = p.get(field).and_then(|a| a.as_str()).unwrap_or("");
+            abi::tron_address_from_hex(hex_addr).unwrap_or_else(|_| hex_addr.to_string())
+        };
+        let number = |field: &str| p.get(field).and_then(|a| a.as_u64()).map(|a| a as u128);
+        let mut info = Self {
+            chain: "tron",
+            network: config::tron_network_key(network).to_string(),
+            from: address("owner_address"),
+            to: String::new(),
+            token: "TRX".to_string(),
+            contract: None,
+            amount: String::new(),
+            raw: 0,
+            action: None,
+            replaces: None,
+            expiration: tx.pointer("/raw_data/expiration").and_then(|e| e.as_i64()),
+        };
+        match contract_type.as_str() {
+            "TransferContract" => {
+                info.to = address("to_address");
+                info.raw = number("amount").unwrap_or(0);
+            }
+            "TransferAssetContract" => {
+                let asset = p.get("asset_name").and_then(|a| a.as_str()).unwrap_or("");
+                let asset = abi::hex_to_bytes(asset)
+                    .ok()
+                    .and_then(|b| String::from_utf8(b).ok())
+                    .unwrap_or_else(|| asset.to_string());
+                info.to = address("to_address");
+                info.token = format!("TRC10 {}", asset);
+                info.raw = number("amount").unwrap_or(0);
+                info.amount = info.raw.to_string();
+                return info;
+            }
+            "TriggerSmartContract" => {
+                let contract = address("contract_address");
+                let data = p
+                    .get("data")
+                    .and_then(|d| d.as_str())
+                    .and_then(|d| abi::hex_to_bytes(d).ok())
+                    .unwrap_or_default();
+                match decode_transfer(&data) {
+                    Some((recipient, raw)) => {
+                        info.to = abi::tron_address_from_hex(&format!("41{}", hex::encode(recipient))).unwrap_or_default();
+                        info.token = contract.clone();
+                        info.contract = Some(contract);
+                        info.raw = raw;
+                        info.amount = raw.to_string();
+                        return info;
+                    }
+                    None => {
+                        info.to = contract;
+                        info.raw = number("call_value").unwrap_or(0);
+                    }
+                }
+            }
+            _ => {
+                info.action = Some(contract_type);
+                info.raw = ["amount", "frozen_balance", "unfreeze_balance", "balance"]
+                    .iter()
+                    .find_map(|f| number(f))
+                    .unwrap_or(0);
+                if p.get("receiver_address").is_some() {
+                    info.to = address("receiver_address");
+                }
+            }
+        }
+        info.amount = abi::format_units(info.raw, 6);
+        info
+    }
+
+    /// 已知代币符号与精度时改写代币与金额
+    pub fn token(mut self, symbol: &str, decimals: u32) -> Self {
+        self.token = symbol.to_string();
+        self.amount = abi::format_units(self.raw, decimals);
+        self
+    }
+
+    /// 标记为同 nonce 替换交易
+    pub fn replaces(mut self, original_hash: &str) -> Self {
+        self.replaces = Some(original_hash.to_string());
+        self
+    }
+}
+
+/// 解析 ERC20 / TRC20 transfer(address,uint256) 调用数据
+pub fn decode_transfer(data: &[u8]) -> Option<([u8; 20], u128)> {
+    let (selector, body) = (data.get(..4)?, data.get(4..)?);
+    if selector != [0xa9, 0x05, 0x9c, 0xbb] {
+        return None;
+    }
+    let mut recipient = [0u8; 20];
+    recipient.copy_from_slice(abi::word(body, 0)?.get(12..)?);
+    Some((recipient, abi::uint_bytes_to_u128(abi::word(body, 1)?)?))
+}
+
+/// 已构建交易的记录句柄，后续签名 / 广播事件以其 id 关联
+pub struct Tracked {
+    id: String,
+}
+
+/// 记录一笔已构建的交易
+pub fn track(info: &TxInfo) -> Tracked {
+    static SEQ: AtomicU32 = AtomicU32::new(0);
+    let id = format!("{}-{}-{}", now_ms(), std::process::id(), SEQ.fetch_add(1, Ordering::Relaxed));
+    append(json!({
+        "event": "built",
+        "id": id,
+        "chain": info.chain,
+        "network": info.network,
+        "from": info.from,
+        "to": info.to,
+        "token": info.token,
+        "contract": info.contract,
+        "amount": info.amount,
+        "raw": info.raw.to_string(),
+        "action": info.action,
+        "replaces": info.replaces,
+        "expiration": info.expiration,
+    }));
+    Tracked { id }
+}
+
+impl Tracked {
+    /// 已签名：EVM 哈希由签名数据计算（见 evm_tx_hash），Tron 为 txID
+    pub fn signed(&self, tx_hash: &str, nonce: Option<u128>) {
+        append(json!({ "event": "signed", "id": self.id, "tx_hash": tx_hash, "nonce": nonce.map(|n| n as u64) }));
+    }
+
+    /// 广播成功，tx_hash 以节点返回为准
+    pub fn broadcast(&self, tx_hash: &str) {
+        append(json!({ "event": "broadcast", "id": self.id, "tx_hash": tx_hash }));
+    }
+
+    /// 节点拒绝广播
+    pub fn rejected(&self, error: &str) {
+        append(json!({ "event": "rejected", "id": self.id, "error": error }));
+    }
+}
+
+/// 已签名 EVM 交易的哈希（RLP 编码的 Keccak-256），广播前即可确定
+pub fn evm_tx_hash(signed_hex: &str) -> Result<String, String> {
+    Ok(format!("0x{}", hex::encode(abi::keccak256(&abi::hex_to_bytes(signed_hex)?))))
+}
+
+/// 监听得到最终结果后写入最终状态与回执摘要（回执查询失败时只写状态）
+pub async fn record_final(backend: &Backend, tx_hash: &str, success: bool) {
+    let receipt = match backend {
+        Backend::Evm(rpc) => match EvmReceipt::fetch(rpc, tx_hash).await {
+            Ok(Some(r)) => json!({
+                "block": r.block_number,
+                "gas_used": r.gas_used.to_string(),
+                "effective_gas_price": r.effective_gas_price.to_string(),
+                "fee_wei": r.fee_wei().to_string(),
+                "revert": r.revert,
+            }),
+            Ok(None) => Value::Null,
+            Err(e) => {
+                println!("   ⚠ 查询回执失败，交易记录只写入最终状态: {}", e);
+                Value::Null
+            }
+        },
+        Backend::Tron(api) => match TronReceipt::fetch(api, tx_hash).await {
+            Ok(Some(r)) => json!({
+                "block": r.block_number,
+                "fee_sun": r.fee,
+                "energy": r.energy_usage_total,
+                "net_usage": r.net_usage,
+                "result": r.contract_ret,
+                "reason": r.failure_reason(),
+            }),
+            Ok(None) => Value::Null,
+            Err(e) => {
+                println!("   ⚠ 查询回执失败，交易记录只写入最终状态: {}", e);
+                Value::Null
+            }
+        },
+    };
+    let status = if success { Status::Confirmed } else { Status::Failed };
+    append(json!({ "event": "final", "tx_hash": tx_hash, "status": status.key(), "receipt": receipt }));
+}
+
+/// EVM 监听结束：已最终确认时写入最终状态，超时或重组时保持未完结（可用 resume 继续）
+pub async fn finish_evm(rpc: &EvmRpc, tx_hash: &str, status: &ConfirmationStatus) {
+    match status {
+        ConfirmationStatus::Confirmed => record_final(&Backend::Evm(rpc.clone()), tx_hash, true).await,
+        ConfirmationStatus::Failed => record_final(&Backend::Evm(rpc.clone()), tx_hash, false).await,
+        ConfirmationStatus::Pending | ConfirmationStatus::Reorged => println!("   可稍后运行 resume 继续监听"),
+    }
+}
+
+/// Tron 监听结束：已固化时写入最终状态，超时时保持未完结（可用 resume 继续）
+pub async fn finish_tron(api: &TronApi, tx_id: &str, status: &FinalityStatus) {
+    match status {
+        FinalityStatus::Solidified => record_final(&Backend::Tron(api.clone()), tx_id, true).await,
+        FinalityStatus::Failed => record_final(&Backend::Tron(api.clone()), tx_id, false).await,
+        FinalityStatus::Included | FinalityStatus::Pending => println!("   可稍后运行 resume 继续监听"),
+    }
+}
+
+/// 追加一行事件；写文件失败只提示，不影响发送流程
+fn append(mut event: Value) {
+    event["ts"] = json!(now_ms());
+    let path = config::ledger_path();
+    // 整行一次写入，避免并发进程的行交错
+    let line = format!("{}\n", event);
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| f.write_all(line.as_bytes()));
+    if let Err(e) = result {
+        println!("   ⚠ 写入交易记录 {} 失败: {}", path, e);
+    }
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0)
+}
+
+/// 合并事件后的一笔交易
+#[derive(Clone)]
+struct Record {
+    id: String,
+    chain: String,
+    network: String,
+    from: String,
+    to: String,
+    token: String,
+    amount: String,
+    action: Option<String>,
+    replaces: Option<String>,
+    expiration: Option<i64>,
+    nonce: Option<u128>,
+    tx_hash: String,
+    status: Status,
+    error: String,
+    created_ms: i64,
+    updated_ms: i64,
+    receipt: Value,
+}
+
+/// 读取并合并交易记录，按构建时间排序
+fn load() -> Result<Vec<Record>, String> {
+    let path = config::ledger_path();
+    let text = match std::fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("读取交易记录 {} 失败: {}", path, e)),
+    };
+    let (records, skipped) = fold(&text);
+    if skipped > 0 {
+        println!("⚠ 交易记录中有 {} 行无法解析（可能是中断时写了一半），已跳过", skipped);
+    }
+    Ok(records)
+}
+
+/// 按 id（构建、签名、广播、拒绝）与交易哈希（最终状态）合并事件行，返回记录与无法解析的行数
+fn fold(text: &str) -> (Vec<Record>, usize) {
+    let mut records: Vec<Record> = Vec::new();
+    let (mut by_id, mut by_hash) = (HashMap::new(), HashMap::new());
+    let mut skipped = 0;
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        let event: Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let text = |key: &str| event.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let ts = event.get("ts").and_then(|t| t.as_i64()).unwrap_or(0);
+        let index = match text("event").as_str() {
+            "built" => {
+                by_id.insert(text("id"), records.len());
+                records.push(Record {
+                    id: text("id"),
+                    chain: text("chain"),
+                    network: text("network"),
+                    from: text("from"),
+                    to: text("to"),
+                    token: text("token"),
+                    amount: text("amount"),
+                    action: event.get("action").and_then(|a| a.as_str()).map(|a| a.to_string()),
+                    replaces: event.get("replaces").and_then(|r| r.as_str()).map(|r| r.to_string()),
+                    expiration: event.get("expiration").and_then(|e| e.as_i64()),
+                    nonce: None,
+                    tx_hash: String::new(),
+                    status: Status::Built,
+                    error: String::new(),
+                    created_ms: ts,
+                    updated_ms: ts,
+                    receipt: Value::Null,
+                });
+                continue;
+            }
+            "final" => by_hash.get(&text("tx_hash").to_lowercase()).copied(),
+            _ => by_id.get(&text("id")).copied(),
+        };
+        // 找不到对应记录（built 行缺失或哈希未知）的事件跳过，不能并入其他记录
+        let Some(index) = index else {
+            continue;
+        };
+        let record = &mut records[index];
+        record.updated_ms = ts;
+        match text("event").as_str() {
+            "signed" | "broadcast" => {
+                if !record.tx_hash.is_empty() {
+                    by_hash.remove(&record.tx_hash.to_lowercase());
+                }
+                record.tx_hash = text("tx_hash");
+                by_hash.insert(record.tx_hash.to_lowercase(), index);
+                if let Some(n) = event.get("nonce").and_then(|n| n.as_u64()) {
+                    record.nonce = Some(n as u128);
+                }
+                record.status = if text("event") == "signed" { Status::Signed } else { Status::Sent };
+                record.error.clear();
+            }
+            "rejected" => {
+                record.status = Status::Rejected;
+                record.error = text("error");
+            }
+            "final" => {
+                record.status = Status::parse(&text("status")).unwrap_or(record.status);
+                if !event["receipt"].is_null() {
+                    record.receipt = event["receipt"].clone();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 同一账户同一 nonce 已有最终状态的交易时，其余未完结的交易已被替换
+    let settled: Vec<(String, String, u128)> = records
+        .iter()
+        .filter(|r| matches!(r.status, Status::Confirmed | Status::Failed))
+        .filter_map(|r| Some((r.network.clone(), r.from.to_lowercase(), r.nonce?)))
+        .collect();
+    for r in records.iter_mut().filter(|r| r.status.is_open() && r.chain == "evm") {
+        if let Some(n) = r.nonce {
+            if settled.contains(&(r.network.clone(), r.from.to_lowercase(), n)) {
+                r.status = Status::Replaced;
+            }
+        }
+    }
+    (records, skipped)
+}
+
+/// `history [N | --all | --open | <交易哈希或记录 id>]`：列出本地交易记录
+///
+/// 默认显示最近 20 笔；`--open` 只显示未完结的交易；给出哈希或 id 时显示该笔详情。
+pub fn run_history(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let records = load()?;
+    let arg = args.first().map(|s| s.as_str());
+    if let Some(key) = arg.filter(|a| !a.starts_with("--") && a.parse::<usize>().is_err()) {
+        match records
+            .iter()
+            .rev()
+            .find(|r| r.id == key || r.tx_hash.eq_ignore_ascii_case(key))
+        {
+            Some(r) => print_detail(r),
+            None => println!("交易记录 {} 中未找到 {}", config::ledger_path(), key),
+        }
+        return Ok(());
+    }
+
+    let selected: Vec<&Record> = match arg {
+        Some("--open") => records.iter().filter(|r| r.status.is_open()).collect(),
+        _ => records.iter().collect(),
+    };
+    let limit = match arg {
+        Some("--all") | Some("--open") => selected.len(),
+        Some(n) => n.parse().map_err(|_| format!("未知参数 {}（可选: N | --all | --open | <交易哈希>）", n))?,
+        None => DEFAULT_HISTORY_LIMIT,
+    };
+    println!("=== 本地交易记录（{}）===", config::ledger_path());
+    if selected.is_empty() {
+        println!("暂无记录");
+        return Ok(());
+    }
+    let shown = &selected[selected.len().saturating_sub(limit)..];
+    if shown.len() < selected.len() {
+        println!("共 {} 笔，显示最近 {} 笔（history --all 显示全部）", selected.len(), shown.len());
+    }
+    println!();
+    for r in shown {
+        let target = match &r.action {
+            Some(action) if r.to.is_empty() => action.clone(),
+            Some(action) => format!("{} → {}", action, r.to),
+            None => format!("→ {}", r.to),
+        };
+        println!(
+            "{}  {:<5} {:<16} {:<8} {} {}  {}",
+            format_time_ms(r.created_ms),
+            r.chain,
+            r.network,
+            r.status.label(),
+            r.amount,
+            r.token,
+            target
+        );
+        let mut detail = Vec::new();
+        if !r.tx_hash.is_empty() {
+            detail.push(r.tx_hash.clone());
+        }
+        if let Some(n) = r.nonce {
+            detail.push(format!("nonce {}", n));
+        }
+        if let Some(h) = &r.replaces {
+            detail.push(format!("替换 {}", h));
+        }
+        if !r.error.is_empty() {
+            detail.push(r.error.clone());
+        }
+        if !detail.is_empty() {
+            println!("    {}", detail.join("  "));
+        }
+    }
+    let open = records.iter().filter(|r| r.status.is_open()).count();
+    if open > 0 {
+        println!("\n{} 笔交易尚未最终确认，可运行 resume 继续监听", open);
+    }
+    Ok(())
+}
+
+/// 单笔交易详情
+fn print_detail(r: &Record) {
+    println!("=== 交易记录 {} ===", r.id);
+    println!("链:       {}（{}）", r.chain, r.network);
+    println!("状态:     {}", r.status.label());
+    println!("发送方:   {}", r.from);
+    if !r.to.is_empty() {
+        println!("接收方:   {}", r.to);
+    }
+    if let Some(action) = &r.action {
+        println!("类型:     {}", action);
+    }
+    println!("金额:     {} {}", r.amount, r.token);
+    if let Some(n) = r.nonce {
+        println!("nonce:    {}", n);
+    }
+    if !r.tx_hash.is_empty() {
+        println!("交易哈希: {}", r.tx_hash);
+    }
+    if let Some(h) = &r.replaces {
+        println!("替换交易: {}", h);
+    }
+    if !r.error.is_empty() {
+        println!("错误:     {}", r.error);
+    }
+    println!("构建时间: {}", format_time_ms(r.created_ms));
+    println!("最后更新: {}", format_time_ms(r.updated_ms));
+    if !r.receipt.is_null() {
+        println!("回执摘要: {}", r.receipt);
+    }
+}
+
+/// 单次查询结果
+enum Probe {
+    /// 未查到，仍可能上链
+    Waiting,
+    /// 已上链，未达到最终确认
+    Included(u64),
+    /// 已最终确认
+    Final { block: u64, success: bool },
+    /// 确定不会再上链（nonce 已被其他交易使用 / Tron 交易已过期）
+    Gone(Status),
+}
+
+/// 查询一笔未完结交易的状态
+async fn probe(backend: &Backend, r: &Record, head: u64, depth: u64) -> Result<Probe, RpcError> {
+    if let Some(inc) = backend.inclusion(&r.tx_hash).await? {
+        // 达到确认数后复核区块哈希：回执所在区块已被重组掉时按已上链继续等待，下一轮重新查询回执
+        return Ok(if head + 1 >= inc.block + depth && backend.is_canonical(&inc).await? {
+            Probe::Final { block: inc.block, success: inc.success }
+        } else {
+            Probe::Included(inc.block)
+        });
+    }
+    match backend {
+        Backend::Evm(rpc) => {
+            let Some(nonce) = r.nonce else { return Ok(Probe::Waiting) };
+            if rpc.get_transaction(&r.tx_hash).await?.is_null()
+                && rpc.get_transaction_count(&r.from, "latest").await? > nonce
+            {
+                return Ok(Probe::Gone(Status::Replaced));
+            }
+        }
+        Backend::Tron(api) => {
+            let Some(expiration) = r.expiration else { return Ok(Probe::Waiting) };
+            // 过期后留 3 秒余量，避免与节点时钟差异
+            if now_ms() > expiration + 3000 && api.get_transaction_by_id(&r.tx_hash).await?.get("txID").is_none() {
+                return Ok(Probe::Gone(Status::Expired));
+            }
+        }
+    }
+    Ok(Probe::Waiting)
+}
+
+/// 按记录中的网络名称准备查询后端与最终确认深度；网络未知或节点链 ID 不符时返回 Err(原因)
+async fn backend_for(chain: &str, network: &str) -> Result<(Backend, u64), Box<dyn std::error::Error>> {
+    if chain == "evm" {
+        let net = config::evm_networks()?
+            .into_iter()
+            .find(|n| n.key == network)
+            .ok_or_else(|| format!("未知的 EVM 网络 {}（不在内置网络与 EVM_NETWORKS_FILE 中）", network))?;
+        let rpc = EvmRpc::new(config::evm_rpc_url(&net).await);
+        ChainIdentity::check(&rpc, &net).await.require_for_signing()?;
+        let depth = batch_monitor::env_number("MONITOR_MIN_CONFIRMATIONS")?
+            .unwrap_or(net.finality_depth.unwrap_or(1))
+            .max(1);
+        Ok((Backend::Evm(rpc), depth))
+    } else {
+        let net = config::TRON_NETWORKS
+            .into_iter()
+            .find(|n| config::tron_network_key(*n) == network)
+            .ok_or_else(|| format!("未知的 Tron 网络 {}", network))?;
+        Ok((Backend::Tron(TronApi::new(config::tron_api_url(net))), 1))
+    }
+}
+
+/// `resume`：对交易记录中已签名或已广播、尚无最终状态的交易重新监听，得到结果后补写最终状态
+///
+/// 按记录中的网络分组监听（与当前 EVM_NETWORK / TRON_NETWORK 无关）；EVM_RPC_URL / TRON_RPC_URL 若已设置，
+/// 须属于记录所在网络（EVM 会校验链 ID，不符时跳过该组）。未上链且 nonce 已被其他交易使用的 EVM 交易记为已被替换，
+/// 过期仍未上链的 Tron 交易记为已过期。
+///
+/// 环境变量：
+/// - MONITOR_TIMEOUT_SEC / MONITOR_POLL_MS / MONITOR_CONCURRENCY / MONITOR_MIN_CONFIRMATIONS（可选）：同 monitor-batch
+/// - LEDGER_FILE（可选）：交易记录文件
+pub async fn run_resume() -> Result<(), Box<dyn std::error::Error>> {
+    let records = load()?;
+    println!("=== 继续监听未完结交易（{}）===", config::ledger_path());
+    let mut groups: BTreeMap<(String, String), Vec<Record>> = BTreeMap::new();
+    for r in records.iter().filter(|r| r.status.is_open() && !r.tx_hash.is_empty()) {
+        groups.entry((r.chain.clone(), r.network.clone())).or_default().push(r.clone());
+    }
+    if groups.is_empty() {
+        println!("没有需要继续监听的交易");
+        return Ok(());
+    }
+
+    let timeout_sec: u64 = batch_monitor::env_number("MONITOR_TIMEOUT_SEC")?.unwrap_or(600);
+    let poll_ms: u64 = batch_monitor::env_number("MONITOR_POLL_MS")?.unwrap_or(3000);
+    let concurrency = batch_monitor::env_number::<usize>("MONITOR_CONCURRENCY")?
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .max(1);
+    let mut unresolved: Vec<(Record, String)> = Vec::new();
+    for ((chain, network), mut pending) in groups {
+        println!("\n[{} {}] {} 笔", chain, network, pending.len());
+        let (backend, depth) = match backend_for(&chain, &network).await {
+            Ok(b) => b,
+            Err(e) => {
+                println!("⚠ 跳过: {}", e);
+                unresolved.extend(pending.into_iter().map(|r| (r, "未监听".to_string())));
+                continue;
+            }
+        };
+        println!("最终确认: {}，轮询: {}ms，超时: {}s", if chain == "evm" { format!("{} 个确认", depth) } else { "所在区块已固化".to_string() }, poll_ms, timeout_sec);
+
+        let started = Instant::now();
+        let deadline = started + Duration::from_secs(timeout_sec);
+        let semaphore = Arc::new(Semaphore::new(concurrency));
+        let mut included: HashMap<String, u64> = HashMap::new();
+        while !pending.is_empty() {
+            let head = match backend.head().await {
+                Ok(h) => h,
+                Err(e) => {
+                    println!("⚠ 查询链头失败，稍后重试: {}", e);
+                    if Instant::now() >= deadline {
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+                    continue;
+                }
+            };
+            let mut tasks = JoinSet::new();
+            for (i, r) in pending.iter().enumerate() {
+                let (backend, r, semaphore) = (backend.clone(), r.clone(), semaphore.clone());
+                tasks.spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    (i, probe(&backend, &r, head, depth).await)
+                });
+            }
+            let mut done = Vec::new();
+            while let Some(joined) = tasks.join_next().await {
+                let (i, result) = match joined {
+                    Ok((i, Ok(p))) => (i, p),
+                    _ => continue,
+                };
+                let r = &pending[i];
+                let elapsed = started.elapsed().as_secs();
+                match result {
+                    Probe::Waiting => {}
+                    Probe::Included(block) => {
+                        if included.insert(r.tx_hash.clone(), block) != Some(block) {
+                            println!("[{:>4}s] {}  已上链（区块 {}），等待最终确认", elapsed, r.tx_hash, block);
+                        }
+                    }
+                    Probe::Final { block, success } => {
+                        println!("[{:>4}s] {}  {}（区块 {}）", elapsed, r.tx_hash, if success { "成功" } else { "失败" }, block);
+                        record_final(&backend, &r.tx_hash, success).await;
+                        done.push(i);
+                    }
+                    Probe::Gone(status) => {
+                        println!("[{:>4}s] {}  {}", elapsed, r.tx_hash, status.label());
+                        append(json!({ "event": "final", "tx_hash": r.tx_hash, "status": status.key(), "receipt": Value::Null }));
+                        done.push(i);
+                    }
+                }
+            }
+            done.sort_unstable();
+            for i in done.into_iter().rev() {
+                pending.remove(i);
+            }
+            if pending.is_empty() || Instant::now() >= deadline {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+        }
+        unresolved.extend(pending.into_iter().map(|r| {
+            let note = match included.get(&r.tx_hash) {
+                Some(block) => format!("已上链（区块 {}），未达到最终确认", block),
+                None => "仍未查到".to_string(),
+            };
+            (r, note)
+        }));
+    }
+
+    println!();
+    if unresolved.is_empty() {
+        println!("✅ 全部交易已有最终状态，可运行 history 查看");
+        return Ok(());
+    }
+    println!("[仍未完结]（可再次运行 resume）");
+    for (r, note) in unresolved {
+        println!("  {} {}  {} {}  {}", r.chain, r.network, r.amount, r.token, r.tx_hash);
+        println!("    {}", note);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn built(id: &str, from: &str, ts: i64) -> String {
+        json!({ "ts": ts, "event": "built", "id": id, "chain": "evm", "network": "sepolia", "from": from,
+                "to": "0xbbb", "token": "ETH", "amount": "0.1", "raw": "100000000000000000" })
+        .to_string()
+    }
+
+    fn event(ts: i64, event: &str, id: &str, hash: &str, nonce: Option<u64>) -> String {
+        json!({ "ts": ts, "event": event, "id": id, "tx_hash": hash, "nonce": nonce }).to_string()
+    }
+
+    fn final_line(ts: i64, hash: &str, status: &str) -> String {
+        json!({ "ts": ts, "event": "final", "tx_hash": hash, "status": status, "receipt": { "block": 7 } }).to_string()
+    }
+
+    #[test]
+    fn fold_follows_lifecycle() {
+        let text = [
+            built("r1", "0xaaa", 1),
+            event(2, "signed", "r1", "0xH1", Some(5)),
+            event(3, "broadcast", "r1", "0xH1", None),
+            final_line(4, "0xh1", "confirmed"),
+        ]
+        .join("\n");
+        let (records, skipped) = fold(&text);
+        assert_eq!((records.len(), skipped), (1, 0));
+        let r = &records[0];
+        assert_eq!(r.status.key(), "confirmed");
+        assert_eq!((r.tx_hash.as_str(), r.nonce), ("0xH1", Some(5)));
+        assert_eq!((r.created_ms, r.updated_ms), (1, 4));
+        assert_eq!(r.receipt["block"], 7);
+    }
+
+    #[test]
+    fn fold_intermediate_states() {
+        let text = [
+            built("r1", "0xaaa", 1),
+            event(2, "signed", "r1", "0xh1", Some(1)),
+            built("r2", "0xaaa", 3),
+            event(4, "signed", "r2", "0xh2", Some(2)),
+            event(5, "broadcast", "r2", "0xh2", None),
+            built("r3", "0xaaa", 6),
+            json!({ "ts": 7, "event": "rejected", "id": "r3", "error": "insufficient funds" }).to_string(),
+            built("r4", "0xaaa", 8),
+            // 未知 id 与未知哈希的事件忽略
+            event(9, "broadcast", "nope", "0xh9", None),
+            final_line(10, "0xh9", "confirmed"),
+        ]
+        .join("\n");
+        let (records, _) = fold(&text);
+        let status: Vec<&str> = records.iter().map(|r| r.status.key()).collect();
+        assert_eq!(status, ["signed", "sent", "rejected", "built"]);
+        assert_eq!(records[2].error, "insufficient funds");
+    }
+
+    #[test]
+    fn fold_marks_same_nonce_as_replaced() {
+        let text = [
+            built("orig", "0xAAA", 1),
+            event(2, "signed", "orig", "0xh1", Some(9)),
+            event(2, "broadcast", "orig", "0xh1", None),
+            built("speedup", "0xaaa", 3),
+            event(4, "signed", "speedup", "0xh2", Some(9)),
+            event(5, "broadcast", "speedup", "0xh2", None),
+            built("other", "0xaaa", 6),
+            event(7, "signed", "other", "0xh3", Some(10)),
+            final_line(8, "0xh2", "failed"),
+        ]
+        .join("\n");
+        let (records, _) = fold(&text);
+        let status: Vec<&str> = records.iter().map(|r| r.status.key()).collect();
+        assert_eq!(status, ["replaced", "failed", "signed"]);
+    }
+
+    #[test]
+    fn fold_skips_half_written_line() {
+        let mut text = [built("r1", "0xaaa", 1), event(2, "signed", "r1", "0xh1", Some(1))].join("\n");
+        text.push('\n');
+        text.push_str(&event(3, "broadcast", "r1", "0xh1", None)[..20]);
+        let (records, skipped) = fold(&text);
+        assert_eq!(skipped, 1);
+        assert_eq!(records[0].status.key(), "signed");
+        assert_eq!(fold("\n\n").1, 0);
+    }
+
+    #[test]
+    fn fold_uses_broadcast_hash() {
+        // 节点返回的哈希与签名时计算的不同：最终状态按广播哈希关联
+        let text = [
+            built("r1", "0xaaa", 1),
+            event(2, "signed", "r1", "0xcomputed", Some(1)),
+            event(3, "broadcast", "r1", "0xreturned", None),
+            final_line(4, "0xcomputed", "failed"),
+        ]
+        .join("\n");
+        let (records, _) = fold(&text);
+        assert_eq!((records[0].tx_hash.as_str(), records[0].status.key()), ("0xreturned", "sent"));
+        assert_eq!(records[0].nonce, Some(1));
+
+        let text = format!("{}\n{}", text, final_line(5, "0xRETURNED", "confirmed"));
+        assert_eq!(fold(&text).0[0].status.key(), "confirmed");
+    }
+
+    #[test]
+    fn decode_transfer_calldata() {
+        let mut data = vec![0xa9, 0x05, 0x9c, 0xbb];
+        data.extend([0u8; 12]);
+        data.extend([0x11u8; 20]);
+        data.extend([0u8; 31]);
+        data.push(42);
+        assert_eq!(decode_transfer(&data), Some(([0x11; 20], 42)));
+        // 截断、selector 不同、金额超出 u128
+        assert_eq!(decode_transfer(&data[..67]), None);
+        let mut approve = data.clone();
+        approve[..4].copy_from_slice(&[0x09, 0x5e, 0xa7, 0xb3]);
+        assert_eq!(decode_transfer(&approve), None);
+        let mut huge = data.clone();
+        huge[40] = 1;
+        assert_eq!(decode_transfer(&huge), None);
+        assert_eq!(decode_transfer(&[]), None);
+    }
+}
@@ -1,12 +1,33 @@
+mod abi;
+mod arbitrum;
+mod batch_monitor;
+mod batch_send;
 mod config;
+mod consistency;
 mod erc20;
+mod events;
+mod evm_chain;
+mod evm_fees;
+mod evm_monitor;
+mod evm_replace;
+mod evm_rpc;
+mod ledger;
+mod nonce_manager;
+mod receipt;
+mod rpc;
+mod rpc_probe;
+mod sdk;
+mod trc10;
 mod trc20;
+mod tron_account;
+mod tron_monitor;
+mod tron_rpc;
+mod tron_stake;
+mod tx;
+mod watch;
 
-use chains_sdk::balance::BalanceProvider;
-use chains_sdk::chain::tron::TronChain;
-use chains_sdk::transaction::{TransactionMonitor, TransactionStatus};
+use sdk::TronSdk;
 use std::env;
-use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -16,20 +37,45 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     match cmd.as_str() {
         // Tron (Nile / Mainnet)
         "tron-balance" => run_tron_balance().await?,
+        "tron-account" => tron_account::run_tron_account().await?,
         "tron-trc20" => trc20::run_trc20_demo().await?,
         "tron-usdt-balance" => trc20::run_usdt_balance().await?,
         "tron-verify-trc20" => trc20::run_verify_trc20().await?,
         "tron-transfer" => trc20::run_trx_transfer().await?,
         "tron-full-flow" => trc20::run_full_flow().await?,
         "tron-monitor" => run_tron_monitor().await?,
+        // Tron TRC10
+        "tron-trc10-info" => trc10::run_trc10_info().await?,
+        "tron-trc10-transfer" => trc10::run_trc10_transfer().await?,
+        // Tron Stake 2.0
+        "tron-freeze" => tron_stake::run_freeze().await?,
+        "tron-unfreeze" => tron_stake::run_unfreeze().await?,
+        "tron-withdraw-unfreeze" => tron_stake::run_withdraw_unfreeze().await?,
+        "tron-delegate" => tron_stake::run_delegate().await?,
+        "tron-undelegate" => tron_stake::run_undelegate().await?,
         // Ethereum 原生 (Sepolia)
         "eth-balance" => erc20::run_eth_balance().await?,
-        "eth-transfer" => erc20::run_eth_transfer().await?,
+        "eth-transfer" => erc20::run_eth_transfer(&args.collect::<Vec<_>>()).await?,
         "eth-monitor" => erc20::run_eth_monitor().await?,
+        "eth-fees" => evm_fees::run_eth_fees().await?,
+        "nonce" => nonce_manager::run_nonce(&args.collect::<Vec<_>>()).await?,
         // ERC20 (Sepolia)
         "erc20-demo" => erc20::run_erc20_demo().await?,
         "erc20-verify" => erc20::run_verify_erc20().await?,
-        "erc20-full-flow" => erc20::run_full_flow_erc20().await?,
+        "erc20-full-flow" => erc20::run_full_flow_erc20(&args.collect::<Vec<_>>()).await?,
+        // 交易查询（EVM / Tron）
+        "tx" => tx::run_tx(&args.collect::<Vec<_>>()).await?,
+        "monitor-batch" => batch_monitor::run_monitor_batch(&args.collect::<Vec<_>>()).await?,
+        // 批量出款（EVM / Tron）
+        "batch-send" => batch_send::run_batch_send(&args.collect::<Vec<_>>()).await?,
+        // 本地交易记录
+        "history" => ledger::run_history(&args.collect::<Vec<_>>())?,
+        "resume" => ledger::run_resume().await?,
+        // 入账监控（EVM / Tron）
+        "watch" => watch::run_watch(&args.collect::<Vec<_>>()).await?,
+        // RPC 节点
+        "rpc" => rpc_probe::run_rpc(&args.collect::<Vec<_>>()).await?,
+        "consistency" => consistency::run_consistency(&args.collect::<Vec<_>>()).await?,
         "help" | "-h" | "--help" | _ => print_usage(cmd.as_str()),
     }
 
@@ -49,6 +95,7 @@ fn print_usage(unknown: &str) {
     eprintln!();
     eprintln!("Tron:");
     eprintln!("  tron-balance          TRX 余额");
+    eprintln!("  tron-account          账户资源（带宽/能量/质押/代理/待解锁/TRC10/权限，TRON_ADDRESS）");
     eprintln!("  tron-trc20            TRC20 代币信息与构建转账");
     eprintln!("  tron-usdt-balance     USDT 余额（按 TRON_NETWORK）");
     eprintln!("  tron-verify-trc20     验证 TRC20 API");
@@ -56,15 +103,49 @@ fn print_usage(unknown: &str) {
     eprintln!("  tron-full-flow        TRC20 全流程（构建→签名→广播→监听）");
     eprintln!("  tron-monitor          监听 Tron 交易（TX_HASH）");
     eprintln!();
+    eprintln!("Tron TRC10:");
+    eprintln!("  tron-trc10-info       TRC10 资产信息（TRC10_ASSET_ID）");
+    eprintln!("  tron-trc10-transfer   TRC10 转账全流程（TRC10_ASSET_ID、TRC10_AMOUNT）");
+    eprintln!();
+    eprintln!("Tron Stake 2.0:");
+    eprintln!("  tron-freeze           质押 TRX 获取资源（STAKE_AMOUNT_SUN、STAKE_RESOURCE）");
+    eprintln!("  tron-unfreeze         解质押（STAKE_AMOUNT_SUN、STAKE_RESOURCE）");
+    eprintln!("  tron-withdraw-unfreeze 提取到期解质押");
+    eprintln!("  tron-delegate         代理资源（TRON_RECEIVER_ADDRESS、DELEGATE_LOCK_PERIOD）");
+    eprintln!("  tron-undelegate       取消代理资源");
+    eprintln!();
     eprintln!("EVM 原生 ETH:");
     eprintln!("  eth-balance           原生 ETH 余额");
-    eprintln!("  eth-transfer          原生 ETH 转账全流程");
+    eprintln!("  eth-transfer [--nonce N] 原生 ETH 转账全流程");
     eprintln!("  eth-monitor           监听交易（TX_HASH，含 ETH/ERC20）");
+    eprintln!("  eth-fees              当前 baseFee 与 slow/normal/fast 费用档位（eth_feeHistory）");
+    eprintln!("  nonce list|fill|reset 本地 nonce 记录：查看空缺 / 以 0 金额自转账补齐 / 清除");
     eprintln!();
     eprintln!("ERC20:");
     eprintln!("  erc20-demo            ERC20 代币信息与构建转账");
     eprintln!("  erc20-verify          验证 ERC20 API");
-    eprintln!("  erc20-full-flow       ERC20 全流程（构建→签名→广播→监听）");
+    eprintln!("  erc20-full-flow [--nonce N] ERC20 全流程（构建→签名→广播→监听）");
+    eprintln!();
+    eprintln!("交易查询:");
+    eprintln!("  tx show <hash>        查询并解析任意交易（0x 开头为 EVM，否则为 Tron）");
+    eprintln!("  tx speedup <hash>     同 nonce 提价重发卡住的 EVM 交易（需 ETH_PRIVATE_KEY）");
+    eprintln!("  tx cancel <hash>      同 nonce 0 金额自转账取消卡住的 EVM 交易（需 ETH_PRIVATE_KEY）");
+    eprintln!("  monitor-batch [hash...] 批量监听交易直至最终确认（- 读标准输入，或 MONITOR_HASH_FILE）");
+    eprintln!();
+    eprintln!("批量出款:");
+    eprintln!("  batch-send <csv> [--send] 按 CSV（recipient,amount,token,memo）校验、预览合计与费用，加 --send 发送；结果写入 .results.csv，中断后重跑可续");
+    eprintln!();
+    eprintln!("交易记录:");
+    eprintln!("  history [N|--all|--open|hash] 查看本工具发送过的交易（LEDGER_FILE，默认 .tx-ledger.jsonl）");
+    eprintln!("  resume                  继续监听记录中未最终确认的交易并补写最终状态");
+    eprintln!();
+    eprintln!("入账监控:");
+    eprintln!("  watch [地址...]       跟随新区块报告转入地址的原生币/代币（或 WATCH_ADDRESSES）");
+    eprintln!();
+    eprintln!("RPC 节点:");
+    eprintln!("  rpc probe [网络...]   并发探测全部已知端点（延迟/链头落后/链 ID），为后续命令选用最快的健康端点");
+    eprintln!("  consistency <地址> [代币...] 在当前网络的每个端点查询同一地址的余额等状态并比对（EVM 同一区块，Tron 固化状态）");
+    eprintln!("  （调用失败时在备用端点间故障转移，含余额、构建与广播）");
     eprintln!();
     eprintln!("  help                  显示此帮助");
 }
@@ -72,19 +153,19 @@ fn print_usage(unknown: &str) {
 /// 查询 TRX 余额（网络由 TRON_NETWORK 指定，默认 nile）
 async fn run_tron_balance() -> Result<(), Box<dyn std::error::Error>> {
     let network = trc20::current_tron_network();
-    let chain = Arc::new(TronChain::from_network(network));
-    let balance_provider = BalanceProvider::new(chain.clone());
 
     println!("=== TRX 余额查询（{}）===", network.name());
     println!("地址: {}", config::TRON_EXAMPLE_ADDR);
 
-    let b = balance_provider.get_balance(&config::TRON_EXAMPLE_ADDR.to_string()).await?;
-    println!("TRX 余额: {}", b.balance);
+    let balance = TronSdk::new(network).trx_balance(config::TRON_EXAMPLE_ADDR).await?;
+    println!("TRX 余额: {}", balance);
 
     Ok(())
 }
 
-/// 监听 Tron 交易（TX_HASH，网络由 TRON_NETWORK 指定）
+/// 监听 Tron 交易直至固化（TX_HASH，网络由 TRON_NETWORK 指定），结束后打印交易回执（能量、带宽、手续费、失败原因、代币事件）
+///
+/// 超时与轮询间隔：TRON_MONITOR_TIMEOUT_SEC（默认 120）、TRON_MONITOR_POLL_MS（默认 3000）
 async fn run_tron_monitor() -> Result<(), Box<dyn std::error::Error>> {
     let tx_hash = match env::var("TX_HASH") {
         Ok(v) => v,
@@ -96,20 +177,17 @@ async fn run_tron_monitor() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let network = trc20::current_tron_network();
-    let chain = Arc::new(TronChain::from_network(network));
-    let monitor = TransactionMonitor::new(chain);
+    let api = tron_rpc::TronApi::new(config::tron_api_url(network));
+    let (timeout_sec, poll_ms) = (config::tron_monitor_timeout_sec(), config::tron_monitor_poll_ms());
 
     println!("=== 监听 Tron 交易（{}）===", network.name());
     println!("交易哈希: {}", tx_hash);
+    println!("等待上链与固化（超时 {}s，轮询 {}ms）...", timeout_sec, poll_ms);
 
-    let status = monitor
-        .wait_for_confirmation(&tx_hash, Some(10), Some(3000), Some(20))
-        .await?;
-
-    match status {
-        TransactionStatus::Confirmed => println!("✅ 交易已确认!"),
-        TransactionStatus::Failed => println!("❌ 交易失败!"),
-        TransactionStatus::Pending => println!("⏳ 交易仍在等待确认（达到最大尝试次数）"),
+    let status = tron_monitor::wait_for_finality(&api, &tx_hash, timeout_sec, poll_ms).await;
+    trc20::print_finality_status(&status);
+    if !matches!(status, tron_monitor::FinalityStatus::Pending) {
+        receipt::print_tron_receipt(network, &tx_hash).await;
     }
 
     Ok(())
@@ -0,0 +1,521 @@
+//! EVM 本地 nonce 管理：按网络与账户在状态文件（NONCE_STATE_FILE）中记录已分配的 nonce 与交易哈希
+//!
+//! 连续发送（如 eth-transfer 后紧接 erc20-full-flow）时以本地记录与节点 pending 计数的较大者分配 nonce，
+//! 避免各自向节点查询导致冲突；支持 `--nonce` 显式指定，`nonce list` 查看空缺，`nonce fill` 以 0 金额自转账补齐。
+//!
+//! 状态文件格式：`{ "<网络>:<小写地址>": { "next": 下一个 nonce, "sent": { "<nonce>": "<交易哈希>" } } }`，
+//! nonce 小于链上 latest 计数的记录（已上链）在每次读取时清理。`reserved_at` 为最近一次分配的 Unix 秒。
+//!
+//! 多个进程可能同时发送，读改写状态文件期间持有锁文件 `<状态文件>.lock`（create_new 独占创建，退出时删除）。
+
+use crate::config;
+use crate::evm_chain::{self, signer_address, ChainIdentity};
+use crate::evm_fees::{self, FeeSettings};
+use crate::evm_rpc::EvmRpc;
+use crate::ledger::{self, TxInfo};
+use crate::sdk::EvmSdk;
+use chains_sdk::chain::evm::sign_ethereum_transaction;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 分配后尚未记录交易哈希的 nonce 视为仍在签名广播中的时长（秒），期间不回退到节点 pending 计数
+const RESERVE_GRACE_SECS: u64 = 120;
+/// 等待状态文件锁的最长时间
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+/// 锁文件存在超过该时长视为持有进程已异常退出（持锁期间只读写本地文件，不会持续这么久）
+const STALE_LOCK: Duration = Duration::from_secs(30);
+
+/// 单个账户的本地记录
+#[derive(Default, Clone)]
+struct AccountState {
+    next: u128,
+    sent: BTreeMap<u128, String>,
+    reserved_at: u64,
+}
+
+impl AccountState {
+    /// 清理已上链的记录，并保证 next 不低于链上计数
+    fn prune(&mut self, latest: u128) {
+        self.sent.retain(|n, _| *n >= latest);
+        self.next = self.next.max(latest);
+    }
+
+    /// 本地已知的 nonce 上界（不含）
+    fn upper(&self) -> u128 {
+        let max_sent = self.sent.keys().next_back().map(|n| n + 1).unwrap_or(0);
+        self.next.max(max_sent)
+    }
+}
+
+/// 整个状态文件
+struct StateFile {
+    accounts: Value,
+}
+
+impl StateFile {
+    fn load() -> Result<Self, String> {
+        let path = config::nonce_state_path();
+        let accounts = match std::fs::read_to_string(&path) {
+            Ok(s) if !s.trim().is_empty() => {
+                serde_json::from_str(&s).map_err(|e| format!("nonce 状态文件 {} 格式错误: {}", path, e))?
+            }
+            _ => json!({}),
+        };
+        if !accounts.is_object() {
+            return Err(format!("nonce 状态文件 {} 应为 JSON 对象", path));
+        }
+        Ok(Self { accounts })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = config::nonce_state_path();
+        let body = serde_json::to_string_pretty(&self.accounts).map_err(|e| e.to_string())?;
+        std::fs::write(&path, body).map_err(|e| format!("写入 nonce 状态文件 {} 失败: {}", path, e))
+    }
+
+    fn get(&self, key: &str) -> AccountState {
+        let v = match self.accounts.get(key) {
+            Some(v) => v,
+            None => return AccountState::default(),
+        };
+        let sent = v
+            .get("sent")
+            .and_then(|s| s.as_object())
+            .map(|m| {
+                m.iter()
+                    .filter_map(|(n, h)| Some((n.parse().ok()?, h.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        AccountState {
+            next: v.get("next").and_then(|n| n.as_u64()).unwrap_or(0) as u128,
+            sent,
+            reserved_at: v.get("reserved_at").and_then(|t| t.as_u64()).unwrap_or(0),
+        }
+    }
+
+    fn put(&mut self, key: &str, state: &AccountState) {
+        let sent: serde_json::Map<String, Value> =
+            state.sent.iter().map(|(n, h)| (n.to_string(), json!(h))).collect();
+        self.accounts[key] = json!({ "next": state.next as u64, "sent": sent, "reserved_at": state.reserved_at });
+    }
+}
+
+/// 状态文件锁：持有期间其他进程的读改写等待，drop 时删除锁文件
+struct StateLock {
+    path: String,
+}
+
+impl StateLock {
+    async fn acquire() -> Result<Self, String> {
+        let path = format!("{}.lock", config::nonce_state_path());
+        let started = SystemTime::now();
+        let mut wait = Duration::from_millis(20);
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Self { path }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(format!("创建 nonce 锁文件 {} 失败: {}", path, e)),
+            }
+            let age = std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.elapsed().ok());
+            if age.is_some_and(|a| a > STALE_LOCK) {
+                println!("   ⚠ nonce 锁文件 {} 已存在超过 {}s，视为遗留并删除", path, STALE_LOCK.as_secs());
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+            if started.elapsed().unwrap_or_default() > LOCK_TIMEOUT {
+                return Err(format!(
+                    "等待 nonce 锁文件 {} 超时（其他进程正在分配 nonce；若没有，可手动删除该文件）",
+                    path
+                ));
+            }
+            // 持锁只涉及本地文件读写，短暂等待即可；异步等待不占用运行时线程（batch-send 等并发任务共用）
+            tokio::time::sleep(wait).await;
+            wait = (wait * 2).min(Duration::from_millis(200));
+        }
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// 持锁读取、修改并写回一个账户的记录
+async fn update<T>(key: &str, f: impl FnOnce(&mut AccountState) -> T) -> Result<T, String> {
+    let _lock = StateLock::acquire().await?;
+    let mut file = StateFile::load()?;
+    let mut state = file.get(key);
+    let out = f(&mut state);
+    file.put(key, &state);
+    file.save()?;
+    Ok(out)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn account_key(network: &str, address: &str) -> String {
+    format!("{}:{}", network, address.to_lowercase())
+}
+
+/// 解析命令参数中的 `--nonce N` / `--nonce=N`
+pub fn parse_nonce_arg(args: &[String]) -> Result<Option<u128>, String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let value = if arg == "--nonce" {
+            iter.next().ok_or("--nonce 需要一个整数参数")?.as_str()
+        } else if let Some(v) = arg.strip_prefix("--nonce=") {
+            v
+        } else {
+            continue;
+        };
+        return value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("--nonce 应为非负整数，当前: {}", value));
+    }
+    Ok(None)
+}
+
+/// 为下一笔交易分配 nonce 并写入状态文件
+///
+/// 未显式指定时取 max(链上 latest, 节点 pending, 本地 next)；本地 next 高于 pending、其间记录的交易都已不在交易池
+/// 且最近 RESERVE_GRACE_SECS 内没有新分配时，视为本地记录过期，回退到 pending。
+/// 显式指定低于链上 latest 时报错，高于下一个可用 nonce 时提示将产生空缺。
+pub async fn reserve(
+    rpc: &EvmRpc,
+    network: &str,
+    address: &str,
+    explicit: Option<u128>,
+) -> Result<u128, Box<dyn std::error::Error>> {
+    let latest = rpc.get_transaction_count(address, "latest").await?;
+    let pool = rpc.get_transaction_count(address, "pending").await?;
+    let key = account_key(network, address);
+
+    // 查询交易状态需要访问节点，在持锁前按快照判断；持锁后本地 next 已被其他进程改动则不回退
+    let mut snapshot = StateFile::load()?.get(&key);
+    snapshot.prune(latest);
+    let mut stale = may_be_stale(&snapshot, pool, now_secs());
+    if stale {
+        for hash in snapshot.sent.range(pool..snapshot.next).map(|(_, h)| h) {
+            if matches!(sent_status(rpc, hash).await?, SentStatus::InPool) {
+                stale = false;
+                break;
+            }
+        }
+    }
+    let stale_next = if stale { Some(snapshot.next) } else { None };
+
+    let nonce = update(&key, |state| allocate(state, latest, pool, explicit, stale_next, now_secs())).await??;
+    Ok(nonce)
+}
+
+/// 本地 next 高于节点 pending 计数且最近 RESERVE_GRACE_SECS 内没有新分配时，本地记录可能已过期
+/// （还需确认其间记录的交易都已不在交易池）
+fn may_be_stale(state: &AccountState, pool: u128, now: u64) -> bool {
+    state.next > pool && state.reserved_at + RESERVE_GRACE_SECS <= now
+}
+
+/// 持锁后的分配：`stale_next` 为持锁前判定过期时快照中的本地 next，持锁后 next 未变才回退到 pending
+fn allocate(
+    state: &mut AccountState,
+    latest: u128,
+    pool: u128,
+    explicit: Option<u128>,
+    stale_next: Option<u128>,
+    now: u64,
+) -> Result<u128, String> {
+    state.prune(latest);
+    if state.next > pool {
+        if stale_next == Some(state.next) {
+            println!(
+                "   ⚠ 本地记录的下一个 nonce {} 高于节点 pending 计数 {}，其间没有仍在交易池的交易，改按 pending 分配",
+                state.next, pool
+            );
+            state.sent.retain(|n, _| *n < pool);
+            state.next = pool;
+        } else {
+            println!(
+                "   ⚠ 本地记录的下一个 nonce {} 高于节点 pending 计数 {}，此前的交易可能未被节点接收，可运行 nonce list 检查空缺",
+                state.next, pool
+            );
+        }
+    }
+    let next = state.next.max(pool);
+    let nonce = match explicit {
+        Some(n) if n < latest => {
+            return Err(format!("--nonce {} 已被使用（链上已确认的交易数为 {}）", n, latest));
+        }
+        Some(n) => {
+            if n > next {
+                println!("   ⚠ --nonce {} 高于下一个可用 nonce {}，中间的 nonce 会形成空缺，交易将卡在交易池", n, next);
+            } else if n < pool {
+                println!("   ⚠ --nonce {} 在交易池中已有交易，本次广播将按替换规则处理（费用需更高）", n);
+            }
+            n
+        }
+        None => next,
+    };
+    state.next = next.max(nonce + 1);
+    state.reserved_at = now;
+    Ok(nonce)
+}
+
+/// 广播成功后记录 nonce 对应的交易哈希；写文件失败只提示，不影响流程
+pub async fn record(network: &str, address: &str, nonce: u128, tx_hash: &str) {
+    let result = update(&account_key(network, address), |state| {
+        state.sent.insert(nonce, tx_hash.to_string());
+        state.next = state.next.max(nonce + 1);
+    })
+    .await;
+    if let Err(e) = result {
+        println!("   ⚠ 记录 nonce 失败: {}", e);
+    }
+}
+
+/// 广播失败时归还刚分配的 nonce（仅当其后没有再分配）
+pub async fn release(network: &str, address: &str, nonce: u128) {
+    let result = update(&account_key(network, address), |state| {
+        if state.next == nonce + 1 && !state.sent.contains_key(&nonce) {
+            state.next = nonce;
+        }
+    })
+    .await;
+    if let Err(e) = result {
+        println!("   ⚠ 归还 nonce 失败: {}", e);
+    }
+}
+
+/// 将 nonce 写入 SDK 构建的交易 JSON
+pub fn set_nonce(tx_json: &str, nonce: u128) -> Result<String, Box<dyn std::error::Error>> {
+    let mut tx: Value = serde_json::from_str(tx_json)?;
+    let obj = tx.as_object_mut().ok_or("交易 JSON 不是对象")?;
+    evm_fees::set_quantity(obj, "nonce", nonce);
+    Ok(serde_json::to_string(&tx)?)
+}
+
+/// 本地记录的 nonce 在节点上的状态
+enum SentStatus {
+    Mined,
+    InPool,
+    Dropped,
+}
+
+async fn sent_status(rpc: &EvmRpc, hash: &str) -> Result<SentStatus, crate::rpc::RpcError> {
+    let tx = rpc.get_transaction(hash).await?;
+    Ok(if tx.is_null() {
+        SentStatus::Dropped
+    } else if tx.get("blockNumber").map(|b| !b.is_null()).unwrap_or(false) {
+        SentStatus::Mined
+    } else {
+        SentStatus::InPool
+    })
+}
+
+/// 空缺：节点 pending 计数之后、本地上界之前，没有记录或记录的交易已被节点丢弃的 nonce
+async fn find_gaps(rpc: &EvmRpc, state: &AccountState, pool: u128) -> Result<Vec<u128>, crate::rpc::RpcError> {
+    let mut dropped = BTreeSet::new();
+    for (n, hash) in state.sent.range(pool..state.upper()) {
+        if matches!(sent_status(rpc, hash).await?, SentStatus::Dropped) {
+            dropped.insert(*n);
+        }
+    }
+    Ok(gaps_in(state, pool, &dropped))
+}
+
+/// 按已查询的丢弃集合列出空缺
+fn gaps_in(state: &AccountState, pool: u128, dropped: &BTreeSet<u128>) -> Vec<u128> {
+    (pool..state.upper())
+        .filter(|n| !state.sent.contains_key(n) || dropped.contains(n))
+        .collect()
+}
+
+/// `nonce <子命令>` 入口
+///
+/// 子命令：
+/// - list：链上 / 交易池 / 本地的 nonce、本地记录的交易状态与空缺
+/// - fill：以 0 金额自转账补齐空缺（需 ETH_PRIVATE_KEY，费用变量同 eth-transfer）
+/// - reset：清除当前账户的本地记录
+///
+/// 账户取 ETH_PRIVATE_KEY 对应地址，未设置时取 ETH_ADDRESS 或示例地址。
+pub async fn run_nonce(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let sub = args.first().map(|s| s.as_str());
+    if !matches!(sub, Some("list") | Some("fill") | Some("reset")) {
+        eprintln!("用法: cargo run -- nonce list | fill | reset");
+        eprintln!("  list  查看链上 / 交易池 / 本地 nonce 与空缺");
+        eprintln!("  fill  以 0 金额自转账补齐空缺（需 ETH_PRIVATE_KEY）");
+        eprintln!("  reset 清除当前账户的本地 nonce 记录");
+        return Ok(());
+    }
+    let private_key = env::var("ETH_PRIVATE_KEY").ok();
+    let address = match &private_key {
+        Some(k) => signer_address(k, config::EVM_EXAMPLE_ADDR),
+        None => env::var("ETH_ADDRESS").unwrap_or_else(|_| config::EVM_EXAMPLE_ADDR.to_string()),
+    };
+    let network = config::current_evm_network()?;
+    let rpc = EvmRpc::new(config::evm_rpc_url(&network).await);
+    let key = account_key(network.name(), &address);
+
+    println!("=== EVM nonce 管理（{}）===", network.name());
+    println!("账户: {}", address);
+    println!("状态文件: {}", config::nonce_state_path());
+
+    if sub == Some("reset") {
+        update(&key, |state| *state = AccountState::default()).await?;
+        println!("✅ 已清除本地记录，下次发送按节点 pending 计数分配 nonce");
+        return Ok(());
+    }
+
+    let latest = rpc.get_transaction_count(&address, "latest").await?;
+    let pool = rpc.get_transaction_count(&address, "pending").await?;
+    let state = update(&key, |state| {
+        state.prune(latest);
+        state.clone()
+    })
+    .await?;
+
+    println!("链上已确认（latest）: {}", latest);
+    println!("含交易池（pending）: {}", pool);
+    println!("本地下一个: {}", state.next);
+    if !state.sent.is_empty() {
+        println!("\n[本地记录]");
+        for (n, hash) in &state.sent {
+            let status = match sent_status(&rpc, hash).await {
+                Ok(SentStatus::Mined) => "已上链".to_string(),
+                Ok(SentStatus::InPool) => "交易池中".to_string(),
+                Ok(SentStatus::Dropped) => "节点未找到（已丢弃）".to_string(),
+                Err(e) => format!("查询失败: {}", e),
+            };
+            println!("  nonce {}  {}  {}", n, hash, status);
+        }
+    }
+
+    let gaps = find_gaps(&rpc, &state, pool).await?;
+    if gaps.is_empty() {
+        println!("\n✅ 无空缺");
+        return Ok(());
+    }
+    println!(
+        "\n[空缺] nonce {}",
+        gaps.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")
+    );
+    if sub == Some("list") {
+        println!("更高 nonce 的交易会卡在交易池，可运行 nonce fill 补齐");
+        return Ok(());
+    }
+
+    let private_key = match private_key {
+        Some(k) => k,
+        None => {
+            eprintln!("nonce fill 需要 ETH_PRIVATE_KEY。");
+            return Ok(());
+        }
+    };
+    let chain_id = match ChainIdentity::check(&rpc, &network).await.require_for_signing() {
+        Ok(id) => id,
+        Err(e) => {
+            println!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    let fees = FeeSettings::from_env(&rpc).await?;
+    let sdk = EvmSdk::new(rpc.url().to_string());
+    let template = sdk.build_native_transfer(&address, &address, "0").await?;
+    let template = evm_chain::bind_chain_id(&fees.apply(&template)?, chain_id)?;
+    fees.print();
+    for n in gaps {
+        let tx_json = set_nonce(&template, n)?;
+        let entry = ledger::track(&TxInfo::evm(&network, &address, &tx_json));
+        let signed_hex = sign_ethereum_transaction(&tx_json, &private_key)?;
+        entry.signed(&ledger::evm_tx_hash(&signed_hex)?, Some(n));
+        match sdk.send(&signed_hex).await {
+            Ok(hash) => {
+                println!("  nonce {} 已补齐: {}", n, hash);
+                record(network.name(), &address, n, &hash).await;
+                entry.broadcast(&hash);
+            }
+            Err(e) => {
+                println!("  ❌ nonce {} 补齐失败: {}", n, e);
+                entry.rejected(&e.to_string());
+            }
+        }
+    }
+    println!("补齐交易已写入交易记录，可运行 resume 等待其最终确认");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(next: u128, sent: &[u128], reserved_at: u64) -> AccountState {
+        AccountState { next, sent: sent.iter().map(|n| (*n, format!("0x{:x}", n))).collect(), reserved_at }
+    }
+
+    #[test]
+    fn prune_drops_mined_and_raises_next() {
+        let mut s = state(3, &[1, 2, 5], 0);
+        s.prune(2);
+        assert_eq!(s.sent.keys().copied().collect::<Vec<_>>(), vec![2, 5]);
+        assert_eq!(s.next, 3);
+        s.prune(4);
+        assert_eq!(s.sent.keys().copied().collect::<Vec<_>>(), vec![5]);
+        assert_eq!((s.next, s.upper()), (4, 6));
+    }
+
+    #[test]
+    fn parses_nonce_arg() {
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(parse_nonce_arg(&args(&["a", "--nonce", "7"])), Ok(Some(7)));
+        assert_eq!(parse_nonce_arg(&args(&["--nonce= 12"])), Ok(Some(12)));
+        assert_eq!(parse_nonce_arg(&args(&["a", "b"])), Ok(None));
+        assert!(parse_nonce_arg(&args(&["--nonce"])).is_err());
+        assert!(parse_nonce_arg(&args(&["--nonce", "-1"])).is_err());
+    }
+
+    #[test]
+    fn stale_local_next_falls_back_to_pending() {
+        let snapshot = state(8, &[5, 6, 7], 1_000);
+        assert!(!may_be_stale(&snapshot, 5, 1_000 + RESERVE_GRACE_SECS - 1));
+        assert!(may_be_stale(&snapshot, 5, 1_000 + RESERVE_GRACE_SECS));
+        assert!(!may_be_stale(&snapshot, 8, 5_000));
+
+        let mut s = snapshot.clone();
+        assert_eq!(allocate(&mut s, 4, 5, None, Some(8), 5_000), Ok(5));
+        assert_eq!((s.next, s.reserved_at), (6, 5_000));
+        assert!(s.sent.is_empty());
+
+        // 持锁前后本地 next 被其他进程改动：不回退
+        let mut s = state(9, &[5, 6, 7, 8], 1_000);
+        assert_eq!(allocate(&mut s, 4, 5, None, Some(8), 5_000), Ok(9));
+        assert_eq!(s.sent.len(), 4);
+    }
+
+    #[test]
+    fn allocate_checks_explicit_nonce() {
+        let mut s = state(6, &[], 0);
+        assert!(allocate(&mut s, 4, 5, Some(3), None, 0).is_err());
+        assert_eq!(allocate(&mut s, 4, 5, Some(9), None, 0), Ok(9));
+        assert_eq!(s.next, 10);
+        assert_eq!(allocate(&mut s, 4, 5, None, None, 0), Ok(10));
+    }
+
+    #[test]
+    fn gaps_cover_missing_and_dropped() {
+        let s = state(6, &[3, 5, 7], 0);
+        assert_eq!(gaps_in(&s, 3, &BTreeSet::new()), vec![4, 6]);
+        assert_eq!(gaps_in(&s, 3, &BTreeSet::from([5])), vec![4, 5, 6]);
+        assert_eq!(gaps_in(&s, 8, &BTreeSet::new()), Vec::<u128>::new());
+    }
+}
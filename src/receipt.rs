@@ -0,0 +1,266 @@
+//! 交易回执报告：监听结束后查询区块、确认数、费用、失败原因与代币事件（EVM 与 Tron）
+
+use crate::abi;
+use crate::config;
+use crate::events::{self, TokenEvent};
+use crate::evm_rpc::{self, CallOutcome, EvmRpc};
+use crate::rpc::RpcError;
+use crate::tron_account::{format_time_ms, format_trx};
+use crate::tron_rpc::{self, TronApi};
+use chains_sdk::rpc::chains::tron::TronNetwork;
+use serde_json::Value;
+
+/// EVM 交易回执（eth_getTransactionReceipt + 区块时间 + 失败重放）
+pub struct EvmReceipt {
+    pub tx_hash: String,
+    pub success: bool,
+    pub block_number: u64,
+    /// 区块时间（毫秒）
+    pub timestamp_ms: Option<i64>,
+    pub confirmations: u64,
+    pub gas_used: u128,
+    /// Arbitrum 回执的 gasUsedForL1：gasUsed 中支付 L1 数据费用的部分
+    pub gas_used_for_l1: Option<u128>,
+    /// 实际 gas 单价（wei），旧节点无 effectiveGasPrice 时取交易 gasPrice
+    pub effective_gas_price: u128,
+    /// 失败时在父区块状态上重放得到的 revert 原因
+    pub revert: Option<String>,
+    /// logs 中的 Transfer / Approval
+    pub events: Vec<TokenEvent>,
+}
+
+impl EvmReceipt {
+    /// 查询回执；交易尚未上链时返回 None
+    pub async fn fetch(rpc: &EvmRpc, tx_hash: &str) -> Result<Option<Self>, RpcError> {
+        let receipt = rpc.get_transaction_receipt(tx_hash).await?;
+        if receipt.is_null() {
+            return Ok(None);
+        }
+        let tx = rpc.get_transaction(tx_hash).await?;
+        let block_number = quantity(&receipt, "blockNumber") as u64;
+        let success = quantity(&receipt, "status") == 1;
+        let effective_gas_price = receipt
+            .get("effectiveGasPrice")
+            .and_then(abi::parse_quantity)
+            .unwrap_or_else(|| quantity(&tx, "gasPrice"));
+        let timestamp_ms = rpc
+            .get_block_by_number(block_number, false)
+            .await?
+            .get("timestamp")
+            .and_then(abi::parse_quantity)
+            .map(|t| t as i64 * 1000);
+        let latest = rpc.block_number().await?;
+        let revert = if success { None } else { Some(replay_revert(rpc, &tx, block_number).await) };
+        Ok(Some(Self {
+            tx_hash: tx_hash.to_string(),
+            success,
+            block_number,
+            timestamp_ms,
+            confirmations: (latest + 1).saturating_sub(block_number),
+            gas_used: quantity(&receipt, "gasUsed"),
+            gas_used_for_l1: receipt.get("gasUsedForL1").and_then(abi::parse_quantity),
+            effective_gas_price,
+            revert,
+            events: events::decode_evm_logs(&receipt),
+        }))
+    }
+
+    /// 总手续费（wei）= gasUsed × effectiveGasPrice
+    pub fn fee_wei(&self) -> u128 {
+        self.gas_used.saturating_mul(self.effective_gas_price)
+    }
+
+    pub fn print(&self) {
+        println!("\n[交易回执]");
+        println!("  交易哈希: {}", self.tx_hash);
+        println!("  状态: {}", if self.success { "成功" } else { "失败" });
+        println!("  区块: {}", self.block_number);
+        if let Some(ms) = self.timestamp_ms {
+            println!("  时间: {}", format_time_ms(ms));
+        }
+        println!("  确认数: {}", self.confirmations);
+        println!("  gasUsed: {}", self.gas_used);
+        println!("  effectiveGasPrice: {} gwei", abi::format_units(self.effective_gas_price, 9));
+        println!("  手续费: {} ETH", abi::format_units(self.fee_wei(), 18));
+        if let Some(l1_gas) = self.gas_used_for_l1 {
+            let l2_gas = self.gas_used.saturating_sub(l1_gas);
+            println!(
+                "    L2 执行: {} gas ≈ {} ETH",
+                l2_gas,
+                abi::format_units(l2_gas.saturating_mul(self.effective_gas_price), 18)
+            );
+            println!(
+                "    L1 数据: {} gas ≈ {} ETH（gasUsedForL1）",
+                l1_gas,
+                abi::format_units(l1_gas.saturating_mul(self.effective_gas_price), 18)
+            );
+        }
+        if let Some(reason) = &self.revert {
+            println!("  失败原因: {}", reason);
+        }
+    }
+}
+
+/// 在交易所在区块的父区块状态上以 eth_call 重放，取 revert 原因
+async fn replay_revert(rpc: &EvmRpc, tx: &Value, block_number: u64) -> String {
+    let from = tx.get("from").and_then(|f| f.as_str()).unwrap_or("");
+    let call = match evm_rpc::call_object_from_tx_json(&tx.to_string(), from) {
+        Ok(c) => c,
+        Err(e) => return format!("无法重放: {}", e),
+    };
+    let block = abi::to_hex_quantity(block_number.saturating_sub(1) as u128);
+    match rpc.eth_call(&call, &block).await {
+        Ok(CallOutcome::Reverted(reason)) => reason,
+        Ok(CallOutcome::Success) => "重放未回滚（可能为 gas 耗尽，或依赖同区块内先前交易的状态）".to_string(),
+        Err(e) => format!("重放失败: {}", e),
+    }
+}
+
+/// Tron 交易回执（gettransactioninfobyid + gettransactionbyid）
+pub struct TronReceipt {
+    pub tx_id: String,
+    pub block_number: i64,
+    pub timestamp_ms: i64,
+    pub confirmations: i64,
+    /// 合约执行结果（SUCCESS / REVERT / OUT_OF_ENERGY 等）
+    pub contract_ret: String,
+    /// 交易结果（SUCCESS / FAILED）
+    pub result: String,
+    /// 失败时节点返回的说明（resMessage）
+    pub res_message: String,
+    /// 总手续费（sun）
+    pub fee: i64,
+    pub energy_usage: i64,
+    pub origin_energy_usage: i64,
+    pub energy_usage_total: i64,
+    pub energy_fee: i64,
+    pub net_usage: i64,
+    pub net_fee: i64,
+    /// contractResult[0] 返回数据
+    pub contract_result: Vec<u8>,
+    /// log 中的 Transfer / Approval
+    pub events: Vec<TokenEvent>,
+}
+
+impl TronReceipt {
+    /// 查询回执；交易尚未上链时返回 None
+    pub async fn fetch(api: &TronApi, tx_id: &str) -> Result<Option<Self>, RpcError> {
+        let info = api.get_transaction_info_by_id(tx_id).await?;
+        if info.get("blockNumber").is_none() {
+            return Ok(None);
+        }
+        let tx = api.get_transaction_by_id(tx_id).await?;
+        let latest = api.now_block_number().await?;
+        let block_number = i64_at(&info, "/blockNumber");
+        let receipt = info.get("receipt").cloned().unwrap_or(Value::Null);
+        Ok(Some(Self {
+            tx_id: tx_id.to_string(),
+            block_number,
+            timestamp_ms: i64_at(&info, "/blockTimeStamp"),
+            confirmations: (latest - block_number + 1).max(0),
+            contract_ret: tx
+                .pointer("/ret/0/contractRet")
+                .and_then(|r| r.as_str())
+                .unwrap_or("SUCCESS")
+                .to_string(),
+            result: info.get("result").and_then(|r| r.as_str()).unwrap_or("SUCCESS").to_string(),
+            res_message: info
+                .get("resMessage")
+                .and_then(|m| m.as_str())
+                .map(tron_rpc::decode_message)
+                .unwrap_or_default(),
+            fee: i64_at(&info, "/fee"),
+            energy_usage: i64_at(&receipt, "/energy_usage"),
+            origin_energy_usage: i64_at(&receipt, "/origin_energy_usage"),
+            energy_usage_total: i64_at(&receipt, "/energy_usage_total"),
+            energy_fee: i64_at(&receipt, "/energy_fee"),
+            net_usage: i64_at(&receipt, "/net_usage"),
+            net_fee: i64_at(&receipt, "/net_fee"),
+            contract_result: info
+                .pointer("/contractResult/0")
+                .and_then(|c| c.as_str())
+                .and_then(|c| abi::hex_to_bytes(c).ok())
+                .unwrap_or_default(),
+            events: events::decode_tron_logs(&info),
+        }))
+    }
+
+    pub fn success(&self) -> bool {
+        self.contract_ret == "SUCCESS" && self.result != "FAILED"
+    }
+
+    /// 失败原因：REVERT 解析 contractResult，其余按 contractRet 说明
+    pub fn failure_reason(&self) -> Option<String> {
+        if self.success() {
+            return None;
+        }
+        let detail = match self.contract_ret.as_str() {
+            "REVERT" => abi::decode_revert_reason(&self.contract_result),
+            "OUT_OF_ENERGY" => "能量不足，fee_limit 或 TRX 余额不够支付能量".to_string(),
+            "OUT_OF_TIME" => "合约执行超时".to_string(),
+            "TRANSFER_FAILED" => "合约内转账失败".to_string(),
+            _ => self.res_message.clone(),
+        };
+        Some(format!("{}: {}", self.contract_ret, detail))
+    }
+
+    pub fn print(&self) {
+        println!("\n[交易回执]");
+        println!("  交易哈希: {}", self.tx_id);
+        println!("  状态: {}（contractRet {}）", if self.success() { "成功" } else { "失败" }, self.contract_ret);
+        println!("  区块: {}", self.block_number);
+        println!("  时间: {}", format_time_ms(self.timestamp_ms));
+        println!("  确认数: {}", self.confirmations);
+        println!(
+            "  能量: 消耗 {}（质押能量 {}，合约方承担 {}），燃烧 {}",
+            self.energy_usage_total,
+            self.energy_usage,
+            self.origin_energy_usage,
+            format_trx(self.energy_fee)
+        );
+        println!("  带宽: 消耗 {}，燃烧 {}", self.net_usage, format_trx(self.net_fee));
+        println!("  总手续费: {}", format_trx(self.fee));
+        if !self.contract_result.is_empty() {
+            println!("  contractResult: 0x{}", hex::encode(&self.contract_result));
+        }
+        if let Some(reason) = self.failure_reason() {
+            println!("  失败原因: {}", reason);
+        }
+        if !self.res_message.is_empty() && self.contract_ret != "REVERT" {
+            println!("  节点说明: {}", self.res_message);
+        }
+    }
+}
+
+/// 查询并打印 EVM 回执与代币事件；失败时仅提示，不影响监听结果
+pub async fn print_evm_receipt(rpc_url: &str, tx_hash: &str) {
+    match EvmReceipt::fetch(&EvmRpc::new(rpc_url.to_string()), tx_hash).await {
+        Ok(Some(r)) => {
+            r.print();
+            events::print_evm_token_events(rpc_url, &r.events).await;
+        }
+        Ok(None) => println!("\n[交易回执] 交易尚未上链"),
+        Err(e) => println!("\n[交易回执] ⚠ 查询失败: {}", e),
+    }
+}
+
+/// 查询并打印 Tron 回执与代币事件；失败时仅提示，不影响监听结果
+pub async fn print_tron_receipt(network: TronNetwork, tx_id: &str) {
+    match TronReceipt::fetch(&TronApi::new(config::tron_api_url(network)), tx_id).await {
+        Ok(Some(r)) => {
+            r.print();
+            events::print_tron_token_events(network, &r.events).await;
+        }
+        Ok(None) => println!("\n[交易回执] 交易尚未上链"),
+        Err(e) => println!("\n[交易回执] ⚠ 查询失败: {}", e),
+    }
+}
+
+/// EVM 数量字段，缺失或无法解析时为 0
+fn quantity(v: &Value, key: &str) -> u128 {
+    v.get(key).and_then(abi::parse_quantity).unwrap_or(0)
+}
+
+fn i64_at(v: &Value, pointer: &str) -> i64 {
+    v.pointer(pointer).and_then(|x| x.as_i64()).unwrap_or(0)
+}
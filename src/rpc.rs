@@ -0,0 +1,402 @@
+//! 原始 RPC 调用的公共部分：错误类型、HTTP 客户端与端点故障转移（EVM JSON-RPC 与 Tron HTTP API 共用）
+//!
+//! 故障转移：每次调用在主端点及 config 登记的备用端点间轮换；网络错误、超时、429 / 5xx 时指数退避（带抖动）后换端点重试，
+//! 连续失败的端点熔断一段时间。环境变量：
+//! - RPC_MAX_ATTEMPTS：单次调用最多尝试次数（默认 4）
+//! - RPC_BACKOFF_MS / RPC_BACKOFF_MAX_MS：首次退避与退避上限毫秒数（默认 250 / 8000）
+//! - RPC_BREAKER_THRESHOLD / RPC_BREAKER_COOLDOWN_SEC：连续失败几次熔断、熔断秒数（默认 3 / 30）
+//! - RPC_LOG=1：记录每次调用由哪个端点完成及耗时（stderr）
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// RPC 调用错误：网络/解析错误，或节点返回的错误对象
+#[derive(Debug)]
+pub enum RpcError {
+    Transport(String),
+    Rpc {
+        code: i64,
+        message: String,
+        data: Option<String>,
+    },
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Transport(msg) => write!(f, "RPC 请求失败: {}", msg),
+            RpcError::Rpc { code, message, .. } => write!(f, "RPC 错误 {}: {}", code, message),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// 原始 RPC 使用的 HTTP 客户端（单次请求 15 秒超时）
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()
+        .unwrap_or_default()
+}
+
+/// 重试、退避与熔断策略（环境变量，进程内共用）
+struct RetryPolicy {
+    /// 单次调用最多尝试次数（含首次）
+    max_attempts: u32,
+    /// 首次重试前的退避毫秒数，之后每次翻倍
+    backoff_ms: u64,
+    /// 退避上限毫秒数
+    backoff_max_ms: u64,
+    /// 连续失败达到该次数即熔断端点
+    breaker_threshold: u32,
+    /// 熔断持续时间
+    breaker_cooldown: Duration,
+    /// 是否记录每次调用由哪个端点完成
+    log_calls: bool,
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key).ok().and_then(|v| v.trim().parse().ok()).unwrap_or(default)
+}
+
+fn policy() -> &'static RetryPolicy {
+    static POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+    POLICY.get_or_init(|| RetryPolicy {
+        max_attempts: env_or("RPC_MAX_ATTEMPTS", 4u32).max(1),
+        backoff_ms: env_or("RPC_BACKOFF_MS", 250),
+        backoff_max_ms: env_or("RPC_BACKOFF_MAX_MS", 8000),
+        breaker_threshold: env_or("RPC_BREAKER_THRESHOLD", 3u32).max(1),
+        breaker_cooldown: Duration::from_secs(env_or("RPC_BREAKER_COOLDOWN_SEC", 30)),
+        log_calls: std::env::var("RPC_LOG").map(|v| v == "1" || v == "true").unwrap_or(false),
+    })
+}
+
+/// 端点健康状态，按 URL 在进程内共享（同一端点的多个客户端共用熔断状态）
+#[derive(Default)]
+struct EndpointHealth {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl EndpointHealth {
+    /// 记录一次失败；本次触发熔断时返回触发前的连续失败次数
+    ///
+    /// 熔断到期后端点处于半开状态：试探调用失败立即重新熔断，成功则由 mark_success 复位。
+    fn record_failure(&mut self, now: Instant, threshold: u32, cooldown: Duration) -> Option<u32> {
+        self.consecutive_failures += 1;
+        let trip = match self.open_until {
+            Some(until) => until <= now,
+            None => self.consecutive_failures >= threshold,
+        };
+        if !trip {
+            return None;
+        }
+        let failures = self.consecutive_failures;
+        self.open_until = Some(now + cooldown);
+        self.consecutive_failures = 0;
+        Some(failures)
+    }
+}
+
+fn health() -> &'static Mutex<HashMap<String, EndpointHealth>> {
+    static HEALTH: OnceLock<Mutex<HashMap<String, EndpointHealth>>> = OnceLock::new();
+    HEALTH.get_or_init(Default::default)
+}
+
+fn fallbacks() -> &'static Mutex<HashMap<String, Vec<String>>> {
+    static FALLBACKS: OnceLock<Mutex<HashMap<String, Vec<String>>>> = OnceLock::new();
+    FALLBACKS.get_or_init(Default::default)
+}
+
+/// 登记主端点的备用端点（config 选定主 URL 时传入同网络的全部候选）；family 为 "evm" 或 "tron"，用于按链族选取限流速率
+pub fn register_fallbacks(primary: &str, candidates: &[String], family: &'static str) {
+    let others: Vec<String> = candidates.iter().filter(|c| c.as_str() != primary).cloned().collect();
+    if let Ok(mut map) = fallbacks().lock() {
+        map.insert(primary.to_string(), others);
+    }
+    register_family(family, candidates);
+    register_family(family, &[primary.to_string()]);
+}
+
+/// 主端点及其备用端点；熔断中的端点排在最后
+pub fn candidates(primary: &str) -> Vec<String> {
+    let mut all = vec![primary.to_string()];
+    if let Ok(map) = fallbacks().lock() {
+        all.extend(map.get(primary).cloned().unwrap_or_default());
+    }
+    let now = Instant::now();
+    let open: Vec<bool> = match health().lock() {
+        Ok(h) => all
+            .iter()
+            .map(|u| h.get(u).and_then(|s| s.open_until).map(|t| t > now).unwrap_or(false))
+            .collect(),
+        Err(_) => vec![false; all.len()],
+    };
+    let (mut ready, broken): (Vec<_>, Vec<_>) = all.into_iter().zip(open).partition(|(_, o)| !o);
+    ready.extend(broken);
+    ready.into_iter().map(|(u, _)| u).collect()
+}
+
+fn mark_success(url: &str) {
+    if let Ok(mut h) = health().lock() {
+        let s = h.entry(url.to_string()).or_default();
+        s.consecutive_failures = 0;
+        s.open_until = None;
+    }
+}
+
+fn mark_failure(url: &str, policy: &RetryPolicy) {
+    if let Ok(mut h) = health().lock() {
+        let s = h.entry(url.to_string()).or_default();
+        let tripped = s.record_failure(Instant::now(), policy.breaker_threshold, policy.breaker_cooldown);
+        if let Some(failures) = tripped {
+            eprintln!("[rpc] ⛔ {} 连续失败 {} 次，熔断 {}s", url, failures, policy.breaker_cooldown.as_secs());
+        }
+    }
+}
+
+/// 网络错误、超时、HTTP 429 / 5xx 与节点限流错误可换端点重试；节点明确返回的业务错误（revert、nonce 等）不重试
+fn is_retryable(e: &RpcError) -> bool {
+    match e {
+        RpcError::Transport(_) => true,
+        RpcError::Rpc { code, message, .. } => {
+            let m = message.to_lowercase();
+            *code == 429
+                || *code == -32005
+                || m.contains("rate limit")
+                || m.contains("too many requests")
+                || m.contains("limit exceeded")
+        }
+    }
+}
+
+/// 第 attempt 次重试前的退避：backoff_ms × 2^attempt（不超过上限）再加 0–50% 随机抖动
+fn backoff(policy: &RetryPolicy, attempt: u32) -> Duration {
+    let base = policy
+        .backoff_ms
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(policy.backoff_max_ms);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0) as u64;
+    Duration::from_millis(base + nanos % (base / 2 + 1))
+}
+
+/// 以故障转移方式执行一次调用：call 接收端点 URL；可重试的错误在退避后换下一个端点，直到成功或用完尝试次数
+pub async fn with_failover<T, F, Fut>(primary: &str, method: &str, call: F) -> Result<T, RpcError>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, RpcError>>,
+{
+    with_endpoints(candidates(primary), method, 1, call).await
+}
+
+/// 在给定端点间轮换执行一次调用（同 with_failover）；每次尝试按 cost 个请求限流，
+/// 供一次调用内部发出多个 HTTP 请求、或只能使用固定端点的 SDK 调用使用
+pub async fn with_endpoints<T, F, Fut>(endpoints: Vec<String>, method: &str, cost: u32, call: F) -> Result<T, RpcError>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, RpcError>>,
+{
+    let policy = policy();
+    let mut attempt = 0;
+    loop {
+        let url = endpoints[attempt as usize % endpoints.len()].clone();
+        throttle_cost(&url, cost).await;
+        let started = Instant::now();
+        match call(url.clone()).await {
+            Ok(v) => {
+                mark_success(&url);
+                if policy.log_calls {
+                    eprintln!("[rpc] {} ← {}（{}ms）", method, url, started.elapsed().as_millis());
+                }
+                return Ok(v);
+            }
+            Err(e) if is_retryable(&e) && attempt + 1 < policy.max_attempts => {
+                mark_failure(&url, policy);
+                let delay = backoff(policy, attempt);
+                let next = &endpoints[(attempt + 1) as usize % endpoints.len()];
+                eprintln!(
+                    "[rpc] ⚠ {} @ {} 失败: {}；{}ms 后重试 {}",
+                    method,
+                    url,
+                    e,
+                    delay.as_millis(),
+                    next
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                if is_retryable(&e) {
+                    mark_failure(&url, policy);
+                } else {
+                    mark_success(&url);
+                }
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// TronGrid 未配置限流时的默认速率（次/秒）；公共节点无 API Key 时超过即返回 429
+const DEFAULT_TRONGRID_RPS: f64 = 5.0;
+
+/// 令牌桶：每秒补充 rate 个令牌，最多积攒 burst 个
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        let burst = rate.max(1.0);
+        Self { rate, burst, tokens: burst, last: Instant::now() }
+    }
+
+    /// 取一个令牌；不足时返回需等待的时长
+    fn take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        self.tokens = (self.tokens + now.duration_since(self.last).as_secs_f64() * self.rate).min(self.burst);
+        self.last = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// 端点 → 令牌桶（None 表示不限流），进程内共享
+fn buckets() -> &'static Mutex<HashMap<String, Option<Bucket>>> {
+    static BUCKETS: OnceLock<Mutex<HashMap<String, Option<Bucket>>>> = OnceLock::new();
+    BUCKETS.get_or_init(Default::default)
+}
+
+/// 端点 → 链族（"evm" / "tron"）
+fn families() -> &'static Mutex<HashMap<String, &'static str>> {
+    static FAMILIES: OnceLock<Mutex<HashMap<String, &'static str>>> = OnceLock::new();
+    FAMILIES.get_or_init(Default::default)
+}
+
+/// 登记端点所属链族（"evm" / "tron"），使 EVM_RPC_RATE_LIMIT / TRON_RPC_RATE_LIMIT 对其生效
+pub fn register_family(family: &'static str, urls: &[String]) {
+    if let Ok(mut map) = families().lock() {
+        for url in urls {
+            map.insert(url.clone(), family);
+        }
+    }
+}
+
+/// 端点的限流速率（次/秒）：RPC_RATE_LIMITS 中匹配的端点 > 链族的 EVM_RPC_RATE_LIMIT / TRON_RPC_RATE_LIMIT > TronGrid 默认；0 或未配置表示不限流
+fn rate_for(url: &str) -> Option<f64> {
+    if let Ok(rules) = std::env::var("RPC_RATE_LIMITS") {
+        for rule in rules.split(',') {
+            if let Some((pattern, rate)) = rule.split_once('=') {
+                if !pattern.trim().is_empty() && url.contains(pattern.trim()) {
+                    return rate.trim().parse::<f64>().ok().filter(|r| *r > 0.0);
+                }
+            }
+        }
+    }
+    // 只有登记过链族的端点才按链族限流；未登记的 URL 不能当作链族名匹配
+    let family = families().lock().ok().and_then(|m| m.get(url).copied());
+    let family_key = match family {
+        Some("evm") => Some("EVM_RPC_RATE_LIMIT"),
+        Some("tron") => Some("TRON_RPC_RATE_LIMIT"),
+        Some(_) | None => None,
+    };
+    if let Some(rate) = family_key.and_then(|k| std::env::var(k).ok()) {
+        return rate.trim().parse::<f64>().ok().filter(|r| *r > 0.0);
+    }
+    if url.contains("trongrid.io") {
+        return Some(DEFAULT_TRONGRID_RPS);
+    }
+    None
+}
+
+/// 按端点令牌桶限流：令牌不足时等待，不丢弃请求
+pub async fn throttle(url: &str) {
+    loop {
+        let wait = match buckets().lock() {
+            Ok(mut map) => map
+                .entry(url.to_string())
+                .or_insert_with(|| rate_for(url).map(Bucket::new))
+                .as_mut()
+                .and_then(Bucket::take),
+            Err(_) => None,
+        };
+        match wait {
+            Some(d) => tokio::time::sleep(d).await,
+            None => return,
+        }
+    }
+}
+
+/// 按 cost 个请求限流（SDK 的一次调用内部可能发出多个 HTTP 请求）
+pub async fn throttle_cost(url: &str, cost: u32) {
+    for _ in 0..cost {
+        throttle(url).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaker_trips_at_threshold() {
+        let mut h = EndpointHealth::default();
+        let now = Instant::now();
+        let cooldown = Duration::from_secs(30);
+        assert_eq!(h.record_failure(now, 3, cooldown), None);
+        assert_eq!(h.record_failure(now, 3, cooldown), None);
+        assert_eq!(h.record_failure(now, 3, cooldown), Some(3));
+        assert_eq!(h.open_until, Some(now + cooldown));
+        assert_eq!(h.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn failures_during_cooldown_do_not_extend_it() {
+        let mut h = EndpointHealth::default();
+        let now = Instant::now();
+        let cooldown = Duration::from_secs(30);
+        h.record_failure(now, 1, cooldown);
+        assert_eq!(h.record_failure(now + Duration::from_secs(10), 1, cooldown), None);
+        assert_eq!(h.open_until, Some(now + cooldown));
+    }
+
+    #[test]
+    fn breaker_retrips_after_cooldown() {
+        let mut h = EndpointHealth::default();
+        let now = Instant::now();
+        let cooldown = Duration::from_secs(30);
+        for _ in 0..3 {
+            h.record_failure(now, 3, cooldown);
+        }
+        // 熔断到期（半开），试探失败一次即重新熔断
+        let later = now + cooldown + Duration::from_secs(1);
+        assert_eq!(h.record_failure(later, 3, cooldown), Some(1));
+        assert_eq!(h.open_until, Some(later + cooldown));
+        // 再次到期后依旧可以重新熔断
+        let again = later + cooldown;
+        assert_eq!(h.record_failure(again, 3, cooldown), Some(1));
+        assert_eq!(h.open_until, Some(again + cooldown));
+    }
+
+    #[test]
+    fn family_rate_needs_registration() {
+        // 只有本测试读写 EVM_RPC_RATE_LIMIT，且不设置 RPC_RATE_LIMITS
+        std::env::set_var("EVM_RPC_RATE_LIMIT", "7");
+        assert_eq!(rate_for("evm"), None);
+        assert_eq!(rate_for("https://unregistered.example"), None);
+        register_family("evm", &["https://registered.example".to_string()]);
+        assert_eq!(rate_for("https://registered.example"), Some(7.0));
+        std::env::remove_var("EVM_RPC_RATE_LIMIT");
+    }
+}
@@ -0,0 +1,361 @@
+//! RPC 节点探测：`rpc probe` 并发探测全部已知端点，为每个网络选出最快的健康端点
+//!
+//! 端点为各 Tron 网络的 TronGrid 与公共备用节点、各 EVM 网络（含 EVM_NETWORKS_FILE 自定义网络）的备选 RPC；
+//! EVM_RPC_URL / TRON_RPC_URL 属于显式指定，不参与探测与选择。每个端点先查询链 ID（EVM 为 eth_chainId，
+//! Tron 为创世区块哈希末 4 字节），再连续请求链头若干次统计延迟；全部端点采样结束后同时再查询一次链头，
+//! 计算与同网络最高链头的差距。链 ID 正确、采样全部成功且落后不超过阈值的端点为健康端点，
+//! 其中延迟中位数最低者写入 RPC_PROBE_FILE，后续命令优先使用（见 config）。
+
+use crate::config;
+use crate::evm_rpc::EvmRpc;
+use crate::rpc::{self, RpcError};
+use crate::tron_rpc::TronApi;
+use serde_json::{json, Value};
+use std::env;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinSet;
+
+/// 每个端点的链头采样次数默认值
+const DEFAULT_SAMPLES: usize = 5;
+/// 健康端点允许落后同网络最高链头的区块数默认值
+const DEFAULT_MAX_LAG: u64 = 5;
+
+/// 被探测端点的客户端（direct 模式：不重试、不切换端点；不自行限流，由 sample 在计时前取令牌）
+#[derive(Clone)]
+enum Endpoint {
+    Evm(EvmRpc),
+    Tron(TronApi),
+}
+
+impl Endpoint {
+    /// 节点所在链的链 ID
+    async fn chain_id(&self) -> Result<u64, RpcError> {
+        match self {
+            Endpoint::Evm(rpc) => rpc.chain_id().await,
+            Endpoint::Tron(api) => {
+                let genesis = api.get_block_by_num(0).await?;
+                let id = genesis.get("blockID").and_then(|b| b.as_str()).unwrap_or("");
+                id.get(id.len().saturating_sub(8)..)
+                    .filter(|_| id.len() >= 8)
+                    .and_then(|tail| u64::from_str_radix(tail, 16).ok())
+                    .ok_or_else(|| RpcError::Transport("创世区块缺少 blockID".to_string()))
+            }
+        }
+    }
+
+    /// 最新区块号
+    async fn head(&self) -> Result<u64, RpcError> {
+        match self {
+            Endpoint::Evm(rpc) => rpc.block_number().await,
+            Endpoint::Tron(api) => Ok(api.now_block_number().await?.max(0) as u64),
+        }
+    }
+}
+
+/// 一个网络中的一个端点
+struct Target {
+    /// "evm" / "tron"
+    family: &'static str,
+    /// EVM_NETWORK / TRON_NETWORK 中的网络名称
+    key: String,
+    url: String,
+    expected_chain_id: u64,
+    endpoint: Endpoint,
+}
+
+/// 单个端点的探测结果
+struct Report {
+    chain_id: Result<u64, String>,
+    /// 成功采样的耗时（升序）
+    latencies: Vec<Duration>,
+    samples: usize,
+    first_error: Option<String>,
+    /// 所有端点同时查询的链头
+    head: Option<u64>,
+}
+
+impl Report {
+    /// 最近秩百分位延迟
+    fn percentile(&self, p: usize) -> Option<Duration> {
+        let n = self.latencies.len();
+        if n == 0 {
+            return None;
+        }
+        let rank = (p * n).div_ceil(100).clamp(1, n);
+        Some(self.latencies[rank - 1])
+    }
+
+    /// 健康判定：不健康时返回原因
+    fn verdict(&self, expected_chain_id: u64, best_head: Option<u64>, max_lag: u64) -> Result<(), String> {
+        match &self.chain_id {
+            Ok(id) if *id == expected_chain_id => {}
+            Ok(id) => return Err(format!("链 ID {} 与网络的 {} 不一致", id, expected_chain_id)),
+            Err(e) => return Err(format!("链 ID 查询失败: {}", e)),
+        }
+        if self.latencies.len() < self.samples {
+            let error = self.first_error.as_deref().unwrap_or("");
+            return Err(format!("采样成功 {}/{}（{}）", self.latencies.len(), self.samples, error));
+        }
+        match (self.head, best_head) {
+            (Some(head), Some(best)) if best.saturating_sub(head) > max_lag => {
+                Err(format!("落后最高链头 {} 个区块", best - head))
+            }
+            (None, _) => Err("链头快照查询失败".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 网络的选择结果：最快健康端点及其延迟中位数（毫秒），无健康端点时为 None
+struct Selection {
+    family: &'static str,
+    key: String,
+    best: Option<(String, u128)>,
+}
+
+/// rpc 子命令入口
+pub async fn run_rpc(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first().map(|s| s.as_str()) {
+        Some("probe") => run_probe(&args[1..]).await,
+        _ => {
+            eprintln!("用法: cargo run -- rpc probe [--samples N] [--max-lag N] [tron | evm | 网络名...]");
+            eprintln!("  并发探测全部已知 RPC 端点（可达性、延迟、链头落后、链 ID），为每个网络选出最快的健康端点");
+            eprintln!("  选择结果写入 RPC_PROBE_FILE（默认 .rpc-probe.json），后续命令未设置 EVM_RPC_URL / TRON_RPC_URL 时优先使用");
+            Ok(())
+        }
+    }
+}
+
+/// 解析 `--name N` / `--name=N` 形式的数字参数，其余参数原样返回
+fn parse_args(args: &[String]) -> Result<(usize, u64, Vec<String>), String> {
+    let (mut samples, mut max_lag, mut filters) = (DEFAULT_SAMPLES, DEFAULT_MAX_LAG, Vec::new());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, value.to_string()),
+            _ if arg.starts_with("--") => (arg.as_str(), iter.next().cloned().unwrap_or_default()),
+            _ => {
+                filters.push(arg.to_lowercase());
+                continue;
+            }
+        };
+        let number = || value.trim().parse::<u64>().map_err(|_| format!("{} 应为非负整数，当前: {}", name, value));
+        match name {
+            "--samples" => samples = number()?.max(1) as usize,
+            "--max-lag" => max_lag = number()?,
+            _ => return Err(format!("未知参数: {}", name)),
+        }
+    }
+    Ok((samples, max_lag, filters))
+}
+
+/// 已知端点；filters 非空时只保留链族或网络名称匹配的网络（mainnet 同时匹配 Tron 与 EVM）
+fn collect_targets(filters: &[String]) -> Result<Vec<Target>, String> {
+    let wanted = |family: &str, key: &str| filters.is_empty() || filters.iter().any(|f| f == family || f == key);
+    let mut targets = Vec::new();
+    for network in config::TRON_NETWORKS {
+        let key = config::tron_network_key(network);
+        if !wanted("tron", key) {
+            continue;
+        }
+        let urls = config::tron_known_urls(network);
+        rpc::register_family("tron", &urls);
+        for url in urls {
+            targets.push(Target {
+                family: "tron",
+                key: key.to_string(),
+                expected_chain_id: config::tron_chain_id(network),
+                endpoint: Endpoint::Tron(TronApi::direct(url.clone()).unthrottled()),
+                url,
+            });
+        }
+    }
+    for network in config::evm_networks()? {
+        if !wanted("evm", &network.key) {
+            continue;
+        }
+        rpc::register_family("evm", &network.rpc_urls);
+        for url in &network.rpc_urls {
+            targets.push(Target {
+                family: "evm",
+                key: network.key.clone(),
+                url: url.clone(),
+                expected_chain_id: network.chain_id,
+                endpoint: Endpoint::Evm(EvmRpc::direct(url.clone()).unthrottled()),
+            });
+        }
+    }
+    Ok(targets)
+}
+
+/// 单个端点采样：先查链 ID，再连续请求链头；首次采样即失败视为不可达，不再继续。
+/// 每次请求前先取限流令牌再开始计时，延迟只包含请求本身
+async fn sample(url: String, endpoint: Endpoint, samples: usize) -> Report {
+    rpc::throttle(&url).await;
+    let chain_id = endpoint.chain_id().await.map_err(|e| e.to_string());
+    let (mut latencies, mut first_error) = (Vec::new(), None);
+    for _ in 0..samples {
+        rpc::throttle(&url).await;
+        let started = Instant::now();
+        match endpoint.head().await {
+            Ok(_) => latencies.push(started.elapsed()),
+            Err(e) => {
+                first_error.get_or_insert_with(|| e.to_string());
+                if latencies.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+    latencies.sort();
+    Report { chain_id, latencies, samples, first_error, head: None }
+}
+
+fn format_ms(d: Option<Duration>) -> String {
+    d.map(|d| format!("{}ms", d.as_millis())).unwrap_or_else(|| "-".to_string())
+}
+
+/// `rpc probe`：并发探测、按网络输出结果并保存选择
+async fn run_probe(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (samples, max_lag, filters) = parse_args(args)?;
+    let targets = collect_targets(&filters)?;
+    if targets.is_empty() {
+        println!("没有匹配的网络（可用: tron、evm 或 EVM_NETWORK / TRON_NETWORK 中的网络名称）");
+        return Ok(());
+    }
+
+    println!("=== RPC 节点探测（{} 个端点，每个采样 {} 次，允许落后 {} 个区块）===", targets.len(), samples, max_lag);
+    let started = Instant::now();
+    let mut tasks = JoinSet::new();
+    for (i, target) in targets.iter().enumerate() {
+        let (url, endpoint) = (target.url.clone(), target.endpoint.clone());
+        tasks.spawn(async move { (i, sample(url, endpoint, samples).await) });
+    }
+    let mut reports: Vec<Option<Report>> = targets.iter().map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        let (i, report) = joined?;
+        reports[i] = Some(report);
+    }
+    let mut reports: Vec<Report> = reports.into_iter().flatten().collect();
+
+    // 链头快照：可达端点同时查询，减少出块造成的偏差
+    let mut tasks = JoinSet::new();
+    for (i, target) in targets.iter().enumerate().filter(|(i, _)| !reports[*i].latencies.is_empty()) {
+        let (url, endpoint) = (target.url.clone(), target.endpoint.clone());
+        tasks.spawn(async move {
+            rpc::throttle(&url).await;
+            (i, endpoint.head().await.ok())
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        let (i, head) = joined?;
+        reports[i].head = head;
+    }
+    println!("用时 {}ms", started.elapsed().as_millis());
+
+    let mut selections = Vec::new();
+    let mut i = 0;
+    while i < targets.len() {
+        let (family, key) = (targets[i].family, targets[i].key.clone());
+        let end = (i..targets.len())
+            .find(|j| targets[*j].family != family || targets[*j].key != key)
+            .unwrap_or(targets.len());
+        let expected = targets[i].expected_chain_id;
+        let best_head = (i..end)
+            .filter(|j| reports[*j].chain_id.as_ref().ok() == Some(&expected))
+            .filter_map(|j| reports[j].head)
+            .max();
+
+        println!();
+        let label = if family == "evm" { "EVM" } else { "Tron" };
+        let best_text = best_head.map(|h| h.to_string()).unwrap_or_else(|| "-".to_string());
+        println!("[{} {}]（链 ID {}，最高链头 {}）", label, key, expected, best_text);
+        let mut best: Option<(usize, Duration)> = None;
+        for j in i..end {
+            let report = &reports[j];
+            let verdict = report.verdict(expected, best_head, max_lag);
+            let lag = match (report.head, best_head) {
+                (Some(h), Some(b)) => b.saturating_sub(h).to_string(),
+                _ => "-".to_string(),
+            };
+            println!(
+                "  {} {}  可达 {}/{}  p50 {}  p90 {}  max {}  链头 {}  落后 {}",
+                if verdict.is_ok() { "✓" } else { "✗" },
+                targets[j].url,
+                report.latencies.len(),
+                report.samples,
+                format_ms(report.percentile(50)),
+                format_ms(report.percentile(90)),
+                format_ms(report.latencies.last().copied()),
+                report.head.map(|h| h.to_string()).unwrap_or_else(|| "-".to_string()),
+                lag
+            );
+            match verdict {
+                Ok(()) => {
+                    let p50 = report.percentile(50).unwrap_or_default();
+                    if best.is_none_or(|(_, d)| p50 < d) {
+                        best = Some((j, p50));
+                    }
+                }
+                Err(reason) => println!("      {}", reason),
+            }
+        }
+        match best {
+            Some((j, p50)) => {
+                println!("  → 选用 {}（p50 {}ms）", targets[j].url, p50.as_millis());
+                selections.push(Selection { family, key, best: Some((targets[j].url.clone(), p50.as_millis())) });
+            }
+            None => {
+                println!("  ⚠ 无健康端点，清除该网络之前的选择");
+                selections.push(Selection { family, key, best: None });
+            }
+        }
+        i = end;
+    }
+
+    save_selections(&selections)?;
+    println!();
+    println!(
+        "选择已写入 {}（有效期 {}s，RPC_PROBE_TTL_SEC），后续命令优先使用所选端点，其余端点仍作为故障转移备用",
+        config::rpc_probe_path(),
+        config::rpc_probe_ttl_sec()
+    );
+    if env::var("EVM_RPC_URL").is_ok() {
+        println!("注意: EVM_RPC_URL 已设置，EVM 命令仍使用其指定的节点");
+    }
+    if env::var("TRON_RPC_URL").is_ok() {
+        println!("注意: TRON_RPC_URL 已设置，Tron HTTP API 仍使用其指定的节点");
+    }
+    Ok(())
+}
+
+/// 合并写入选择结果：`{ "evm" | "tron": { "<网络>": { "url", "p50_ms", "probed_at" } } }`；无健康端点的网络删除原有记录
+fn save_selections(selections: &[Selection]) -> Result<(), String> {
+    let path = config::rpc_probe_path();
+    let mut file: Value = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .filter(|v: &Value| v.is_object())
+        .unwrap_or_else(|| json!({}));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    for selection in selections {
+        let family = selection.family;
+        if !file[family].is_object() {
+            file[family] = json!({});
+        }
+        let networks = file[family].as_object_mut().ok_or("RPC_PROBE_FILE 格式错误")?;
+        match &selection.best {
+            Some((url, p50_ms)) => {
+                networks.insert(
+                    selection.key.clone(),
+                    json!({ "url": url, "p50_ms": *p50_ms as u64, "probed_at": now }),
+                );
+            }
+            None => {
+                networks.remove(&selection.key);
+            }
+        }
+    }
+    let body = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    std::fs::write(&path, body).map_err(|e| format!("写入 RPC_PROBE_FILE（{}）失败: {}", path, e))
+}
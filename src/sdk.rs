@@ -0,0 +1,347 @@
+//! chains-sdk 调用封装：余额、代币信息、交易构建与广播统一经此发起
+//!
+//! 每次调用前按该调用实际发出的 HTTP 请求数从端点令牌桶取令牌（与原始 RPC 共用，见 rpc::throttle），
+//! 调用方无需再自行限流。EVM 调用与原始 RPC 一样在主节点及备用节点间故障转移（见 rpc::with_endpoints）。
+//! Tron 的 SDK provider 只能按网络固定使用 TronGrid，因此 Tron 的读取、构建与广播不经 SDK，
+//! 直接以 TronApi 调用节点 HTTP API（triggerconstantcontract / triggersmartcontract / createtransaction /
+//! broadcasttransaction），同样故障转移到 TRON_API_FALLBACK_URLS。
+//! 错误统一转为错误信息字符串。
+
+use crate::abi;
+use crate::config;
+use crate::evm_chain;
+use crate::ledger;
+use crate::rpc::{self, RpcError};
+use crate::trc20;
+use crate::tron_rpc::TronApi;
+use chains_sdk::chain::evm::{EvmChain, TokenBalance as EvmTokenBalance};
+use chains_sdk::rpc::chains::evm::EvmRpcProvider;
+use chains_sdk::rpc::chains::tron::TronNetwork;
+use chains_sdk::transaction::TransactionSender;
+use chains_sdk::Blockchain;
+use serde_json::json;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::Arc;
+
+/// SDK 构建 EVM 交易发出的请求数：nonce、链 ID、费用与 gas 估算
+const EVM_BUILD_REQUESTS: u32 = 4;
+/// 代币信息（余额、符号、精度）发出的请求数
+const TOKEN_INFO_REQUESTS: u32 = 3;
+
+/// EVM SDK 调用（指定 RPC 节点）
+#[derive(Clone)]
+pub struct EvmSdk {
+    url: String,
+}
+
+impl EvmSdk {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+
+    /// 以故障转移方式执行一次 SDK 调用（每次尝试按所选节点新建 provider）；cost 为该调用发出的 HTTP 请求数
+    async fn call<T, E, F, Fut>(&self, method: &str, cost: u32, f: F) -> Result<T, String>
+    where
+        E: Display,
+        F: Fn(EvmRpcProvider, EvmChain) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let f = &f;
+        rpc::with_endpoints(rpc::candidates(&self.url), method, cost, |url| async move {
+            let provider = EvmRpcProvider::new(url);
+            let chain = evm_chain::sdk_chain(&provider);
+            f(provider, chain).await.map_err(|e| classify(&e.to_string()))
+        })
+        .await
+        .map_err(message)
+    }
+
+    /// 原生币余额（wei 字符串）
+    pub async fn native_balance(&self, address: &str) -> Result<String, String> {
+        self.call("native_balance", 1, |_, c| async move { c.get_balance(address).await }).await
+    }
+
+    pub async fn erc20_balance_of(&self, owner: &str, contract: &str) -> Result<String, String> {
+        self.call("erc20_balance_of", 1, |p, c| async move { c.erc20_balance_of(&p, owner, contract).await }).await
+    }
+
+    pub async fn erc20_symbol(&self, contract: &str) -> Result<String, String> {
+        self.call("erc20_symbol", 1, |p, c| async move { c.erc20_symbol(&p, contract).await }).await
+    }
+
+    pub async fn erc20_decimals(&self, contract: &str) -> Result<u8, String> {
+        self.call("erc20_decimals", 1, |p, c| async move { c.erc20_decimals(&p, contract).await }).await
+    }
+
+    pub async fn erc20_name(&self, contract: &str) -> Result<String, String> {
+        self.call("erc20_name", 1, |p, c| async move { c.erc20_name(&p, contract).await }).await
+    }
+
+    pub async fn erc20_total_supply(&self, contract: &str) -> Result<String, String> {
+        self.call("erc20_total_supply", 1, |p, c| async move { c.erc20_total_supply(&p, contract).await }).await
+    }
+
+    pub async fn erc20_allowance(&self, owner: &str, spender: &str, contract: &str) -> Result<String, String> {
+        self.call("erc20_allowance", 1, |p, c| async move {
+            c.erc20_allowance(&p, owner, spender, contract).await
+        })
+        .await
+    }
+
+    pub async fn erc20_token_info(&self, owner: &str, contract: &str) -> Result<EvmTokenBalance, String> {
+        self.call("erc20_token_info", TOKEN_INFO_REQUESTS, |p, c| async move {
+            c.erc20_token_info(&p, owner, contract).await
+        })
+        .await
+    }
+
+    pub async fn erc20_build_transfer(&self, from: &str, to: &str, contract: &str, amount: &str) -> Result<String, String> {
+        self.call("erc20_build_transfer", EVM_BUILD_REQUESTS, |p, c| async move {
+            c.erc20_build_transfer(&p, from, to, contract, amount, None).await
+        })
+        .await
+    }
+
+    pub async fn erc20_build_approve(&self, owner: &str, spender: &str, contract: &str, amount: &str) -> Result<String, String> {
+        self.call("erc20_build_approve", EVM_BUILD_REQUESTS, |p, c| async move {
+            c.erc20_build_approve(&p, owner, spender, contract, amount, None).await
+        })
+        .await
+    }
+
+    pub async fn erc20_build_transfer_from(&self, from: &str, to: &str, contract: &str, amount: &str) -> Result<String, String> {
+        self.call("erc20_build_transfer_from", EVM_BUILD_REQUESTS, |p, c| async move {
+            c.erc20_build_transfer_from(&p, from, to, contract, amount, None).await
+        })
+        .await
+    }
+
+    /// 原生币转账（value 为 wei 字符串）
+    pub async fn build_native_transfer(&self, from: &str, to: &str, value: &str) -> Result<String, String> {
+        self.call("build_native_transfer", EVM_BUILD_REQUESTS, |p, c| async move {
+            c.evm_build_native_transfer(&p, from, to, value, None).await
+        })
+        .await
+    }
+
+    /// 广播已签名交易，返回交易哈希；重试时节点已收到该交易（already known）视为成功
+    pub async fn send(&self, signed_hex: &str) -> Result<String, String> {
+        let sent =
+            self.call("send", 1, |_, c| async move { TransactionSender::new(Arc::new(c)).send(signed_hex).await }).await;
+        match sent {
+            Err(e) if is_known_transaction(&e) => ledger::evm_tx_hash(signed_hex),
+            result => result,
+        }
+    }
+}
+
+/// TRC20 余额与代币信息（字段同 SDK 的 TokenBalance）
+pub struct TronTokenBalance {
+    pub balance: String,
+    pub symbol: Option<String>,
+    pub decimals: Option<u8>,
+}
+
+/// Tron 调用：经 TronApi 访问节点 HTTP API（TRON_RPC_URL 或 TronGrid），与原始调用一样切换到 TRON_API_FALLBACK_URLS
+#[derive(Clone)]
+pub struct TronSdk {
+    api: TronApi,
+}
+
+impl TronSdk {
+    pub fn new(network: TronNetwork) -> Self {
+        Self { api: TronApi::new(config::tron_api_url(network)) }
+    }
+
+    /// triggerconstantcontract 只读调用合约，返回 constant_result；回滚视为错误
+    async fn constant_call(
+        &self,
+        owner: &str,
+        contract: &str,
+        selector: &str,
+        param: &[u8],
+    ) -> Result<Vec<u8>, String> {
+        let result = self
+            .api
+            .trigger_constant_contract(owner, contract, selector, &hex::encode(param))
+            .await
+            .map_err(message)?;
+        match result.revert {
+            Some(reason) => Err(format!("{} 调用失败: {}", selector, reason)),
+            None => Ok(result.output),
+        }
+    }
+
+    async fn call_uint(&self, owner: &str, contract: &str, selector: &str, param: &[u8]) -> Result<u128, String> {
+        let out = self.constant_call(owner, contract, selector, param).await?;
+        abi::word(&out, 0)
+            .and_then(abi::uint_bytes_to_u128)
+            .ok_or_else(|| format!("{} 返回值无法解析: 0x{}", selector, hex::encode(&out)))
+    }
+
+    /// string 返回值；早期代币以 bytes32 返回 symbol / name，去掉末尾的 0 后按文本解析
+    async fn call_string(&self, contract: &str, selector: &str) -> Result<String, String> {
+        let out = self.constant_call(contract, contract, selector, &[]).await?;
+        abi::decode_abi_string(&out)
+            .or_else(|| {
+                let word = abi::word(&out, 0)?;
+                let end = word.iter().position(|b| *b == 0).unwrap_or(32);
+                std::str::from_utf8(&word[..end]).ok().map(str::to_string)
+            })
+            .ok_or_else(|| format!("{} 返回值无法解析: 0x{}", selector, hex::encode(&out)))
+    }
+
+    /// triggersmartcontract 构建合约调用交易；未指定 fee_limit 时使用默认 100 TRX
+    async fn build_call(
+        &self,
+        owner: &str,
+        contract: &str,
+        selector: &str,
+        param: &[u8],
+        fee_limit: Option<i64>,
+    ) -> Result<String, String> {
+        let fee_limit = fee_limit.unwrap_or(trc20::DEFAULT_FEE_LIMIT_SUN);
+        self.api
+            .trigger_smart_contract(owner, contract, selector, &hex::encode(param), fee_limit)
+            .await
+            .map_err(message)
+    }
+
+    /// TRX 余额（TRX，6 位小数）
+    pub async fn trx_balance(&self, address: &str) -> Result<String, String> {
+        let sun = self.api.account_balance(address).await.map_err(message)?;
+        Ok(abi::format_units(sun.max(0) as u128, 6))
+    }
+
+    pub async fn trc20_balance_of(&self, owner: &str, contract: &str) -> Result<String, String> {
+        let param = encode_addresses(&[owner])?;
+        Ok(self.call_uint(owner, contract, "balanceOf(address)", &param).await?.to_string())
+    }
+
+    pub async fn trc20_symbol(&self, contract: &str) -> Result<String, String> {
+        self.call_string(contract, "symbol()").await
+    }
+
+    pub async fn trc20_decimals(&self, contract: &str) -> Result<u8, String> {
+        let decimals = self.call_uint(contract, contract, "decimals()", &[]).await?;
+        u8::try_from(decimals).map_err(|_| format!("decimals() 返回值超出范围: {}", decimals))
+    }
+
+    pub async fn trc20_name(&self, contract: &str) -> Result<String, String> {
+        self.call_string(contract, "name()").await
+    }
+
+    pub async fn trc20_total_supply(&self, contract: &str) -> Result<String, String> {
+        Ok(self.call_uint(contract, contract, "totalSupply()", &[]).await?.to_string())
+    }
+
+    pub async fn trc20_allowance(&self, owner: &str, spender: &str, contract: &str) -> Result<String, String> {
+        let param = encode_addresses(&[owner, spender])?;
+        Ok(self.call_uint(owner, contract, "allowance(address,address)", &param).await?.to_string())
+    }
+
+    /// 余额、符号与精度（符号、精度查询失败时为 None）
+    pub async fn trc20_token_info(&self, owner: &str, contract: &str) -> Result<TronTokenBalance, String> {
+        Ok(TronTokenBalance {
+            balance: self.trc20_balance_of(owner, contract).await?,
+            symbol: self.trc20_symbol(contract).await.ok(),
+            decimals: self.trc20_decimals(contract).await.ok(),
+        })
+    }
+
+    pub async fn trc20_build_transfer(
+        &self,
+        from: &str,
+        to: &str,
+        contract: &str,
+        amount: &str,
+        fee_limit: Option<i64>,
+    ) -> Result<String, String> {
+        let param = [encode_addresses(&[to])?, encode_amount(amount)?].concat();
+        self.build_call(from, contract, "transfer(address,uint256)", &param, fee_limit).await
+    }
+
+    pub async fn trc20_build_approve(
+        &self,
+        owner: &str,
+        spender: &str,
+        contract: &str,
+        amount: &str,
+        fee_limit: Option<i64>,
+    ) -> Result<String, String> {
+        let param = [encode_addresses(&[spender])?, encode_amount(amount)?].concat();
+        self.build_call(owner, contract, "approve(address,uint256)", &param, fee_limit).await
+    }
+
+    /// transferFrom(from, to, amount)，由 to（须已获 from 授权）发起
+    pub async fn trc20_build_transfer_from(
+        &self,
+        from: &str,
+        to: &str,
+        contract: &str,
+        amount: &str,
+        fee_limit: Option<i64>,
+    ) -> Result<String, String> {
+        let param = [encode_addresses(&[from, to])?, encode_amount(amount)?].concat();
+        self.build_call(to, contract, "transferFrom(address,address,uint256)", &param, fee_limit).await
+    }
+
+    /// TRX 转账（amount 单位为 sun），经 createtransaction 构建
+    pub async fn trx_build_transfer(&self, from: &str, to: &str, amount_sun: i64) -> Result<String, String> {
+        let body = json!({ "owner_address": from, "to_address": to, "amount": amount_sun, "visible": true });
+        self.api.build_transaction("createtransaction", body).await.map_err(message)
+    }
+
+    /// 广播已签名交易（签名后的交易 JSON），返回 txID；重试时返回 DUP_TRANSACTION 视为成功
+    pub async fn send(&self, signed_json: &str) -> Result<String, String> {
+        match self.api.broadcast(signed_json).await.map_err(message) {
+            Err(e) if e.contains("DUP_TRANSACTION") => serde_json::from_str::<serde_json::Value>(signed_json)
+                .ok()
+                .and_then(|v| v["txID"].as_str().map(str::to_string))
+                .ok_or(e),
+            result => result,
+        }
+    }
+}
+
+/// 按顺序 ABI 编码 Tron Base58 地址参数
+fn encode_addresses(addresses: &[&str]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(32 * addresses.len());
+    for address in addresses {
+        out.extend_from_slice(&abi::encode_address(&abi::tron_address_to_bytes(address)?));
+    }
+    Ok(out)
+}
+
+/// 最小单位整数字符串编码为 uint256 参数
+fn encode_amount(amount: &str) -> Result<Vec<u8>, String> {
+    let raw: u128 = amount.trim().parse().map_err(|_| format!("金额应为最小单位整数: {}", amount))?;
+    Ok(abi::encode_uint(raw).to_vec())
+}
+
+/// 按 SDK 错误信息区分网络类错误（可换节点重试）与节点返回的错误
+fn classify(msg: &str) -> RpcError {
+    let m = msg.to_lowercase();
+    let transport = [
+        "timeout", "timed out", "connection", "error sending request", "dns", "429", "too many requests",
+        "rate limit", "502", "503", "504", "bad gateway", "service unavailable",
+    ];
+    if transport.iter().any(|k| m.contains(k)) {
+        RpcError::Transport(msg.to_string())
+    } else {
+        RpcError::Rpc { code: 0, message: msg.to_string(), data: None }
+    }
+}
+
+fn message(e: RpcError) -> String {
+    match e {
+        RpcError::Transport(msg) => msg,
+        RpcError::Rpc { message, .. } => message,
+    }
+}
+
+fn is_known_transaction(msg: &str) -> bool {
+    let m = msg.to_lowercase();
+    m.contains("already known") || m.contains("known transaction")
+}
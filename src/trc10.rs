@@ -0,0 +1,224 @@
+//! TRC10 资产：按 ID 查询资产信息、账户 TRC10 余额、TransferAssetContract 转账全流程
+//!
+//! 交易由 Tron HTTP API（/wallet/transferasset）创建，签名 → 广播 → 监听复用 trc20::sign_broadcast_and_wait。
+
+use crate::abi;
+use crate::config;
+use crate::rpc::RpcError;
+use crate::trc20;
+use crate::tron_rpc::TronApi;
+use serde_json::{json, Value};
+use std::env;
+
+/// TRC10 资产信息（getassetissuebyid）
+pub struct Trc10Asset {
+    pub id: String,
+    pub name: String,
+    pub abbr: String,
+    /// 精度（小数位），旧资产缺省为 0
+    pub precision: u32,
+    /// 发行方 Base58 地址
+    pub issuer: String,
+    /// 总发行量（最小单位）
+    pub total_supply: i64,
+    pub url: String,
+    pub description: String,
+}
+
+impl Trc10Asset {
+    /// 按 ID 查询资产；资产不存在时返回 None
+    pub async fn fetch(api: &TronApi, id: &str) -> Result<Option<Self>, RpcError> {
+        let v = api.get_asset_issue_by_id(id).await?;
+        if v.get("id").is_none() {
+            return Ok(None);
+        }
+        let issuer = str_field(&v, "owner_address");
+        Ok(Some(Self {
+            id: str_field(&v, "id"),
+            name: hex_text(&str_field(&v, "name")),
+            abbr: hex_text(&str_field(&v, "abbr")),
+            precision: v.get("precision").and_then(|p| p.as_u64()).unwrap_or(0) as u32,
+            issuer: abi::tron_address_from_hex(&issuer).unwrap_or(issuer),
+            total_supply: v.get("total_supply").and_then(|t| t.as_i64()).unwrap_or(0),
+            url: hex_text(&str_field(&v, "url")),
+            description: hex_text(&str_field(&v, "description")),
+        }))
+    }
+
+    /// 显示用符号：优先 abbr，否则 name
+    pub fn symbol(&self) -> &str {
+        if self.abbr.is_empty() {
+            &self.name
+        } else {
+            &self.abbr
+        }
+    }
+
+    /// 最小单位 → "1.5 SYMBOL"
+    pub fn format_amount(&self, raw: i64) -> String {
+        format!("{} {}", abi::format_units(raw.max(0) as u128, self.precision), self.symbol())
+    }
+}
+
+/// 账户持有的 TRC10 资产（getaccount 的 assetV2：资产 ID → 余额最小单位）
+pub fn account_trc10_balances(account: &Value) -> Vec<(String, i64)> {
+    account
+        .get("assetV2")
+        .and_then(|a| a.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    let id = item.get("key")?.as_str()?.to_string();
+                    let value = item.get("value").and_then(|v| v.as_i64()).unwrap_or(0);
+                    Some((id, value))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 按 ID 查询 TRC10 资产信息（网络由 TRON_NETWORK 指定）
+///
+/// 环境变量：
+/// - TRC10_ASSET_ID（必填）：资产 ID，如 1000001
+/// - TRON_RPC_URL（可选）：Tron HTTP API
+pub async fn run_trc10_info() -> Result<(), Box<dyn std::error::Error>> {
+    let asset_id = match env::var("TRC10_ASSET_ID") {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("未设置 TRC10_ASSET_ID。");
+            eprintln!("示例: export TRC10_ASSET_ID=1000001");
+            return Ok(());
+        }
+    };
+    let network = config::current_tron_network();
+    let api = TronApi::new(config::tron_api_url(network));
+
+    println!("=== TRC10 资产信息（{}）===", network.name());
+    println!("资产 ID: {}", asset_id);
+
+    let asset = match Trc10Asset::fetch(&api, &asset_id).await? {
+        Some(a) => a,
+        None => {
+            println!("❌ 资产不存在");
+            return Ok(());
+        }
+    };
+    println!("名称: {}", asset.name);
+    println!("缩写: {}", asset.abbr);
+    println!("精度: {}", asset.precision);
+    println!("发行方: {}", asset.issuer);
+    println!("总发行量: {}", asset.format_amount(asset.total_supply));
+    if !asset.url.is_empty() {
+        println!("网址: {}", asset.url);
+    }
+    if !asset.description.is_empty() {
+        println!("描述: {}", asset.description);
+    }
+
+    Ok(())
+}
+
+/// TRC10 转账全流程（TransferAssetContract）：构建 → 签名 → 广播 → 监听确认/失败
+///
+/// 构建前查询资产信息与发送方 TRC10 余额，余额不足时中止。
+///
+/// 环境变量：
+/// - TRON_PRIVATE_KEY（必填）：发送方私钥，64 位十六进制（32 字节）
+/// - TRC10_ASSET_ID（必填）：资产 ID
+/// - TRON_FROM_ADDRESS（可选）：发送方地址，默认示例地址
+/// - TRON_TO_ADDRESS（可选）：接收方地址，默认同 FROM
+/// - TRC10_AMOUNT（可选）：转账金额（最小单位），默认 1
+pub async fn run_trc10_transfer() -> Result<(), Box<dyn std::error::Error>> {
+    let private_key = match env::var("TRON_PRIVATE_KEY") {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("未设置 TRON_PRIVATE_KEY。");
+            eprintln!("示例: export TRON_PRIVATE_KEY=你的64位十六进制私钥");
+            return Ok(());
+        }
+    };
+    let asset_id = match env::var("TRC10_ASSET_ID") {
+        Ok(v) => v,
+        Err(_) => {
+            eprintln!("未设置 TRC10_ASSET_ID。");
+            eprintln!("示例: export TRC10_ASSET_ID=1000001");
+            return Ok(());
+        }
+    };
+    let from_addr = env::var("TRON_FROM_ADDRESS").unwrap_or_else(|_| config::TRON_EXAMPLE_ADDR.to_string());
+    let to_addr = env::var("TRON_TO_ADDRESS").unwrap_or_else(|_| config::TRON_EXAMPLE_ADDR.to_string());
+    let amount: i64 = match env::var("TRC10_AMOUNT") {
+        Ok(v) => v
+            .trim()
+            .parse()
+            .ok()
+            .filter(|a| *a > 0)
+            .ok_or_else(|| format!("TRC10_AMOUNT 应为正整数（最小单位），当前: {}", v))?,
+        Err(_) => 1,
+    };
+
+    let network = config::current_tron_network();
+    let api = TronApi::new(config::tron_api_url(network));
+
+    println!("=== TRC10 转账流程（{}，构建 → 签名 → 广播 → 监听）===", network.name());
+    let asset = match Trc10Asset::fetch(&api, &asset_id).await? {
+        Some(a) => a,
+        None => {
+            println!("❌ 资产 {} 不存在", asset_id);
+            return Ok(());
+        }
+    };
+    println!("资产: {} {}（ID {}，精度 {}）", asset.name, asset.abbr, asset.id, asset.precision);
+    println!("发送方: {}", from_addr);
+    println!("接收方: {}", to_addr);
+    println!("金额: {}（= {} 最小单位）", asset.format_amount(amount), amount);
+
+    let account = api.get_account(&from_addr).await?;
+    let balance = account_trc10_balances(&account)
+        .into_iter()
+        .find(|(id, _)| *id == asset.id)
+        .map(|(_, v)| v)
+        .unwrap_or(0);
+    println!("发送方余额: {}", asset.format_amount(balance));
+    if balance < amount {
+        println!("❌ TRC10 余额不足，已中止（未签名未广播）");
+        return Ok(());
+    }
+
+    // 1. 构建交易
+    println!("\n1. 构建 TRC10 转账交易（/wallet/transferasset）...");
+    let body = json!({
+        "owner_address": from_addr,
+        "to_address": to_addr,
+        "asset_name": asset.id,
+        "amount": amount,
+        "visible": true,
+    });
+    let tx_json = match api.build_transaction("transferasset", body).await {
+        Ok(tx) => tx,
+        Err(e) => {
+            println!("❌ 构建失败: {}", e);
+            return Ok(());
+        }
+    };
+    println!("   构建成功");
+
+    // 2~4. 签名 → 广播 → 等待确认
+    trc20::sign_broadcast_and_wait(network, &tx_json, &private_key, 2).await?;
+
+    Ok(())
+}
+
+fn str_field(v: &Value, key: &str) -> String {
+    v.get(key).and_then(|x| x.as_str()).unwrap_or("").to_string()
+}
+
+/// 十六进制编码的 UTF-8 文本（资产 name / abbr / url / description）
+fn hex_text(s: &str) -> String {
+    match abi::hex_to_bytes(s) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => s.to_string(),
+    }
+}
@@ -1,11 +1,13 @@
 //! TRC20 代币测试与示例（只读查询、构建交易、全自动流程）
 
 use crate::config;
-use chains_sdk::chain::tron::{sign_tron_transaction, TronChain};
-use chains_sdk::rpc::chains::tron::TronRpcProvider;
-use chains_sdk::transaction::{TransactionMonitor, TransactionSender, TransactionStatus};
+use crate::ledger::{self, TxInfo};
+use crate::sdk::TronSdk;
+use crate::tron_monitor::{self, FinalityStatus};
+use crate::tron_rpc::TronApi;
+use chains_sdk::chain::tron::sign_tron_transaction;
+use chains_sdk::rpc::chains::tron::TronNetwork;
 use std::env;
-use std::sync::Arc;
 
 /// 对外暴露：当前 Tron 网络（供 main 等使用）
 pub fn current_tron_network() -> chains_sdk::rpc::chains::tron::TronNetwork {
@@ -26,15 +28,14 @@ pub async fn run_usdt_balance() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     let network = config::current_tron_network();
-    let chain = TronChain::from_network(network);
-    let provider = TronRpcProvider::from_network(network);
+    let sdk = TronSdk::new(network);
     let usdt_contract = network.usdt_contract();
 
     println!("=== TRC20 USDT 余额（{}）===", network.name());
     println!("地址: {}", address);
     println!("合约: {}", usdt_contract);
 
-    match chain.trc20_balance_of(&provider, &address, usdt_contract).await {
+    match sdk.trc20_balance_of(&address, usdt_contract).await {
         Ok(raw) => {
             // USDT 精度为 6，原始值 / 1_000_000 = 显示金额
             let decimals = 1_000_000u64;
@@ -51,22 +52,37 @@ pub async fn run_usdt_balance() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// 根据 SDK 验证 TRC20 API：只读接口 + 构建交易（网络由 TRON_NETWORK 指定）
+///
+/// 环境变量：TRC20_FEE_LIMIT（可选）— 构建交易的 fee_limit，sun 整数或 auto，默认 100_000_000
 pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     let network = config::current_tron_network();
-    let chain = TronChain::from_network(network);
-    let provider = TronRpcProvider::from_network(network);
+    let sdk = TronSdk::new(network);
     let contract = network.usdt_contract();
 
+    // fee_limit：TRC20_FEE_LIMIT 固定值，或 auto 时按示例转账预估
+    let fee_mode = fee_limit_mode()?;
+    let fee_limit = match fee_mode {
+        FeeLimitMode::Fixed(v) => v,
+        FeeLimitMode::Auto { .. } => {
+            let api = TronApi::new(config::tron_api_url(network));
+            let est = estimate_trc20_transfer(&api, config::TRON_EXAMPLE_ADDR, contract, config::TRON_EXAMPLE_ADDR, 1_000_000)
+                .await
+                .ok();
+            fee_mode.resolve(est.as_ref())
+        }
+    };
+
     println!("=== 根据 SDK 验证 TRC20 API（{}）===", network.name());
     println!("地址: {}", config::TRON_EXAMPLE_ADDR);
     println!("合约: {}", contract);
+    println!("fee_limit: {} sun（{}）", fee_limit, fee_mode.describe());
     println!();
 
     let mut ok = 0;
     let mut fail = 0;
 
     // trc20_balance_of
-    match chain.trc20_balance_of(&provider, config::TRON_EXAMPLE_ADDR, contract).await {
+    match sdk.trc20_balance_of(config::TRON_EXAMPLE_ADDR, contract).await {
         Ok(b) => {
             println!("✅ trc20_balance_of  -> {}", b);
             ok += 1;
@@ -78,7 +94,7 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_symbol
-    match chain.trc20_symbol(&provider, contract).await {
+    match sdk.trc20_symbol(contract).await {
         Ok(s) => {
             println!("✅ trc20_symbol       -> {}", s);
             ok += 1;
@@ -90,7 +106,7 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_decimals
-    match chain.trc20_decimals(&provider, contract).await {
+    match sdk.trc20_decimals(contract).await {
         Ok(d) => {
             println!("✅ trc20_decimals     -> {}", d);
             ok += 1;
@@ -102,7 +118,7 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_name
-    match chain.trc20_name(&provider, contract).await {
+    match sdk.trc20_name(contract).await {
         Ok(n) => {
             println!("✅ trc20_name         -> {}", n);
             ok += 1;
@@ -114,7 +130,7 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_total_supply
-    match chain.trc20_total_supply(&provider, contract).await {
+    match sdk.trc20_total_supply(contract).await {
         Ok(t) => {
             println!("✅ trc20_total_supply -> {}", t);
             ok += 1;
@@ -126,7 +142,7 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_allowance(owner, spender, contract)
-    match chain.trc20_allowance(&provider, config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, contract).await {
+    match sdk.trc20_allowance(config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, contract).await {
         Ok(a) => {
             println!("✅ trc20_allowance    -> {}", a);
             ok += 1;
@@ -138,7 +154,7 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_token_info(owner, contract) -> Balance
-    match chain.trc20_token_info(&provider, config::TRON_EXAMPLE_ADDR, contract).await {
+    match sdk.trc20_token_info(config::TRON_EXAMPLE_ADDR, contract).await {
         Ok(info) => {
             println!(
                 "✅ trc20_token_info   -> balance={} symbol={:?} decimals={:?}",
@@ -155,8 +171,8 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_build_transfer：校验返回 JSON 含 txID、raw_data
-    match chain
-        .trc20_build_transfer(&provider, config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, contract, "1000000", Some(100_000_000))
+    match sdk
+        .trc20_build_transfer(config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, contract, "1000000", Some(fee_limit))
         .await
     {
         Ok(tx_json) => {
@@ -182,8 +198,8 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_build_approve：仅校验返回为合法 JSON 且含 txID
-    match chain
-        .trc20_build_approve(&provider, config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, contract, "0", Some(100_000_000))
+    match sdk
+        .trc20_build_approve(config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, contract, "0", Some(fee_limit))
         .await
     {
         Ok(tx_json) => {
@@ -207,8 +223,8 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // trc20_build_transfer_from：仅校验返回为合法 JSON 且含 txID
-    match chain
-        .trc20_build_transfer_from(&provider, config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, contract, "0", Some(100_000_000))
+    match sdk
+        .trc20_build_transfer_from(config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, contract, "0", Some(fee_limit))
         .await
     {
         Ok(tx_json) => {
@@ -243,8 +259,7 @@ pub async fn run_verify_trc20() -> Result<(), Box<dyn std::error::Error>> {
 /// TRC20 查询与构建交易示例（不签名不广播，安全演示；网络由 TRON_NETWORK 指定）
 pub async fn run_trc20_demo() -> Result<(), Box<dyn std::error::Error>> {
     let network = config::current_tron_network();
-    let chain = TronChain::from_network(network);
-    let provider = TronRpcProvider::from_network(network);
+    let sdk = TronSdk::new(network);
 
     println!("=== TRC20 代币功能示例（{}）===", network.name());
     println!("地址: {}", config::TRON_EXAMPLE_ADDR);
@@ -258,49 +273,53 @@ pub async fn run_trc20_demo() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // 余额
-    match chain.trc20_balance_of(&provider, config::TRON_EXAMPLE_ADDR, &contract).await {
+    match sdk.trc20_balance_of(config::TRON_EXAMPLE_ADDR, &contract).await {
         Ok(balance) => println!("余额: {}", balance),
         Err(e) => println!("查询余额失败: {}", e),
     }
 
     // 符号
-    match chain.trc20_symbol(&provider, &contract).await {
+    match sdk.trc20_symbol(&contract).await {
         Ok(symbol) => println!("符号: {}", symbol),
         Err(e) => println!("查询符号失败: {}", e),
     }
 
     // 精度
-    match chain.trc20_decimals(&provider, &contract).await {
+    match sdk.trc20_decimals(&contract).await {
         Ok(decimals) => println!("精度: {}", decimals),
         Err(e) => println!("查询精度失败: {}", e),
     }
 
     // 名称
-    match chain.trc20_name(&provider, &contract).await {
+    match sdk.trc20_name(&contract).await {
         Ok(name) => println!("名称: {}", name),
         Err(e) => println!("查询名称失败: {}", e),
     }
 
     // 总供应
-    match chain.trc20_total_supply(&provider, &contract).await {
+    match sdk.trc20_total_supply(&contract).await {
         Ok(supply) => println!("总供应量: {}", supply),
         Err(e) => println!("查询总供应失败: {}", e),
     }
 
-    // 构建一个示例 TRC20 转账交易（不签名不发送）
-    match chain
-        .trc20_build_transfer(
-            &provider,
-            config::TRON_EXAMPLE_ADDR,
-            config::TRON_EXAMPLE_ADDR,
-            &contract,
-            "1000000",
-            Some(100_000_000),
-        )
+    // 构建一个示例 TRC20 转账交易（不签名不发送）；fee_limit 同 tron-full-flow 按 TRC20_FEE_LIMIT 确定
+    let fee_mode = fee_limit_mode()?;
+    let fee_limit = match fee_mode {
+        FeeLimitMode::Fixed(v) => v,
+        FeeLimitMode::Auto { .. } => {
+            let api = TronApi::new(config::tron_api_url(network));
+            let est = estimate_trc20_transfer(&api, config::TRON_EXAMPLE_ADDR, &contract, config::TRON_EXAMPLE_ADDR, 1_000_000)
+                .await
+                .ok();
+            fee_mode.resolve(est.as_ref())
+        }
+    };
+    match sdk
+        .trc20_build_transfer(config::TRON_EXAMPLE_ADDR, config::TRON_EXAMPLE_ADDR, &contract, "1000000", Some(fee_limit))
         .await
     {
         Ok(tx_json) => {
-            println!("构建 TRC20 转账交易成功 (未签名未发送)");
+            println!("构建 TRC20 转账交易成功 (未签名未发送)，fee_limit: {} sun（{}）", fee_limit, fee_mode.describe());
             println!("交易 JSON 长度: {} 字节", tx_json.len());
         }
         Err(e) => println!("构建 TRC20 转账交易失败: {}", e),
@@ -333,8 +352,7 @@ pub async fn run_trx_transfer() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap_or(1000);
 
     let network = config::current_tron_network();
-    let chain = TronChain::from_network(network);
-    let provider = TronRpcProvider::from_network(network);
+    let sdk = TronSdk::new(network);
 
     println!("=== TRX 转账流程（{}，构建 → 签名 → 广播 → 监听）===", network.name());
     println!("发送方: {}", from_addr);
@@ -343,49 +361,21 @@ pub async fn run_trx_transfer() -> Result<(), Box<dyn std::error::Error>> {
 
     // 1. 构建交易
     println!("\n1. 构建 TRX 转账交易...");
-    let tx_json = chain
-        .trx_build_transfer(&provider, &from_addr, &to_addr, amount_sun)
-        .await?;
+    let tx_json = sdk.trx_build_transfer(&from_addr, &to_addr, amount_sun).await?;
 
     println!("   构建成功");
 
-    // 2. 签名
-    println!("2. 使用 TRON_PRIVATE_KEY 签名...");
-    let signed_tx = sign_tron_transaction(&tx_json, &private_key)?;
-    println!("   签名成功");
-
-    // 3. 广播
-    let chain_arc = Arc::new(chain);
-    let sender = TransactionSender::new(chain_arc.clone());
-    let monitor = TransactionMonitor::new(chain_arc);
-    println!("3. 广播交易...");
-    let tx_hash = match sender.send(&signed_tx).await {
-        Ok(h) => h,
-        Err(e) => {
-            println!("❌ 广播失败: {}", e);
-            return Ok(());
-        }
-    };
-    println!("   交易哈希: {}", tx_hash);
-
-    // 4. 等待确认
-    const MIN_CONFIRMATIONS: u32 = 19;
-    println!("4. 等待最终确认（至少 {} 个区块，超时 120s，轮询 3s）...", MIN_CONFIRMATIONS);
-    let status = monitor
-        .wait_for_confirmation_with_timeout(&tx_hash, 120, Some(3000), Some(MIN_CONFIRMATIONS))
-        .await?;
-    match status {
-        TransactionStatus::Confirmed => {
-            println!("✅ 交易已最终确认（{} 个区块确认）!", MIN_CONFIRMATIONS);
-        }
-        TransactionStatus::Failed => println!("❌ 交易失败!"),
-        TransactionStatus::Pending => println!("⏳ 超时仍未达到 {} 个区块确认", MIN_CONFIRMATIONS),
-    }
+    // 2~4. 签名 → 广播 → 等待确认
+    sign_broadcast_and_wait(network, &tx_json, &private_key, 2).await?;
 
     Ok(())
 }
 
-/// 方案 B：全自动流程 —— 构建 TRC20 转账 → 私钥签名 → 广播 → 监听确认/失败
+/// 方案 B：全自动流程 —— 预执行 → 构建 TRC20 转账 → 私钥签名 → 广播 → 监听确认/失败
+///
+/// 构建前以 triggerconstantcontract 预执行转账：会回滚（黑名单、余额不足等）时中止；
+/// 按预估能量、账户可用能量与链上能量单价计算需燃烧的 TRX，超过 fee_limit 或余额时拒绝广播，
+/// 避免 OUT_OF_ENERGY 失败白白燃烧 TRX。
 ///
 /// 环境变量：
 /// - TRON_PRIVATE_KEY（必填）：发送方私钥，64 位十六进制（32 字节）
@@ -393,7 +383,8 @@ pub async fn run_trx_transfer() -> Result<(), Box<dyn std::error::Error>> {
 /// - TRON_FROM_ADDRESS（可选）：发送方地址，默认示例地址
 /// - TRON_TO_ADDRESS（可选）：接收方地址，默认同 FROM
 /// - TRC20_AMOUNT（可选）：转账金额（最小单位），默认 "1000000"
-/// - TRC20_FEE_LIMIT（可选）：费用上限 sun，默认 100_000_000
+/// - TRC20_FEE_LIMIT（可选）：费用上限 sun，默认 100_000_000；设为 auto 时按预估能量 × 能量单价 × (1 + 余量) 自动计算
+/// - TRC20_FEE_MARGIN_PCT（可选）：auto 模式的安全余量百分比，默认 20
 pub async fn run_full_flow() -> Result<(), Box<dyn std::error::Error>> {
     let private_key = match env::var("TRON_PRIVATE_KEY") {
         Ok(v) => v,
@@ -409,15 +400,11 @@ pub async fn run_full_flow() -> Result<(), Box<dyn std::error::Error>> {
     let from_addr = env::var("TRON_FROM_ADDRESS").unwrap_or_else(|_| config::TRON_EXAMPLE_ADDR.to_string());
     let to_addr = env::var("TRON_TO_ADDRESS").unwrap_or_else(|_| config::TRON_EXAMPLE_ADDR.to_string());
     let amount = env::var("TRC20_AMOUNT").unwrap_or_else(|_| "1000000".to_string());
-    let fee_limit: i64 = env::var("TRC20_FEE_LIMIT")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(100_000_000);
+    let fee_mode = fee_limit_mode()?;
 
-    let chain = TronChain::from_network(network);
-    let provider = TronRpcProvider::from_network(network);
+    let sdk = TronSdk::new(network);
 
-    println!("=== 全自动 TRC20 流程（{}，构建 → 签名 → 广播 → 监听）===", network.name());
+    println!("=== 全自动 TRC20 流程（{}，预执行 → 构建 → 签名 → 广播 → 监听）===", network.name());
     println!("发送方: {}", from_addr);
     println!("接收方: {}", to_addr);
     if env::var("TRC20_CONTRACT_ADDRESS").is_err() {
@@ -426,46 +413,218 @@ pub async fn run_full_flow() -> Result<(), Box<dyn std::error::Error>> {
         println!("合约: {}", contract);
     }
     println!("金额(最小单位): {}", amount);
+    println!("fee_limit 模式: {}", fee_mode.describe());
+
+    // 1. 预执行与能量预估（自动 fee_limit 依赖预估结果，故先于构建）
+    println!("\n1. 预执行（triggerconstantcontract）并预估能量...");
+    let amount_raw: u128 = amount
+        .trim()
+        .parse()
+        .map_err(|_| format!("TRC20_AMOUNT 应为非负整数（最小单位），当前: {}", amount))?;
+    let api = TronApi::new(config::tron_api_url(network));
+    let fee_limit = match estimate_trc20_transfer(&api, &from_addr, &contract, &to_addr, amount_raw).await {
+        Ok(est) => {
+            if let Some(reason) = &est.revert {
+                println!("❌ 预执行失败，交易将会回滚，已中止（未签名未广播）: {}", reason);
+                return Ok(());
+            }
+            est.print();
+            let fee_limit = fee_mode.resolve(Some(&est));
+            let burn = est.burn_sun();
+            if burn > fee_limit {
+                println!(
+                    "❌ fee_limit 不足: 预计需燃烧 {} sun（{:.6} TRX）> fee_limit {} sun，已中止（避免 OUT_OF_ENERGY）",
+                    burn,
+                    burn as f64 / 1_000_000.0,
+                    fee_limit
+                );
+                return Ok(());
+            }
+            if burn > est.balance_sun {
+                println!(
+                    "❌ TRX 余额不足: 预计需燃烧 {} sun > 账户余额 {} sun，已中止（避免 OUT_OF_ENERGY）",
+                    burn, est.balance_sun
+                );
+                return Ok(());
+            }
+            println!("   预执行通过");
+            fee_limit
+        }
+        Err(e) => {
+            println!("   ⚠ 预执行未完成，跳过模拟与能量检查: {}", e);
+            fee_mode.resolve(None)
+        }
+    };
+    println!("   fee_limit: {} sun（{:.6} TRX）", fee_limit, fee_limit as f64 / 1_000_000.0);
 
-    // 1. 构建交易
-    println!("\n1. 构建 TRC20 转账交易...");
-    let tx_json = chain
-        .trc20_build_transfer(&provider, &from_addr, &to_addr, &contract, &amount, Some(fee_limit))
-        .await?;
+    // 2. 构建交易
+    println!("2. 构建 TRC20 转账交易...");
+    let tx_json = sdk.trc20_build_transfer(&from_addr, &to_addr, &contract, &amount, Some(fee_limit)).await?;
     println!("   构建成功");
 
-    // 2. 签名
-    println!("2. 使用 TRON_PRIVATE_KEY 签名...");
-    let signed_tx = sign_tron_transaction(&tx_json, &private_key)?;
-    println!("   签名成功");
+    // 3~5. 签名 → 广播 → 等待确认
+    sign_broadcast_and_wait(network, &tx_json, &private_key, 3).await?;
 
-    // 3. 广播（需要 Arc<dyn Blockchain>）
-    let chain_arc = Arc::new(chain);
-    let sender = TransactionSender::new(chain_arc.clone());
-    let monitor = TransactionMonitor::new(chain_arc);
-    println!("3. 广播交易...");
-    let tx_hash = match sender.send(&signed_tx).await {
+    Ok(())
+}
+
+/// 签名 → 广播 → 等待固化（TRX 转账、TRC20、TRC10、质押等交易共用）
+///
+/// `step` 为签名步骤在输出中的序号；广播失败时打印原因并返回 None，成功返回交易哈希。
+/// 等待的超时与轮询间隔由 TRON_MONITOR_TIMEOUT_SEC / TRON_MONITOR_POLL_MS 配置。
+pub async fn sign_broadcast_and_wait(
+    network: TronNetwork,
+    tx_json: &str,
+    private_key: &str,
+    step: u32,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let entry = ledger::track(&TxInfo::tron(network, tx_json));
+    println!("{}. 使用 TRON_PRIVATE_KEY 签名...", step);
+    let signed_tx = sign_tron_transaction(tx_json, private_key)?;
+    println!("   签名成功");
+    let tx_id = serde_json::from_str::<serde_json::Value>(&signed_tx)?
+        .get("txID")
+        .and_then(|t| t.as_str())
+        .unwrap_or("")
+        .to_string();
+    entry.signed(&tx_id, None);
+
+    let sdk = TronSdk::new(network);
+    println!("{}. 广播交易...", step + 1);
+    let tx_hash = match sdk.send(&signed_tx).await {
         Ok(h) => h,
         Err(e) => {
             println!("❌ 广播失败: {}", e);
-            return Ok(());
+            entry.rejected(&e.to_string());
+            return Ok(None);
         }
     };
     println!("   交易哈希: {}", tx_hash);
+    entry.broadcast(&tx_hash);
 
-    // 4. 等待最终确认（Tron 常用 19 个区块后视为不可逆）
-    const MIN_CONFIRMATIONS: u32 = 19;
-    println!("4. 等待最终确认（至少 {} 个区块，超时 120s，轮询 3s）...", MIN_CONFIRMATIONS);
-    let status = monitor
-        .wait_for_confirmation_with_timeout(&tx_hash, 120, Some(3000), Some(MIN_CONFIRMATIONS))
-        .await?;
+    // 等待固化：所在区块进入固化区块后不可逆
+    let (timeout_sec, poll_ms) = (config::tron_monitor_timeout_sec(), config::tron_monitor_poll_ms());
+    println!("{}. 等待上链与固化（超时 {}s，轮询 {}ms）...", step + 2, timeout_sec, poll_ms);
+    let api = TronApi::new(config::tron_api_url(network));
+    let status = tron_monitor::wait_for_finality(&api, &tx_hash, timeout_sec, poll_ms).await;
+    print_finality_status(&status);
+    ledger::finish_tron(&api, &tx_hash, &status).await;
+
+    Ok(Some(tx_hash))
+}
+
+/// 打印 Tron 最终性监听结果
+pub fn print_finality_status(status: &FinalityStatus) {
     match status {
-        TransactionStatus::Confirmed => {
-            println!("✅ 交易已最终确认（{} 个区块确认）!", MIN_CONFIRMATIONS);
+        FinalityStatus::Solidified => println!("✅ 交易已固化（不可逆）!"),
+        FinalityStatus::Failed => println!("❌ 交易失败（已固化）!"),
+        FinalityStatus::Included => println!("⏳ 交易已上链，超时仍未固化"),
+        FinalityStatus::Pending => println!("⏳ 超时仍未上链"),
+    }
+}
+
+/// TRC20 转账预执行结果与能量费用预估
+pub struct Trc20Estimate {
+    /// 会回滚时的原因
+    pub revert: Option<String>,
+    /// 预估消耗能量
+    pub energy_used: i64,
+    /// 发送方当前可用能量（质押所得）
+    pub energy_available: i64,
+    /// 能量单价（sun / energy，链参数 getEnergyFee）
+    pub energy_price: i64,
+    /// 发送方 TRX 余额（sun）
+    pub balance_sun: i64,
+}
+
+impl Trc20Estimate {
+    /// 可用能量不足部分需燃烧的 TRX（sun）
+    fn burn_sun(&self) -> i64 {
+        (self.energy_used - self.energy_available).max(0) * self.energy_price
+    }
+
+    fn print(&self) {
+        let burn = self.burn_sun();
+        println!("   预估能量: {}（可用能量 {}）", self.energy_used, self.energy_available);
+        println!("   能量单价: {} sun", self.energy_price);
+        println!("   预计燃烧: {} sun（{:.6} TRX），账户余额 {:.6} TRX", burn, burn as f64 / 1_000_000.0, self.balance_sun as f64 / 1_000_000.0);
+    }
+}
+
+/// 预执行 TRC20 转账并查询发送方能量、余额与链上能量单价
+async fn estimate_trc20_transfer(
+    api: &TronApi,
+    from: &str,
+    contract: &str,
+    to: &str,
+    amount: u128,
+) -> Result<Trc20Estimate, Box<dyn std::error::Error>> {
+    let sim = api.simulate_trc20_transfer(from, contract, to, amount).await?;
+    Ok(Trc20Estimate {
+        revert: sim.revert,
+        energy_used: sim.energy_used,
+        energy_available: api.available_energy(from).await?,
+        energy_price: api.energy_price().await?,
+        balance_sun: api.account_balance(from).await?,
+    })
+}
+
+/// 未设置 TRC20_FEE_LIMIT 或自动计算失败时使用的 fee_limit（100 TRX）
+pub const DEFAULT_FEE_LIMIT_SUN: i64 = 100_000_000;
+
+/// auto 模式默认安全余量（百分比）
+const DEFAULT_FEE_MARGIN_PCT: i64 = 20;
+
+/// fee_limit 配置：固定值，或按预估能量自动计算
+pub enum FeeLimitMode {
+    Fixed(i64),
+    Auto { margin_pct: i64 },
+}
+
+impl FeeLimitMode {
+    pub fn describe(&self) -> String {
+        match self {
+            FeeLimitMode::Fixed(v) => format!("固定 {} sun", v),
+            FeeLimitMode::Auto { margin_pct } => format!("自动（预估能量 × 能量单价 × {}%）", 100 + margin_pct),
         }
-        TransactionStatus::Failed => println!("❌ 交易失败!"),
-        TransactionStatus::Pending => println!("⏳ 超时仍未达到 {} 个区块确认", MIN_CONFIRMATIONS),
     }
 
-    Ok(())
+    /// 计算实际使用的 fee_limit；auto 模式下无预估结果时退回默认值
+    pub fn resolve(&self, est: Option<&Trc20Estimate>) -> i64 {
+        match (self, est) {
+            (FeeLimitMode::Fixed(v), _) => *v,
+            (FeeLimitMode::Auto { margin_pct }, Some(est)) if est.energy_used > 0 => {
+                let full_cost = est.energy_used * est.energy_price;
+                (full_cost * (100 + margin_pct) + 99) / 100
+            }
+            (FeeLimitMode::Auto { .. }, _) => {
+                println!("   ⚠ 无能量预估结果，fee_limit 退回默认 {} sun", DEFAULT_FEE_LIMIT_SUN);
+                DEFAULT_FEE_LIMIT_SUN
+            }
+        }
+    }
+}
+
+/// 从环境变量 TRC20_FEE_LIMIT / TRC20_FEE_MARGIN_PCT 解析 fee_limit 配置
+pub fn fee_limit_mode() -> Result<FeeLimitMode, Box<dyn std::error::Error>> {
+    match env::var("TRC20_FEE_LIMIT").as_deref() {
+        Ok("auto") => {
+            let margin_pct = match env::var("TRC20_FEE_MARGIN_PCT") {
+                Ok(v) => v
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|m| *m >= 0)
+                    .ok_or_else(|| format!("TRC20_FEE_MARGIN_PCT 应为非负整数，当前: {}", v))?,
+                Err(_) => DEFAULT_FEE_MARGIN_PCT,
+            };
+            Ok(FeeLimitMode::Auto { margin_pct })
+        }
+        Ok(v) => v
+            .trim()
+            .parse()
+            .map(FeeLimitMode::Fixed)
+            .map_err(|_| format!("TRC20_FEE_LIMIT 应为 sun 整数或 auto，当前: {}", v).into()),
+        Err(_) => Ok(FeeLimitMode::Fixed(DEFAULT_FEE_LIMIT_SUN)),
+    }
 }
@@ -0,0 +1,219 @@
+//! Tron 账户资源查看：带宽、能量、Stake 2.0 质押、资源代理、待解锁、TRC10 余额、激活状态与权限
+
+use crate::config;
+use crate::trc10::{self, Trc10Asset};
+use crate::tron_rpc::TronApi;
+use serde_json::Value;
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 查看 Tron 账户资源（网络由 TRON_NETWORK 指定）
+///
+/// 环境变量：
+/// - TRON_ADDRESS（可选）：要查看的地址，默认示例地址
+/// - TRON_RPC_URL（可选）：Tron HTTP API
+pub async fn run_tron_account() -> Result<(), Box<dyn std::error::Error>> {
+    let network = config::current_tron_network();
+    let address = env::var("TRON_ADDRESS").unwrap_or_else(|_| config::TRON_EXAMPLE_ADDR.to_string());
+    let api = TronApi::new(config::tron_api_url(network));
+
+    println!("=== Tron 账户资源（{}）===", network.name());
+    println!("地址: {}", address);
+
+    let account = api.get_account(&address).await?;
+    if account.get("address").is_none() {
+        println!("状态: 未激活（链上无此账户，首次接收 TRX/TRC10 时激活）");
+        return Ok(());
+    }
+    match account.get("create_time").and_then(|t| t.as_i64()) {
+        Some(ms) => println!("状态: 已激活（创建时间 {}）", format_time_ms(ms)),
+        None => println!("状态: 已激活"),
+    }
+    println!("TRX 余额: {}", format_trx(i64_field(&account, "balance")));
+
+    let resource = api.get_account_resource(&address).await?;
+    print_bandwidth_and_energy(&resource);
+    print_stake(&account, &resource);
+    print_delegation(&api, &address, &account).await;
+    print_unfreezing(&account);
+    print_trc10(&api, &account).await;
+    print_permissions(&account);
+
+    Ok(())
+}
+
+/// 带宽（免费 + 质押）与能量额度、已用量
+fn print_bandwidth_and_energy(resource: &Value) {
+    println!("\n[带宽]");
+    println!(
+        "  免费: {} / {}",
+        i64_field(resource, "freeNetUsed"),
+        i64_field(resource, "freeNetLimit")
+    );
+    println!("  质押: {} / {}", i64_field(resource, "NetUsed"), i64_field(resource, "NetLimit"));
+    println!("[能量]");
+    println!("  已用 / 额度: {} / {}", i64_field(resource, "EnergyUsed"), i64_field(resource, "EnergyLimit"));
+}
+
+/// Stake 2.0 质押（frozenV2，type 缺省为带宽）与投票权
+fn print_stake(account: &Value, resource: &Value) {
+    let (mut bandwidth, mut energy) = (0i64, 0i64);
+    for item in array_field(account, "frozenV2") {
+        let amount = i64_field(item, "amount");
+        match item.get("type").and_then(|t| t.as_str()) {
+            Some("ENERGY") => energy += amount,
+            Some("TRON_POWER") => {}
+            _ => bandwidth += amount,
+        }
+    }
+    println!("[Stake 2.0 质押]");
+    println!("  带宽: {}", format_trx(bandwidth));
+    println!("  能量: {}", format_trx(energy));
+    println!(
+        "  投票权: {} / {}",
+        i64_field(resource, "tronPowerUsed"),
+        i64_field(resource, "tronPowerLimit")
+    );
+}
+
+/// 资源代理：代理给他人 / 他人代理给我（金额来自 getaccount，对方地址来自代理索引）
+async fn print_delegation(api: &TronApi, address: &str, account: &Value) {
+    let res = account.get("account_resource").cloned().unwrap_or(Value::Null);
+    println!("[资源代理]");
+    println!(
+        "  代理给他人: 带宽 {}，能量 {}",
+        format_trx(i64_field(account, "delegated_frozenV2_balance_for_bandwidth")),
+        format_trx(i64_field(&res, "delegated_frozenV2_balance_for_energy"))
+    );
+    println!(
+        "  他人代理给我: 带宽 {}，能量 {}",
+        format_trx(i64_field(account, "acquired_delegated_frozenV2_balance_for_bandwidth")),
+        format_trx(i64_field(&res, "acquired_delegated_frozenV2_balance_for_energy"))
+    );
+    match api.delegated_resource_index(address).await {
+        Ok(index) => {
+            for (key, label) in [("toAccounts", "代理对象"), ("fromAccounts", "代理来源")] {
+                let list: Vec<&str> = array_field(&index, key).iter().filter_map(|a| a.as_str()).collect();
+                if !list.is_empty() {
+                    println!("  {}: {}", label, list.join(", "));
+                }
+            }
+        }
+        Err(e) => println!("  ⚠ 查询代理索引失败: {}", e),
+    }
+}
+
+/// 待解锁（unfrozenV2）：金额、到期时间、是否可提取
+fn print_unfreezing(account: &Value) {
+    let items = array_field(account, "unfrozenV2");
+    println!("[待解锁]");
+    if items.is_empty() {
+        println!("  无");
+        return;
+    }
+    let now = now_ms();
+    for item in items {
+        let kind = item.get("type").and_then(|t| t.as_str()).unwrap_or("BANDWIDTH");
+        let expire = i64_field(item, "unfreeze_expire_time");
+        let state = if expire <= now {
+            "可提取（WithdrawExpireUnfreeze）".to_string()
+        } else {
+            format!("剩余 {}", format_duration_ms(expire - now))
+        };
+        println!(
+            "  {} {}，到期 {}，{}",
+            kind,
+            format_trx(i64_field(item, "unfreeze_amount")),
+            format_time_ms(expire),
+            state
+        );
+    }
+}
+
+/// TRC10 资产余额（assetV2），按资产 ID 查询名称与精度
+async fn print_trc10(api: &TronApi, account: &Value) {
+    let balances = trc10::account_trc10_balances(account);
+    println!("[TRC10 资产]");
+    if balances.is_empty() {
+        println!("  无");
+        return;
+    }
+    for (id, raw) in balances {
+        match Trc10Asset::fetch(api, &id).await {
+            Ok(Some(asset)) => println!("  {} {}: {}", id, asset.name, asset.format_amount(raw)),
+            Ok(None) | Err(_) => println!("  {}: {}（最小单位，资产信息查询失败）", id, raw),
+        }
+    }
+}
+
+/// owner / active 权限：阈值与各 key 权重
+fn print_permissions(account: &Value) {
+    println!("[权限]");
+    let mut perms: Vec<&Value> = account.get("owner_permission").into_iter().collect();
+    perms.extend(array_field(account, "active_permission"));
+    for p in perms {
+        let name = p.get("permission_name").and_then(|n| n.as_str()).unwrap_or("?");
+        let keys: Vec<String> = array_field(p, "keys")
+            .iter()
+            .map(|k| {
+                format!(
+                    "{}(权重 {})",
+                    k.get("address").and_then(|a| a.as_str()).unwrap_or("?"),
+                    i64_field(k, "weight")
+                )
+            })
+            .collect();
+        println!("  {}: 阈值 {}，{}", name, i64_field(p, "threshold"), keys.join(", "));
+    }
+}
+
+fn i64_field(v: &Value, key: &str) -> i64 {
+    v.get(key).and_then(|x| x.as_i64()).unwrap_or(0)
+}
+
+fn array_field<'a>(v: &'a Value, key: &str) -> &'a [Value] {
+    v.get(key).and_then(|x| x.as_array()).map(|a| a.as_slice()).unwrap_or(&[])
+}
+
+/// sun → "x.xxxxxx TRX"
+pub fn format_trx(sun: i64) -> String {
+    format!("{:.6} TRX", sun as f64 / 1_000_000.0)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// 毫秒时间戳 → UTC "YYYY-MM-DD HH:MM:SS"
+pub fn format_time_ms(ms: i64) -> String {
+    let secs = ms.div_euclid(1000);
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // 公历日期换算（Howard Hinnant civil_from_days）
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        y,
+        m,
+        d,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// 毫秒时长 → "x 天 y 小时 z 分"
+fn format_duration_ms(ms: i64) -> String {
+    let mins = ms / 60_000;
+    format!("{} 天 {} 小时 {} 分", mins / 1440, mins % 1440 / 60, mins % 60)
+}
@@ -0,0 +1,90 @@
+//! Tron 交易最终性监听：以固化区块（walletsolidity）判定不可逆，替代固定 19 个区块确认
+//!
+//! 两个里程碑：上链（gettransactioninfobyid 出现 blockNumber）与固化（walletsolidity/gettransactioninfobyid 查到该交易，
+//! 区块号与执行结果以固化节点为准）。
+
+use crate::rpc::RpcError;
+use crate::tron_rpc::TronApi;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// 监听结果
+pub enum FinalityStatus {
+    /// 已固化且执行成功
+    Solidified,
+    /// 已固化但执行失败（REVERT、OUT_OF_ENERGY 等）
+    Failed,
+    /// 超时：已上链但尚未固化
+    Included,
+    /// 超时：仍未上链
+    Pending,
+}
+
+/// 轮询直到交易所在区块被固化或超时；单次查询失败仅提示并继续轮询
+pub async fn wait_for_finality(api: &TronApi, tx_id: &str, timeout_sec: u64, poll_ms: u64) -> FinalityStatus {
+    let deadline = Instant::now() + Duration::from_secs(timeout_sec);
+    let mut tracker = Tracker { included: None, last_solidified: None };
+    loop {
+        match tracker.poll(api, tx_id).await {
+            Ok(Some(status)) => return status,
+            Ok(None) => {}
+            Err(e) => println!("   ⚠ 查询失败，稍后重试: {}", e),
+        }
+        if Instant::now() >= deadline {
+            return if tracker.included.is_some() { FinalityStatus::Included } else { FinalityStatus::Pending };
+        }
+        tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+    }
+}
+
+/// 轮询状态：未固化节点上查到的上链区块、已打印的固化区块号
+struct Tracker {
+    included: Option<i64>,
+    last_solidified: Option<i64>,
+}
+
+impl Tracker {
+    /// 固化里程碑以固化节点（walletsolidity/gettransactioninfobyid）查到的区块与执行结果为准；
+    /// 未固化节点上的上链区块只用于显示进度，该高度已固化而固化节点查不到交易时视为原区块已被分叉丢弃
+    async fn poll(&mut self, api: &TronApi, tx_id: &str) -> Result<Option<FinalityStatus>, RpcError> {
+        let solidified = api.solidified_block_number().await?;
+        let solid = api.solidified_transaction_info(tx_id).await?;
+        if let Some(block) = solid.get("blockNumber").and_then(|b| b.as_i64()) {
+            let (ret, success) = execution_result(&solid);
+            println!("   已固化: 区块 {}（执行结果 {}，最新固化区块 {}）", block, ret, solidified);
+            return Ok(Some(if success { FinalityStatus::Solidified } else { FinalityStatus::Failed }));
+        }
+
+        let block = match self.included {
+            Some(b) => b,
+            None => {
+                let info = api.get_transaction_info_by_id(tx_id).await?;
+                let block = match info.get("blockNumber").and_then(|b| b.as_i64()) {
+                    Some(b) => b,
+                    None => return Ok(None),
+                };
+                println!("   已上链: 区块 {}（执行结果 {}）", block, execution_result(&info).0);
+                self.included = Some(block);
+                block
+            }
+        };
+        if solidified >= block {
+            println!("   ⚠ 区块 {} 已固化但不含该交易（所在区块已被分叉丢弃），等待重新打包", block);
+            self.included = None;
+            self.last_solidified = None;
+            return Ok(None);
+        }
+        if self.last_solidified != Some(solidified) {
+            println!("   等待固化: 最新固化区块 {}，还差 {} 个区块", solidified, block - solidified);
+            self.last_solidified = Some(solidified);
+        }
+        Ok(None)
+    }
+}
+
+/// gettransactioninfobyid 的执行结果：receipt.result（缺省为 SUCCESS）及是否成功
+pub fn execution_result(info: &Value) -> (String, bool) {
+    let ret = info.pointer("/receipt/result").and_then(|r| r.as_str()).unwrap_or("SUCCESS");
+    let success = ret == "SUCCESS" && info.get("result").and_then(|r| r.as_str()) != Some("FAILED");
+    (ret.to_string(), success)
+}
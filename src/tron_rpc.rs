@@ -0,0 +1,399 @@
+//! Tron HTTP API 原始调用：triggerconstantcontract 预执行、交易构建与广播、账户资源、链参数、能量单价等
+
+use crate::abi;
+use crate::rpc::{self, RpcError};
+use serde_json::{json, Value};
+
+/// 轻量 Tron HTTP API 客户端（/wallet/*）；失败时按 rpc 模块的策略退避并切换到 config 登记的备用节点
+#[derive(Clone)]
+pub struct TronApi {
+    base_url: String,
+    client: reqwest::Client,
+    /// 只访问 base_url 本身：不重试、不切换端点
+    direct: bool,
+    /// direct 模式下请求前是否限流
+    throttled: bool,
+}
+
+/// 预执行结果与能量消耗
+pub struct TriggerResult {
+    /// 预估消耗能量（含动态能量惩罚）
+    pub energy_used: i64,
+    /// 会回滚时的原因
+    pub revert: Option<String>,
+    /// constant_result 返回数据
+    pub output: Vec<u8>,
+}
+
+impl TronApi {
+    pub fn new(base_url: String) -> Self {
+        Self { base_url, client: rpc::http_client(), direct: false, throttled: true }
+    }
+
+    /// 只访问指定节点的客户端（仍受限流，不重试、不切换端点），供 `rpc probe`、`consistency` 逐节点比较使用
+    pub fn direct(base_url: String) -> Self {
+        Self { base_url, client: rpc::http_client(), direct: true, throttled: true }
+    }
+
+    /// direct 客户端不再自行限流：由调用方在计时前取令牌（`rpc probe` 测延迟时避免把限流等待计入）
+    pub fn unthrottled(self) -> Self {
+        Self { throttled: false, ..self }
+    }
+
+    /// POST {base_url}/wallet/{path}，返回响应 JSON；响应含 Error 字段时视为节点错误
+    pub async fn post(&self, path: &str, body: Value) -> Result<Value, RpcError> {
+        self.post_to("wallet", path, body).await
+    }
+
+    /// POST {base_url}/walletsolidity/{path}：只返回已固化（不可逆）区块中的数据
+    pub async fn post_solidity(&self, path: &str, body: Value) -> Result<Value, RpcError> {
+        self.post_to("walletsolidity", path, body).await
+    }
+
+    async fn post_to(&self, prefix: &str, path: &str, body: Value) -> Result<Value, RpcError> {
+        if self.direct {
+            if self.throttled {
+                rpc::throttle(&self.base_url).await;
+            }
+            return self.post_once(self.base_url.clone(), prefix, path, body).await;
+        }
+        rpc::with_failover(&self.base_url, path, |base| self.post_once(base, prefix, path, body.clone())).await
+    }
+
+    async fn post_once(&self, base: String, prefix: &str, path: &str, body: Value) -> Result<Value, RpcError> {
+        let url = format!("{}/{}/{}", base, prefix, path);
+        let resp = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| RpcError::Transport(e.to_string()))?;
+        let status = resp.status();
+        if status.as_u16() == 429 || status.is_server_error() {
+            return Err(RpcError::Transport(format!("HTTP {}", status)));
+        }
+        let v: Value = resp
+            .json()
+            .await
+            .map_err(|e| RpcError::Transport(format!("HTTP {}，响应解析失败: {}", status, e)))?;
+        if let Some(err) = v.get("Error").and_then(|e| e.as_str()) {
+            return Err(RpcError::Rpc { code: status.as_u16() as i64, message: err.to_string(), data: None });
+        }
+        Ok(v)
+    }
+
+    /// GET {base_url}/wallet/{path}（无参数接口，如 getenergyprices）
+    pub async fn get(&self, path: &str) -> Result<Value, RpcError> {
+        if self.direct {
+            if self.throttled {
+                rpc::throttle(&self.base_url).await;
+            }
+            return self.get_once(self.base_url.clone(), path).await;
+        }
+        rpc::with_failover(&self.base_url, path, |base| self.get_once(base, path)).await
+    }
+
+    async fn get_once(&self, base: String, path: &str) -> Result<Value, RpcError> {
+        let url = format!("{}/wallet/{}", base, path);
+        let resp = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| RpcError::Transport(e.to_string()))?;
+        let status = resp.status();
+        if status.as_u16() == 429 || status.is_server_error() {
+            return Err(RpcError::Transport(format!("HTTP {}", status)));
+        }
+        resp.json()
+            .await
+            .map_err(|e| RpcError::Transport(format!("HTTP {}，响应解析失败: {}", status, e)))
+    }
+
+    /// triggerconstantcontract 预执行合约调用（地址使用 Base58，visible=true）
+    pub async fn trigger_constant_contract(
+        &self,
+        owner: &str,
+        contract: &str,
+        function_selector: &str,
+        parameter_hex: &str,
+    ) -> Result<TriggerResult, RpcError> {
+        let v = self
+            .post(
+                "triggerconstantcontract",
+                json!({
+                    "owner_address": owner,
+                    "contract_address": contract,
+                    "function_selector": function_selector,
+                    "parameter": parameter_hex,
+                    "visible": true,
+                }),
+            )
+            .await?;
+        let energy_used = v.get("energy_used").and_then(|e| e.as_i64()).unwrap_or(0);
+        let output = v
+            .pointer("/constant_result/0")
+            .and_then(|c| c.as_str())
+            .and_then(|c| abi::hex_to_bytes(c).ok())
+            .unwrap_or_default();
+        let ok = v.pointer("/result/result").and_then(|r| r.as_bool()).unwrap_or(false);
+        if !ok {
+            let msg = v.pointer("/result/message").and_then(|m| m.as_str()).unwrap_or("");
+            return Ok(TriggerResult { energy_used, revert: Some(decode_message(msg)), output });
+        }
+        let ret = v.pointer("/transaction/ret/0/ret").and_then(|r| r.as_str()).unwrap_or("SUCCESS");
+        if ret != "SUCCESS" {
+            let reason = format!("{}: {}", ret, abi::decode_revert_reason(&output));
+            return Ok(TriggerResult { energy_used, revert: Some(reason), output });
+        }
+        Ok(TriggerResult { energy_used, revert: None, output })
+    }
+
+    /// 预执行 TRC20 transfer(to, amount)；合约返回 false 也视为失败
+    pub async fn simulate_trc20_transfer(
+        &self,
+        owner: &str,
+        contract: &str,
+        to: &str,
+        amount: u128,
+    ) -> Result<TriggerResult, Box<dyn std::error::Error>> {
+        let to_bytes = abi::tron_address_to_bytes(to)?;
+        let mut param = Vec::with_capacity(64);
+        param.extend_from_slice(&abi::encode_address(&to_bytes));
+        param.extend_from_slice(&abi::encode_uint(amount));
+        let mut result = self
+            .trigger_constant_contract(owner, contract, "transfer(address,uint256)", &hex::encode(param))
+            .await?;
+        if result.revert.is_none() && abi::word(&result.output, 0).and_then(abi::uint_bytes_to_u128) == Some(0) {
+            result.revert = Some("transfer 返回 false".to_string());
+        }
+        Ok(result)
+    }
+
+    /// 调用 /wallet 下的交易创建接口（freezebalancev2、delegateresource 等），返回未签名交易 JSON
+    ///
+    /// 节点以 result.message（十六进制文本）返回校验失败原因，如余额不足、锁定期未到。
+    pub async fn build_transaction(&self, path: &str, body: Value) -> Result<String, RpcError> {
+        let v = self.post(path, body).await?;
+        if v.get("txID").and_then(|t| t.as_str()).is_some() {
+            return Ok(v.to_string());
+        }
+        let msg = v.pointer("/result/message").and_then(|m| m.as_str()).unwrap_or("");
+        Err(RpcError::Rpc {
+            code: 0,
+            message: format!("{} 未返回交易: {}", path, decode_message(msg)),
+            data: None,
+        })
+    }
+
+    /// triggersmartcontract：构建合约调用交易（地址使用 Base58，visible=true），返回未签名交易 JSON
+    pub async fn trigger_smart_contract(
+        &self,
+        owner: &str,
+        contract: &str,
+        function_selector: &str,
+        parameter_hex: &str,
+        fee_limit: i64,
+    ) -> Result<String, RpcError> {
+        let v = self
+            .post(
+                "triggersmartcontract",
+                json!({
+                    "owner_address": owner,
+                    "contract_address": contract,
+                    "function_selector": function_selector,
+                    "parameter": parameter_hex,
+                    "fee_limit": fee_limit,
+                    "call_value": 0,
+                    "visible": true,
+                }),
+            )
+            .await?;
+        match v.get("transaction") {
+            Some(tx) if v.pointer("/result/result").and_then(|r| r.as_bool()) == Some(true) => Ok(tx.to_string()),
+            _ => {
+                let msg = v.pointer("/result/message").and_then(|m| m.as_str()).unwrap_or("");
+                Err(RpcError::Rpc {
+                    code: 0,
+                    message: format!("triggersmartcontract 未返回交易: {}", decode_message(msg)),
+                    data: None,
+                })
+            }
+        }
+    }
+
+    /// broadcasttransaction：广播已签名交易 JSON，返回 txID；节点拒绝时错误信息为 "code: 原因"（如 DUP_TRANSACTION_ERROR）
+    pub async fn broadcast(&self, signed_json: &str) -> Result<String, RpcError> {
+        let tx: Value = serde_json::from_str(signed_json).map_err(|e| RpcError::Rpc {
+            code: 0,
+            message: format!("已签名交易不是合法 JSON: {}", e),
+            data: None,
+        })?;
+        let v = self.post("broadcasttransaction", tx.clone()).await?;
+        if v.get("result").and_then(|r| r.as_bool()) == Some(true) {
+            let tx_id = v.get("txid").or_else(|| tx.get("txID")).and_then(|t| t.as_str()).unwrap_or("");
+            return Ok(tx_id.to_string());
+        }
+        let code = v.get("code").and_then(|c| c.as_str()).unwrap_or("UNKNOWN");
+        let msg = v.get("message").and_then(|m| m.as_str()).unwrap_or("");
+        Err(RpcError::Rpc { code: 0, message: format!("{}: {}", code, decode_message(msg)), data: None })
+    }
+
+    /// getaccount：账户信息（余额、Stake 2.0 质押、待解锁、权限等）；未激活账户返回空对象
+    pub async fn get_account(&self, address: &str) -> Result<Value, RpcError> {
+        self.post("getaccount", json!({ "address": address, "visible": true })).await
+    }
+
+    /// getaccountresource：带宽、能量的额度与已用量
+    pub async fn get_account_resource(&self, address: &str) -> Result<Value, RpcError> {
+        self.post("getaccountresource", json!({ "address": address, "visible": true })).await
+    }
+
+    /// getdelegatedresourceaccountindexv2：代理资源的对方地址（toAccounts / fromAccounts）
+    pub async fn delegated_resource_index(&self, address: &str) -> Result<Value, RpcError> {
+        self.post("getdelegatedresourceaccountindexv2", json!({ "value": address, "visible": true }))
+            .await
+    }
+
+    /// getassetissuebyid：TRC10 资产信息（未使用 visible，name 等字段为十六进制文本，地址为 41 开头十六进制）
+    ///
+    /// 资产不存在时节点返回空对象。
+    pub async fn get_asset_issue_by_id(&self, id: &str) -> Result<Value, RpcError> {
+        self.post("getassetissuebyid", json!({ "value": id })).await
+    }
+
+    /// gettransactionbyid：交易本体（raw_data、ret[0].contractRet）；未知交易返回空对象
+    pub async fn get_transaction_by_id(&self, tx_id: &str) -> Result<Value, RpcError> {
+        self.post("gettransactionbyid", json!({ "value": tx_id, "visible": true })).await
+    }
+
+    /// gettransactioninfobyid：交易执行信息（区块、费用、能量/带宽消耗、contractResult、日志）；未上链返回空对象
+    pub async fn get_transaction_info_by_id(&self, tx_id: &str) -> Result<Value, RpcError> {
+        self.post("gettransactioninfobyid", json!({ "value": tx_id })).await
+    }
+
+    /// walletsolidity/gettransactioninfobyid：固化区块中的交易执行信息；交易所在区块尚未固化（或已被分叉丢弃）时返回空对象
+    pub async fn solidified_transaction_info(&self, tx_id: &str) -> Result<Value, RpcError> {
+        self.post_solidity("gettransactioninfobyid", json!({ "value": tx_id })).await
+    }
+
+    /// walletsolidity/getblockbynum：固化链上该高度的区块（尚未固化时返回空对象）
+    pub async fn solidified_block_by_num(&self, num: i64) -> Result<Value, RpcError> {
+        self.post_solidity("getblockbynum", json!({ "num": num })).await
+    }
+
+    /// getblockbynum：区块及其交易（visible=true，地址为 Base58）
+    pub async fn get_block_by_num(&self, num: i64) -> Result<Value, RpcError> {
+        self.post("getblockbynum", json!({ "num": num, "visible": true })).await
+    }
+
+    /// gettransactioninfobyblocknum：区块内全部交易的执行信息（含 log）；空区块返回空数组
+    pub async fn get_transaction_info_by_block_num(&self, num: i64) -> Result<Vec<Value>, RpcError> {
+        let v = self.post("gettransactioninfobyblocknum", json!({ "num": num })).await?;
+        Ok(v.as_array().cloned().unwrap_or_default())
+    }
+
+    /// getnowblock：最新区块号
+    pub async fn now_block_number(&self) -> Result<i64, RpcError> {
+        let v = self.post("getnowblock", json!({})).await?;
+        v.pointer("/block_header/raw_data/number")
+            .and_then(|n| n.as_i64())
+            .ok_or_else(|| RpcError::Transport("getnowblock 缺少 block_header.raw_data.number".to_string()))
+    }
+
+    /// walletsolidity/getnowblock：最新固化区块号（该高度及以下的区块不可逆）
+    pub async fn solidified_block_number(&self) -> Result<i64, RpcError> {
+        let v = self.post_solidity("getnowblock", json!({})).await?;
+        v.pointer("/block_header/raw_data/number")
+            .and_then(|n| n.as_i64())
+            .ok_or_else(|| RpcError::Transport("walletsolidity/getnowblock 缺少 block_header.raw_data.number".to_string()))
+    }
+
+    /// 账户 TRX 余额（sun），未激活账户返回 0
+    pub async fn account_balance(&self, address: &str) -> Result<i64, RpcError> {
+        let v = self.get_account(address).await?;
+        Ok(v.get("balance").and_then(|b| b.as_i64()).unwrap_or(0))
+    }
+
+    /// walletsolidity/getaccount：最新固化区块上的 TRX 余额（sun），未激活账户返回 0
+    pub async fn solidified_account_balance(&self, address: &str) -> Result<i64, RpcError> {
+        let v = self
+            .post_solidity("getaccount", json!({ "address": address, "visible": true }))
+            .await?;
+        Ok(v.get("balance").and_then(|b| b.as_i64()).unwrap_or(0))
+    }
+
+    /// walletsolidity/triggerconstantcontract：在最新固化状态上执行只读调用，返回 constant_result；调用失败时返回节点错误
+    pub async fn solidified_constant_call(
+        &self,
+        owner: &str,
+        contract: &str,
+        function_selector: &str,
+        parameter_hex: &str,
+    ) -> Result<Vec<u8>, RpcError> {
+        let v = self
+            .post_solidity(
+                "triggerconstantcontract",
+                json!({
+                    "owner_address": owner,
+                    "contract_address": contract,
+                    "function_selector": function_selector,
+                    "parameter": parameter_hex,
+                    "visible": true,
+                }),
+            )
+            .await?;
+        if !v.pointer("/result/result").and_then(|r| r.as_bool()).unwrap_or(false) {
+            let msg = v.pointer("/result/message").and_then(|m| m.as_str()).unwrap_or("");
+            return Err(RpcError::Rpc { code: 0, message: decode_message(msg), data: None });
+        }
+        Ok(v.pointer("/constant_result/0")
+            .and_then(|c| c.as_str())
+            .and_then(|c| abi::hex_to_bytes(c).ok())
+            .unwrap_or_default())
+    }
+
+    /// 账户当前可用能量（EnergyLimit - EnergyUsed）
+    pub async fn available_energy(&self, address: &str) -> Result<i64, RpcError> {
+        let v = self.get_account_resource(address).await?;
+        let limit = v.get("EnergyLimit").and_then(|x| x.as_i64()).unwrap_or(0);
+        let used = v.get("EnergyUsed").and_then(|x| x.as_i64()).unwrap_or(0);
+        Ok((limit - used).max(0))
+    }
+
+    /// 当前能量单价（sun / energy）：优先 getenergyprices 的最新一段，失败时读链参数 getEnergyFee
+    pub async fn energy_price(&self) -> Result<i64, RpcError> {
+        if let Ok(v) = self.get("getenergyprices").await {
+            if let Some(price) = v.get("prices").and_then(|p| p.as_str()).and_then(latest_price) {
+                return Ok(price);
+            }
+        }
+        let v = self.post("getchainparameters", json!({})).await?;
+        chain_parameter(&v, "getEnergyFee")
+            .ok_or_else(|| RpcError::Transport("getchainparameters 缺少 getEnergyFee".to_string()))
+    }
+}
+
+/// 解析 getenergyprices 的 "时间戳:单价,时间戳:单价" 历史，取最后一段
+fn latest_price(prices: &str) -> Option<i64> {
+    prices.rsplit(',').next()?.split(':').nth(1)?.trim().parse().ok()
+}
+
+/// 从 getchainparameters 响应中读取指定参数
+pub fn chain_parameter(v: &Value, key: &str) -> Option<i64> {
+    v.get("chainParameter")?
+        .as_array()?
+        .iter()
+        .find(|p| p.get("key").and_then(|k| k.as_str()) == Some(key))
+        .and_then(|p| p.get("value").and_then(|x| x.as_i64()))
+}
+
+/// 节点返回的 message 多为十六进制编码的 UTF-8 文本
+pub fn decode_message(msg: &str) -> String {
+    match abi::hex_to_bytes(msg) {
+        Ok(bytes) if !bytes.is_empty() => String::from_utf8_lossy(&bytes).into_owned(),
+        _ if !msg.is_empty() => msg.to_string(),
+        _ => "节点未返回原因".to_string(),
+    }
+}
@@ -0,0 +1,193 @@
+//! Tron Stake 2.0：质押、解质押、提取到期解质押、资源代理与取消代理
+//!
+//! 交易由 Tron HTTP API 创建，签名 → 广播 → 监听复用 trc20::sign_broadcast_and_wait。
+
+use crate::config;
+use crate::trc20;
+use crate::tron_account::format_trx;
+use crate::tron_rpc::TronApi;
+use serde_json::{json, Value};
+use std::env;
+
+/// 质押 TRX 获取资源（FreezeBalanceV2）
+///
+/// 环境变量：
+/// - TRON_PRIVATE_KEY（必填）：发送方私钥
+/// - TRON_FROM_ADDRESS（可选）：质押账户，默认示例地址
+/// - STAKE_AMOUNT_SUN（必填）：质押金额 sun
+/// - STAKE_RESOURCE（可选）：ENERGY（默认）| BANDWIDTH
+pub async fn run_freeze() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(ctx) = StakeContext::from_env()? else { return Ok(()) };
+    let amount = required_amount("STAKE_AMOUNT_SUN")?;
+    let resource = stake_resource()?;
+    ctx.print_header("质押 FreezeBalanceV2");
+    println!("资源: {}", resource);
+    println!("金额: {}", format_trx(amount));
+    ctx.execute(
+        "freezebalancev2",
+        json!({ "owner_address": ctx.from, "frozen_balance": amount, "resource": resource, "visible": true }),
+    )
+    .await
+}
+
+/// 解质押（UnfreezeBalanceV2），资金进入待解锁，到期后需 tron-withdraw-unfreeze 提取
+///
+/// 环境变量：同 tron-freeze（STAKE_AMOUNT_SUN 为解质押金额）
+pub async fn run_unfreeze() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(ctx) = StakeContext::from_env()? else { return Ok(()) };
+    let amount = required_amount("STAKE_AMOUNT_SUN")?;
+    let resource = stake_resource()?;
+    ctx.print_header("解质押 UnfreezeBalanceV2");
+    println!("资源: {}", resource);
+    println!("金额: {}", format_trx(amount));
+    ctx.execute(
+        "unfreezebalancev2",
+        json!({ "owner_address": ctx.from, "unfreeze_balance": amount, "resource": resource, "visible": true }),
+    )
+    .await
+}
+
+/// 提取已到期的解质押资金（WithdrawExpireUnfreeze）
+///
+/// 环境变量：TRON_PRIVATE_KEY（必填）、TRON_FROM_ADDRESS（可选）
+pub async fn run_withdraw_unfreeze() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(ctx) = StakeContext::from_env()? else { return Ok(()) };
+    ctx.print_header("提取到期解质押 WithdrawExpireUnfreeze");
+    ctx.execute("withdrawexpireunfreeze", json!({ "owner_address": ctx.from, "visible": true }))
+        .await
+}
+
+/// 将质押所得资源代理给其他地址（DelegateResource）
+///
+/// 环境变量：
+/// - TRON_PRIVATE_KEY（必填）、TRON_FROM_ADDRESS（可选）
+/// - TRON_RECEIVER_ADDRESS（必填）：资源接收方
+/// - STAKE_AMOUNT_SUN（必填）：代理的质押金额 sun
+/// - STAKE_RESOURCE（可选）：ENERGY（默认）| BANDWIDTH
+/// - DELEGATE_LOCK_PERIOD（可选）：锁定期（区块数，约 3 秒/块），大于 0 时锁定，期间不可取消代理
+pub async fn run_delegate() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(ctx) = StakeContext::from_env()? else { return Ok(()) };
+    let receiver = required_receiver()?;
+    let amount = required_amount("STAKE_AMOUNT_SUN")?;
+    let resource = stake_resource()?;
+    let lock_period: i64 = match env::var("DELEGATE_LOCK_PERIOD") {
+        Ok(v) => v
+            .trim()
+            .parse()
+            .map_err(|_| format!("DELEGATE_LOCK_PERIOD 应为区块数整数，当前: {}", v))?,
+        Err(_) => 0,
+    };
+    ctx.print_header("代理资源 DelegateResource");
+    println!("接收方: {}", receiver);
+    println!("资源: {}", resource);
+    println!("金额: {}", format_trx(amount));
+    let mut body = json!({
+        "owner_address": ctx.from,
+        "receiver_address": receiver,
+        "balance": amount,
+        "resource": resource,
+        "visible": true,
+    });
+    if lock_period > 0 {
+        println!("锁定期: {} 个区块（约 {} 小时）", lock_period, lock_period * 3 / 3600);
+        body["lock"] = json!(true);
+        body["lock_period"] = json!(lock_period);
+    }
+    ctx.execute("delegateresource", body).await
+}
+
+/// 取消资源代理（UnDelegateResource），锁定期内节点会拒绝
+///
+/// 环境变量：同 tron-delegate（无 DELEGATE_LOCK_PERIOD）
+pub async fn run_undelegate() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(ctx) = StakeContext::from_env()? else { return Ok(()) };
+    let receiver = required_receiver()?;
+    let amount = required_amount("STAKE_AMOUNT_SUN")?;
+    let resource = stake_resource()?;
+    ctx.print_header("取消代理 UnDelegateResource");
+    println!("接收方: {}", receiver);
+    println!("资源: {}", resource);
+    println!("金额: {}", format_trx(amount));
+    ctx.execute(
+        "undelegateresource",
+        json!({
+            "owner_address": ctx.from,
+            "receiver_address": receiver,
+            "balance": amount,
+            "resource": resource,
+            "visible": true,
+        }),
+    )
+    .await
+}
+
+/// 质押类命令共用的私钥、发送方与网络
+struct StakeContext {
+    private_key: String,
+    from: String,
+    network: chains_sdk::rpc::chains::tron::TronNetwork,
+}
+
+impl StakeContext {
+    /// 读取 TRON_PRIVATE_KEY / TRON_FROM_ADDRESS；未设置私钥时打印提示并返回 None
+    fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let private_key = match env::var("TRON_PRIVATE_KEY") {
+            Ok(v) => v,
+            Err(_) => {
+                eprintln!("未设置 TRON_PRIVATE_KEY。");
+                eprintln!("示例: export TRON_PRIVATE_KEY=你的64位十六进制私钥");
+                return Ok(None);
+            }
+        };
+        Ok(Some(Self {
+            private_key,
+            from: env::var("TRON_FROM_ADDRESS").unwrap_or_else(|_| config::TRON_EXAMPLE_ADDR.to_string()),
+            network: config::current_tron_network(),
+        }))
+    }
+
+    fn print_header(&self, title: &str) {
+        println!("=== {}（{}，构建 → 签名 → 广播 → 监听）===", title, self.network.name());
+        println!("账户: {}", self.from);
+    }
+
+    /// 1. 由 Tron HTTP API 创建交易；2~4. 签名 → 广播 → 等待确认
+    async fn execute(&self, path: &str, body: Value) -> Result<(), Box<dyn std::error::Error>> {
+        let api = TronApi::new(config::tron_api_url(self.network));
+        println!("\n1. 构建交易（/wallet/{}）...", path);
+        let tx_json = match api.build_transaction(path, body).await {
+            Ok(tx) => tx,
+            Err(e) => {
+                println!("❌ 构建失败: {}", e);
+                return Ok(());
+            }
+        };
+        println!("   构建成功");
+        trc20::sign_broadcast_and_wait(self.network, &tx_json, &self.private_key, 2).await?;
+        Ok(())
+    }
+}
+
+/// STAKE_RESOURCE：ENERGY（默认）| BANDWIDTH
+fn stake_resource() -> Result<&'static str, Box<dyn std::error::Error>> {
+    match env::var("STAKE_RESOURCE").as_deref() {
+        Err(_) | Ok("ENERGY") | Ok("energy") => Ok("ENERGY"),
+        Ok("BANDWIDTH") | Ok("bandwidth") => Ok("BANDWIDTH"),
+        Ok(other) => Err(format!("STAKE_RESOURCE 应为 ENERGY 或 BANDWIDTH，当前: {}", other).into()),
+    }
+}
+
+/// 必填金额（sun，正整数）
+fn required_amount(var: &str) -> Result<i64, Box<dyn std::error::Error>> {
+    let v = env::var(var).map_err(|_| format!("未设置 {}（金额 sun，1 TRX = 1000000 sun）", var))?;
+    v.trim()
+        .parse::<i64>()
+        .ok()
+        .filter(|a| *a > 0)
+        .ok_or_else(|| format!("{} 应为正整数（sun），当前: {}", var, v).into())
+}
+
+/// 必填资源接收方 TRON_RECEIVER_ADDRESS
+fn required_receiver() -> Result<String, Box<dyn std::error::Error>> {
+    env::var("TRON_RECEIVER_ADDRESS").map_err(|_| "未设置 TRON_RECEIVER_ADDRESS（资源接收方地址）".into())
+}
@@ -0,0 +1,256 @@
+//! 交易查询：`tx show <hash>` 按哈希查询任意交易（含历史交易）并解析类型、双方、代币、金额、手续费与状态；
+//! `tx speedup` / `tx cancel` 替换卡住的 EVM 交易（见 evm_replace）
+//!
+//! 0x 开头的哈希按 EVM（EVM_NETWORK）查询，否则按 Tron（TRON_NETWORK）查询。
+
+use crate::abi;
+use crate::config;
+use crate::events::{self, TokenMeta};
+use crate::evm_replace::{self, ReplaceKind};
+use crate::evm_rpc::EvmRpc;
+use crate::receipt;
+use crate::sdk::{EvmSdk, TronSdk};
+use crate::trc10::Trc10Asset;
+use crate::tron_account::format_trx;
+use crate::tron_rpc::TronApi;
+use serde_json::Value;
+
+/// transfer(address,uint256)
+const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+/// approve(address,uint256)
+const APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+/// transferFrom(address,address,uint256)
+const TRANSFER_FROM_SELECTOR: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+/// `tx <子命令> ...` 入口
+///
+/// 子命令：
+/// - show <hash>：查询并解析交易
+/// - speedup <hash>：同 nonce 提价重发仍在交易池中的 EVM 交易
+/// - cancel <hash>：同 nonce 发送 0 金额自转账，取消仍在交易池中的 EVM 交易
+pub async fn run_tx(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match (args.first().map(|s| s.as_str()), args.get(1)) {
+        (Some("show"), Some(hash)) => run_tx_show(hash).await,
+        (Some("speedup"), Some(hash)) => evm_replace::run_replace(hash, ReplaceKind::SpeedUp).await,
+        (Some("cancel"), Some(hash)) => evm_replace::run_replace(hash, ReplaceKind::Cancel).await,
+        _ => {
+            eprintln!("用法: cargo run -- tx show <交易哈希>");
+            eprintln!("      cargo run -- tx speedup <交易哈希>   （EVM，需 ETH_PRIVATE_KEY）");
+            eprintln!("      cargo run -- tx cancel <交易哈希>    （EVM，需 ETH_PRIVATE_KEY）");
+            eprintln!("  0x 开头按 EVM（EVM_NETWORK）查询，否则按 Tron（TRON_NETWORK）查询");
+            Ok(())
+        }
+    }
+}
+
+/// 查询交易与回执并解析（一次性查询，不等待确认）
+pub async fn run_tx_show(hash: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if hash.starts_with("0x") || hash.starts_with("0X") {
+        show_evm_tx(hash).await
+    } else {
+        show_tron_tx(hash).await
+    }
+}
+
+/// ERC20 / TRC20 常用调用
+enum TokenCall {
+    Transfer { to: [u8; 20], amount: Option<u128> },
+    Approve { spender: [u8; 20], amount: Option<u128> },
+    TransferFrom { from: [u8; 20], to: [u8; 20], amount: Option<u128> },
+}
+
+/// 按 selector 解析 transfer / approve / transferFrom 的 calldata
+fn decode_token_call(data: &[u8]) -> Option<TokenCall> {
+    let (selector, body) = (data.get(..4)?, data.get(4..)?);
+    let addr = |i: usize| -> Option<[u8; 20]> {
+        let mut out = [0u8; 20];
+        out.copy_from_slice(&abi::word(body, i)?[12..]);
+        Some(out)
+    };
+    let amount = |i: usize| abi::word(body, i).map(abi::uint_bytes_to_u128);
+    if selector == TRANSFER_SELECTOR {
+        Some(TokenCall::Transfer { to: addr(0)?, amount: amount(1)? })
+    } else if selector == APPROVE_SELECTOR {
+        Some(TokenCall::Approve { spender: addr(0)?, amount: amount(1)? })
+    } else if selector == TRANSFER_FROM_SELECTOR {
+        Some(TokenCall::TransferFrom { from: addr(0)?, to: addr(1)?, amount: amount(2)? })
+    } else {
+        None
+    }
+}
+
+/// 打印代币调用；format_addr 将 20 字节地址转为所在链的显示格式
+fn print_token_call(standard: &str, call: &TokenCall, sender: &str, meta: &TokenMeta, format_addr: impl Fn(&[u8; 20]) -> String) {
+    match call {
+        TokenCall::Transfer { to, amount } => {
+            println!("类型: {} transfer", standard);
+            println!("发送方: {}", sender);
+            println!("接收方: {}", format_addr(to));
+            println!("金额: {}", meta.format_amount(*amount));
+        }
+        TokenCall::Approve { spender, amount } => {
+            println!("类型: {} approve", standard);
+            println!("授权方: {}", sender);
+            println!("被授权方: {}", format_addr(spender));
+            println!("额度: {}", meta.format_amount(*amount));
+        }
+        TokenCall::TransferFrom { from, to, amount } => {
+            println!("类型: {} transferFrom", standard);
+            println!("调用方: {}", sender);
+            println!("转出方: {}", format_addr(from));
+            println!("接收方: {}", format_addr(to));
+            println!("金额: {}", meta.format_amount(*amount));
+        }
+    }
+}
+
+/// EVM：eth_getTransactionByHash 解析调用，回执部分复用 receipt::print_evm_receipt
+async fn show_evm_tx(hash: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let network = config::current_evm_network()?;
+    let rpc_url = config::evm_rpc_url(&network).await;
+    let rpc = EvmRpc::new(rpc_url.clone());
+
+    println!("=== 交易详情（{}）===", network.name());
+    println!("交易哈希: {}", hash);
+
+    let tx = rpc.get_transaction(hash).await?;
+    if tx.is_null() {
+        println!("❌ 未找到交易（请确认 EVM_NETWORK 与哈希所属网络一致）");
+        return Ok(());
+    }
+    let sender = tx.get("from").and_then(|f| f.as_str()).unwrap_or("?").to_string();
+    let value = tx.get("value").and_then(abi::parse_quantity).unwrap_or(0);
+    let input = abi::hex_to_bytes(tx.get("input").and_then(|i| i.as_str()).unwrap_or("0x")).unwrap_or_default();
+
+    match tx.get("to").and_then(|t| t.as_str()) {
+        None => {
+            println!("类型: 合约创建");
+            println!("发送方: {}", sender);
+        }
+        Some(to) if input.is_empty() => {
+            println!("类型: 原生转账");
+            println!("发送方: {}", sender);
+            println!("接收方: {}", to);
+            println!("金额: {} {}", abi::format_units(value, 18), network.native_symbol);
+        }
+        Some(to) => match decode_token_call(&input) {
+            Some(call) => {
+                let sdk = EvmSdk::new(rpc_url.clone());
+                let meta = events::evm_token_meta(&sdk, &sender, to).await;
+                print_token_call("ERC20", &call, &sender, &meta, |a| format!("0x{}", hex::encode(a)));
+                println!("代币: {}（{}）", to, if meta.symbol.is_empty() { "?" } else { &meta.symbol });
+            }
+            None => {
+                println!("类型: 未知合约调用（selector 0x{}）", hex::encode(input.get(..4).unwrap_or(&input)));
+                println!("发送方: {}", sender);
+                println!("合约: {}", to);
+                if value > 0 {
+                    println!("附带金额: {} {}", abi::format_units(value, 18), network.native_symbol);
+                }
+            }
+        },
+    }
+
+    receipt::print_evm_receipt(&rpc_url, hash).await;
+    Ok(())
+}
+
+/// Tron：gettransactionbyid 解析合约类型与参数，回执部分复用 receipt::print_tron_receipt
+async fn show_tron_tx(hash: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let network = config::current_tron_network();
+    let api = TronApi::new(config::tron_api_url(network));
+
+    println!("=== 交易详情（{}）===", network.name());
+    println!("交易哈希: {}", hash);
+
+    let tx = api.get_transaction_by_id(hash).await?;
+    if tx.get("txID").is_none() {
+        println!("❌ 未找到交易（请确认 TRON_NETWORK 与哈希所属网络一致）");
+        return Ok(());
+    }
+    let contract = tx.pointer("/raw_data/contract/0").cloned().unwrap_or(Value::Null);
+    let kind = contract.get("type").and_then(|t| t.as_str()).unwrap_or("?");
+    let p = contract.pointer("/parameter/value").cloned().unwrap_or(Value::Null);
+    let owner = str_field(&p, "owner_address");
+
+    match kind {
+        "TransferContract" => {
+            println!("类型: 原生转账（TRX）");
+            println!("发送方: {}", owner);
+            println!("接收方: {}", str_field(&p, "to_address"));
+            println!("金额: {}", format_trx(i64_field(&p, "amount")));
+        }
+        "TransferAssetContract" => {
+            let asset_id = str_field(&p, "asset_name");
+            let amount = i64_field(&p, "amount");
+            println!("类型: TRC10 转账");
+            println!("发送方: {}", owner);
+            println!("接收方: {}", str_field(&p, "to_address"));
+            match Trc10Asset::fetch(&api, &asset_id).await {
+                Ok(Some(asset)) => {
+                    println!("代币: {} {}（ID {}）", asset.name, asset.abbr, asset.id);
+                    println!("金额: {}", asset.format_amount(amount));
+                }
+                _ => {
+                    println!("代币: TRC10 ID {}", asset_id);
+                    println!("金额: {} 最小单位", amount);
+                }
+            }
+        }
+        "TriggerSmartContract" => {
+            let token = str_field(&p, "contract_address");
+            let data = abi::hex_to_bytes(&str_field(&p, "data")).unwrap_or_default();
+            match decode_token_call(&data) {
+                Some(call) => {
+                    let sdk = TronSdk::new(network);
+                    let meta = events::tron_token_meta(&sdk, &owner, &token).await;
+                    print_token_call("TRC20", &call, &owner, &meta, |a| {
+                        abi::tron_address_from_hex(&format!("41{}", hex::encode(a))).unwrap_or_default()
+                    });
+                    println!("代币: {}（{}）", token, if meta.symbol.is_empty() { "?" } else { &meta.symbol });
+                }
+                None => {
+                    println!("类型: 未知合约调用（selector 0x{}）", hex::encode(data.get(..4).unwrap_or(&data)));
+                    println!("发送方: {}", owner);
+                    println!("合约: {}", token);
+                    let call_value = i64_field(&p, "call_value");
+                    if call_value > 0 {
+                        println!("附带金额: {}", format_trx(call_value));
+                    }
+                }
+            }
+        }
+        "FreezeBalanceV2Contract" | "UnfreezeBalanceV2Contract" | "DelegateResourceContract"
+        | "UnDelegateResourceContract" | "WithdrawExpireUnfreezeContract" => {
+            println!("类型: 质押（{}）", kind);
+            println!("账户: {}", owner);
+            let receiver = str_field(&p, "receiver_address");
+            if !receiver.is_empty() {
+                println!("资源接收方: {}", receiver);
+            }
+            let amount = ["frozen_balance", "unfreeze_balance", "balance"]
+                .iter()
+                .map(|k| i64_field(&p, k))
+                .find(|a| *a > 0);
+            if let Some(amount) = amount {
+                println!("资源: {}", p.get("resource").and_then(|r| r.as_str()).unwrap_or("BANDWIDTH"));
+                println!("金额: {}", format_trx(amount));
+            }
+        }
+        other => {
+            println!("类型: 其他合约（{}）", other);
+            println!("发送方: {}", owner);
+        }
+    }
+
+    receipt::print_tron_receipt(network, hash).await;
+    Ok(())
+}
+
+fn str_field(v: &Value, key: &str) -> String {
+    v.get(key).and_then(|x| x.as_str()).unwrap_or("").to_string()
+}
+
+fn i64_field(v: &Value, key: &str) -> i64 {
+    v.get(key).and_then(|x| x.as_i64()).unwrap_or(0)
+}
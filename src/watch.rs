@@ -0,0 +1,474 @@
+//! 入账监控：跟随新区块，报告转入指定地址的原生币与代币（TRX / TRC10 / TRC20，ETH / ERC20）
+//!
+//! 地址以 0x 开头时监控 EVM（EVM_NETWORK），以 T 开头时监控 Tron（TRON_NETWORK）。
+//! 入账在所在区块之后累计达到确认深度才报告为最终。EVM 记录确认深度内每个已扫描区块的哈希，
+//! 新区块的父哈希与记录不符时回退到分叉点重扫（重组区块即使原本没有入账也会重扫）；最终报告前再复核一次区块哈希。
+
+use crate::abi;
+use crate::config;
+use crate::events::{self, TokenEventKind, TokenMeta};
+use crate::evm_rpc::EvmRpc;
+use crate::sdk::{EvmSdk, TronSdk};
+use crate::trc10::Trc10Asset;
+use crate::tron_account::format_trx;
+use crate::tron_rpc::TronApi;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::time::Duration;
+
+/// EVM 默认确认深度
+const DEFAULT_EVM_CONFIRMATIONS: u64 = 12;
+
+/// 监控指定地址的入账（持续运行，Ctrl+C 退出）
+///
+/// 地址来自命令参数（`watch <地址>...`）或 WATCH_ADDRESSES（逗号分隔）。
+///
+/// 环境变量：
+/// - WATCH_ADDRESSES（未传参数时必填）：要监控的地址，同一次只能全为 EVM 或全为 Tron 地址
+/// - WATCH_CONFIRMATIONS（可选，EVM）：确认深度，默认取网络的 finality_depth（未配置时 12）；1 表示上链即最终。
+///   Tron 与转账流程一致，以所在区块被固化（walletsolidity）为最终，不使用该变量
+/// - WATCH_START_BLOCK（可选）：起始区块，用于补扫历史；默认从最新区块开始
+/// - WATCH_POLL_MS（可选）：轮询间隔毫秒，默认 3000
+pub async fn run_watch(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let addresses: Vec<String> = if args.is_empty() {
+        env::var("WATCH_ADDRESSES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    } else {
+        args.to_vec()
+    };
+    if addresses.is_empty() {
+        eprintln!("请通过参数或 WATCH_ADDRESSES 提供要监控的地址。");
+        eprintln!("示例: cargo run -- watch TG2D8vTp4xHBB2vhVbgHK2AhA2p9wY4q9M");
+        eprintln!("      export WATCH_ADDRESSES=0xabc...,0xdef... && cargo run -- watch");
+        return Ok(());
+    }
+    let is_evm = addresses.iter().all(|a| a.starts_with("0x") || a.starts_with("0X"));
+    if !is_evm && addresses.iter().any(|a| a.starts_with("0x") || a.starts_with("0X")) {
+        return Err("WATCH_ADDRESSES 不能混用 EVM 与 Tron 地址，请分开运行".into());
+    }
+    let depth = match env::var("WATCH_CONFIRMATIONS") {
+        Ok(_) if !is_evm => {
+            println!("⚠ Tron 入账以固化区块为最终，忽略 WATCH_CONFIRMATIONS");
+            1
+        }
+        Ok(v) => v
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|d| *d > 0)
+            .ok_or_else(|| format!("WATCH_CONFIRMATIONS 应为正整数，当前: {}", v))?,
+        Err(_) if is_evm => config::current_evm_network()?.finality_depth.unwrap_or(DEFAULT_EVM_CONFIRMATIONS),
+        Err(_) => 1,
+    };
+    let start_block = match env::var("WATCH_START_BLOCK") {
+        Ok(v) => Some(
+            v.trim()
+                .parse::<u64>()
+                .map_err(|_| format!("WATCH_START_BLOCK 应为区块号整数，当前: {}", v))?,
+        ),
+        Err(_) => None,
+    };
+    let poll = Duration::from_millis(
+        env::var("WATCH_POLL_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3000),
+    );
+    let settings = WatchSettings { depth, start_block, poll };
+    if is_evm {
+        watch_evm(&addresses, &settings).await
+    } else {
+        watch_tron(&addresses, &settings).await
+    }
+}
+
+struct WatchSettings {
+    depth: u64,
+    start_block: Option<u64>,
+    poll: Duration,
+}
+
+/// 已发现、尚未达到确认深度的入账
+struct Deposit {
+    block: u64,
+    block_hash: String,
+    tx_hash: String,
+    from: String,
+    to: String,
+    /// 已格式化的金额与币种
+    amount: String,
+}
+
+impl Deposit {
+    fn confirmations(&self, head: u64) -> u64 {
+        (head + 1).saturating_sub(self.block)
+    }
+}
+
+/// 待确认入账列表：发现时打印，达到深度时打印最终确认
+struct PendingDeposits {
+    depth: u64,
+    /// Tron：head 为已核对的固化区块号，最终确认以固化为准
+    solidified: bool,
+    items: Vec<Deposit>,
+}
+
+impl PendingDeposits {
+    fn add(&mut self, d: Deposit) {
+        println!(
+            "📥 [区块 {}] 入账 {} → {}（来自 {}）\n   交易: {}",
+            d.block, d.amount, d.to, d.from, d.tx_hash
+        );
+        self.items.push(d);
+    }
+
+    /// 取出已达到确认深度的入账
+    fn take_final(&mut self, head: u64) -> Vec<Deposit> {
+        let (done, rest): (Vec<_>, Vec<_>) =
+            self.items.drain(..).partition(|d| d.confirmations(head) >= self.depth);
+        self.items = rest;
+        done
+    }
+
+    fn report_final(&self, d: &Deposit, head: u64) {
+        let finality = if self.solidified {
+            "已固化".to_string()
+        } else {
+            format!("{} 个确认", d.confirmations(head))
+        };
+        println!("✅ [最终] 入账 {} → {}（区块 {}，{}）\n   交易: {}", d.amount, d.to, d.block, finality, d.tx_hash);
+    }
+}
+
+fn print_header(chain: &str, addresses: &[String], settings: &WatchSettings, finality: &str, start: u64) {
+    println!("=== 入账监控（{}）===", chain);
+    for a in addresses {
+        println!("地址: {}", a);
+    }
+    println!("最终确认: {}，起始区块: {}，轮询: {}ms", finality, start, settings.poll.as_millis());
+    println!("（Ctrl+C 退出）\n");
+}
+
+/// EVM：逐块扫描原生转账（交易 to 为监控地址，不含合约内部转账）与 ERC20 Transfer 日志
+async fn watch_evm(addresses: &[String], settings: &WatchSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let network = config::current_evm_network()?;
+    let rpc_url = config::evm_rpc_url(&network).await;
+    let rpc = EvmRpc::new(rpc_url.clone());
+    let sdk = EvmSdk::new(rpc_url.clone());
+
+    let watched: HashSet<String> = addresses.iter().map(|a| a.to_lowercase()).collect();
+    // Transfer 的 topic2（接收方）按监控地址过滤，多个地址为 OR
+    let to_topics: Vec<String> = watched
+        .iter()
+        .map(|a| format!("0x{:0>64}", abi::strip_0x(a)))
+        .collect();
+
+    let mut next = match settings.start_block {
+        Some(b) => b,
+        None => rpc.block_number().await?,
+    };
+    print_header(network.name(), addresses, settings, &format!("{} 个确认", settings.depth), next);
+    println!("RPC: {}\n", rpc_url);
+
+    let mut pending = PendingDeposits { depth: settings.depth, solidified: false, items: Vec::new() };
+    let mut metas: HashMap<String, TokenMeta> = HashMap::new();
+    // 已扫描区块号 → 区块哈希（仅保留确认深度内），用于发现没有入账的区块被重组
+    let mut scanned: BTreeMap<u64, String> = BTreeMap::new();
+    loop {
+        let head = match rpc.block_number().await {
+            Ok(h) => h,
+            Err(e) => {
+                println!("⚠ 查询最新区块失败，稍后重试: {}", e);
+                tokio::time::sleep(settings.poll).await;
+                continue;
+            }
+        };
+        while next <= head {
+            match scan_evm_block(&rpc, &sdk, next, &watched, &to_topics, &mut metas).await {
+                Ok(block) => {
+                    let recorded_parent = next.checked_sub(1).and_then(|p| scanned.get(&p));
+                    if recorded_parent.is_some_and(|h| *h != block.parent_hash) {
+                        let fork = find_fork_point(&rpc, &scanned, next - 1).await;
+                        if fork == next {
+                            // 记录的父区块仍在规范链上：链头正在切换，下一轮重新获取该区块
+                            break;
+                        }
+                        println!("⚠ 区块 {} 起已被重组，撤销其后的待确认入账，从该区块重新扫描", fork);
+                        scanned.split_off(&fork);
+                        pending.items.retain(|p| p.block < fork);
+                        next = fork;
+                        continue;
+                    }
+                    scanned.insert(next, block.hash);
+                    block.deposits.into_iter().for_each(|d| pending.add(d));
+                }
+                Err(e) => {
+                    println!("⚠ 扫描区块 {} 失败，稍后重试: {}", next, e);
+                    break;
+                }
+            }
+            next += 1;
+        }
+        scanned = scanned.split_off(&head.saturating_sub(settings.depth));
+
+        for d in pending.take_final(head) {
+            // 最终报告前复核区块哈希：被重组的区块需回退重扫
+            let canonical = rpc
+                .get_block_by_number(d.block, false)
+                .await
+                .ok()
+                .and_then(|b| b.get("hash").and_then(|h| h.as_str()).map(|h| h.to_lowercase()));
+            match canonical {
+                Some(h) if h == d.block_hash => pending.report_final(&d, head),
+                Some(_) => {
+                    println!("⚠ 区块 {} 已被重组，撤销入账 {}，从该区块重新扫描", d.block, d.tx_hash);
+                    pending.items.retain(|p| p.block < d.block);
+                    scanned.split_off(&d.block);
+                    next = next.min(d.block);
+                }
+                None => pending.items.push(d),
+            }
+        }
+        tokio::time::sleep(settings.poll).await;
+    }
+}
+
+/// 从 tip 向下逐块比对记录的哈希与当前规范链，返回第一个被替换的区块号（分叉点）
+///
+/// 记录范围内都不一致（重组深于确认深度）时返回记录中最早的区块；查询失败按不一致处理，多重扫不会漏扫。
+async fn find_fork_point(rpc: &EvmRpc, scanned: &BTreeMap<u64, String>, tip: u64) -> u64 {
+    let mut fork = tip;
+    for (&number, recorded) in scanned.range(..=tip).rev() {
+        let canonical = rpc
+            .get_block_by_number(number, false)
+            .await
+            .ok()
+            .and_then(|b| b.get("hash").and_then(|h| h.as_str()).map(|h| h.to_lowercase()));
+        if canonical.as_deref() == Some(recorded.as_str()) {
+            return number + 1;
+        }
+        fork = number;
+    }
+    fork
+}
+
+/// 单个区块的扫描结果
+struct ScannedBlock {
+    hash: String,
+    parent_hash: String,
+    deposits: Vec<Deposit>,
+}
+
+async fn scan_evm_block(
+    rpc: &EvmRpc,
+    sdk: &EvmSdk,
+    number: u64,
+    watched: &HashSet<String>,
+    to_topics: &[String],
+    metas: &mut HashMap<String, TokenMeta>,
+) -> Result<ScannedBlock, Box<dyn std::error::Error>> {
+    let block = rpc.get_block_by_number(number, true).await?;
+    let block_hash = match block.get("hash").and_then(|h| h.as_str()) {
+        Some(h) => h.to_lowercase(),
+        None => return Err(format!("区块 {} 尚不可用", number).into()),
+    };
+    let parent_hash = block.get("parentHash").and_then(|h| h.as_str()).unwrap_or("").to_lowercase();
+    let mut found = Vec::new();
+
+    // 原生 ETH：交易 to 为监控地址且金额大于 0，且回执状态成功
+    let txs = block.get("transactions").and_then(|t| t.as_array()).cloned().unwrap_or_default();
+    for tx in txs {
+        let to = tx.get("to").and_then(|t| t.as_str()).unwrap_or("").to_lowercase();
+        let value = tx.get("value").and_then(abi::parse_quantity).unwrap_or(0);
+        if value == 0 || !watched.contains(&to) {
+            continue;
+        }
+        let tx_hash = tx.get("hash").and_then(|h| h.as_str()).unwrap_or("").to_string();
+        let receipt = rpc.get_transaction_receipt(&tx_hash).await?;
+        if receipt.get("status").and_then(abi::parse_quantity) != Some(1) {
+            continue;
+        }
+        found.push(Deposit {
+            block: number,
+            block_hash: block_hash.clone(),
+            tx_hash,
+            from: tx.get("from").and_then(|f| f.as_str()).unwrap_or("?").to_string(),
+            to,
+            amount: format!("{} ETH", abi::format_units(value, 18)),
+        });
+    }
+
+    // ERC20：按区块哈希查询 Transfer 日志（接收方为监控地址）
+    let filter = json!({
+        "blockHash": block_hash,
+        "topics": [format!("0x{}", events::TRANSFER_TOPIC), Value::Null, to_topics],
+    });
+    for log in rpc.get_logs(&filter).await? {
+        let e = match events::decode_evm_log(&log) {
+            Some(e) if e.kind == TokenEventKind::Transfer && watched.contains(&e.to) => e,
+            _ => continue,
+        };
+        if !metas.contains_key(&e.token) {
+            let meta = events::evm_token_meta(sdk, &e.to, &e.token).await;
+            metas.insert(e.token.clone(), meta);
+        }
+        found.push(Deposit {
+            block: number,
+            block_hash: block_hash.clone(),
+            tx_hash: log.get("transactionHash").and_then(|h| h.as_str()).unwrap_or("").to_string(),
+            from: e.from.clone(),
+            to: e.to.clone(),
+            amount: format!("{}（合约 {}）", metas[&e.token].format_amount(e.amount), e.token),
+        });
+    }
+    Ok(ScannedBlock { hash: block_hash, parent_hash, deposits: found })
+}
+
+/// Tron：逐块扫描 TRX（TransferContract）、TRC10（TransferAssetContract）与 TRC20 Transfer 日志
+async fn watch_tron(addresses: &[String], settings: &WatchSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let network = config::current_tron_network();
+    let api = TronApi::new(config::tron_api_url(network));
+    let sdk = TronSdk::new(network);
+    let watched: HashSet<String> = addresses.iter().cloned().collect();
+
+    let mut next = match settings.start_block {
+        Some(b) => b,
+        None => api.now_block_number().await? as u64,
+    };
+    print_header(network.name(), addresses, settings, "所在区块已固化", next);
+
+    let mut pending = PendingDeposits { depth: 1, solidified: true, items: Vec::new() };
+    let mut metas: HashMap<String, TokenMeta> = HashMap::new();
+    let mut assets: HashMap<String, Option<Trc10Asset>> = HashMap::new();
+    // 已扫描、尚未核对固化的区块号 → blockID
+    let mut scanned: BTreeMap<u64, String> = BTreeMap::new();
+    // 已与固化节点核对一致的最高区块号；入账所在区块不超过该高度即为最终
+    let mut verified = next.saturating_sub(1);
+    loop {
+        let (head, solidified) = match (api.now_block_number().await, api.solidified_block_number().await) {
+            (Ok(h), Ok(s)) => (h.max(0) as u64, s.max(0) as u64),
+            (Err(e), _) | (_, Err(e)) => {
+                println!("⚠ 查询最新区块失败，稍后重试: {}", e);
+                tokio::time::sleep(settings.poll).await;
+                continue;
+            }
+        };
+        while next <= head {
+            match scan_tron_block(&api, &sdk, next, &watched, &mut metas, &mut assets).await {
+                Ok((block_id, found)) => {
+                    scanned.insert(next, block_id);
+                    found.into_iter().for_each(|d| pending.add(d));
+                }
+                Err(e) => {
+                    println!("⚠ 扫描区块 {} 失败，稍后重试: {}", next, e);
+                    break;
+                }
+            }
+            next += 1;
+        }
+
+        // 已固化高度内的已扫描区块逐个与固化节点比对 blockID：不一致说明扫描到的是被丢弃的分叉区块，从该区块重新扫描
+        while let Some((&number, block_id)) = scanned.first_key_value().filter(|(n, _)| **n <= solidified) {
+            match api.solidified_block_by_num(number as i64).await {
+                Ok(block) if block.get("blockID").and_then(|h| h.as_str()) == Some(block_id.as_str()) => {
+                    scanned.remove(&number);
+                    verified = number;
+                }
+                Ok(_) => {
+                    println!("⚠ 区块 {} 已被分叉丢弃（固化区块不同），撤销其后的待确认入账，从该区块重新扫描", number);
+                    scanned.clear();
+                    pending.items.retain(|p| p.block < number);
+                    next = number;
+                    break;
+                }
+                Err(e) => {
+                    println!("⚠ 查询固化区块 {} 失败，稍后重试: {}", number, e);
+                    break;
+                }
+            }
+        }
+        for d in pending.take_final(verified) {
+            pending.report_final(&d, verified);
+        }
+        tokio::time::sleep(settings.poll).await;
+    }
+}
+
+async fn scan_tron_block(
+    api: &TronApi,
+    sdk: &TronSdk,
+    number: u64,
+    watched: &HashSet<String>,
+    metas: &mut HashMap<String, TokenMeta>,
+    assets: &mut HashMap<String, Option<Trc10Asset>>,
+) -> Result<(String, Vec<Deposit>), Box<dyn std::error::Error>> {
+    let block = api.get_block_by_num(number as i64).await?;
+    let block_hash = match block.get("blockID").and_then(|h| h.as_str()) {
+        Some(h) => h.to_string(),
+        None => return Err(format!("区块 {} 尚不可用", number).into()),
+    };
+    let mut found = Vec::new();
+
+    // TRX / TRC10：合约参数中的 to_address 为监控地址且执行成功
+    let txs = block.get("transactions").and_then(|t| t.as_array()).cloned().unwrap_or_default();
+    for tx in txs {
+        let ret = tx.pointer("/ret/0/contractRet").and_then(|r| r.as_str()).unwrap_or("SUCCESS");
+        let contract = tx.pointer("/raw_data/contract/0").cloned().unwrap_or(Value::Null);
+        let p = contract.pointer("/parameter/value").cloned().unwrap_or(Value::Null);
+        let to = p.get("to_address").and_then(|t| t.as_str()).unwrap_or("");
+        if ret != "SUCCESS" || !watched.contains(to) {
+            continue;
+        }
+        let amount = p.get("amount").and_then(|a| a.as_i64()).unwrap_or(0);
+        let amount = match contract.get("type").and_then(|t| t.as_str()) {
+            Some("TransferContract") => format_trx(amount),
+            Some("TransferAssetContract") => {
+                let id = p.get("asset_name").and_then(|a| a.as_str()).unwrap_or("").to_string();
+                if !assets.contains_key(&id) {
+                    assets.insert(id.clone(), Trc10Asset::fetch(api, &id).await.ok().flatten());
+                }
+                match &assets[&id] {
+                    Some(asset) => format!("{}（TRC10 {}）", asset.format_amount(amount), id),
+                    None => format!("{} 最小单位（TRC10 {}）", amount, id),
+                }
+            }
+            _ => continue,
+        };
+        found.push(Deposit {
+            block: number,
+            block_hash: block_hash.clone(),
+            tx_hash: tx.get("txID").and_then(|h| h.as_str()).unwrap_or("").to_string(),
+            from: p.get("owner_address").and_then(|o| o.as_str()).unwrap_or("?").to_string(),
+            to: to.to_string(),
+            amount,
+        });
+    }
+
+    // TRC20：区块内交易执行信息中的 Transfer 日志（回滚交易无日志）
+    for info in api.get_transaction_info_by_block_num(number as i64).await? {
+        let tx_hash = info.get("id").and_then(|h| h.as_str()).unwrap_or("").to_string();
+        for e in events::decode_tron_logs(&info) {
+            if e.kind != TokenEventKind::Transfer || !watched.contains(&e.to) {
+                continue;
+            }
+            if !metas.contains_key(&e.token) {
+                let meta = events::tron_token_meta(sdk, &e.to, &e.token).await;
+                metas.insert(e.token.clone(), meta);
+            }
+            found.push(Deposit {
+                block: number,
+                block_hash: block_hash.clone(),
+                tx_hash: tx_hash.clone(),
+                from: e.from.clone(),
+                to: e.to.clone(),
+                amount: format!("{}（合约 {}）", metas[&e.token].format_amount(e.amount), e.token),
+            });
+        }
+    }
+    Ok((block_hash, found))
+}